@@ -0,0 +1,309 @@
+//! Euler tour trees: every tree of a forest is stored as its Euler tour in a treap, so that
+//! trees can be linked, cut and rerooted in expected logarithmic time.
+//!
+//! A tour contains a node for every vertex, and two arcs for every edge, one in each direction.
+//! Linking two trees concatenates their tours around the new arcs, and cutting an edge splits
+//! the tour at both of its arcs.
+
+const NIL: usize = usize::MAX;
+
+/// What a node of a tour stands for.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Item {
+    Vertex(usize),
+    /// The edge `(u, v)`, traversed from `u` to `v`.
+    Arc(usize, usize),
+}
+
+impl Item {
+    fn is_vertex(self) -> bool {
+        matches!(self, Item::Vertex(_))
+    }
+}
+
+#[derive(Debug, Clone)]
+struct Node {
+    left: usize,
+    right: usize,
+    parent: usize,
+    priority: u64,
+    item: Item,
+    /// A mark that can be searched for in a tree, see [`EulerTourForest::flagged`].
+    flag: bool,
+    /// The number of vertex nodes in the subtree.
+    vertices: usize,
+    /// Whether the subtree contains a flagged vertex node.
+    flagged_vertex: bool,
+    /// Whether the subtree contains a flagged arc.
+    flagged_arc: bool,
+}
+
+/// The nodes of any number of Euler tour trees. Nodes are identified by their index.
+#[derive(Debug, Clone)]
+pub(crate) struct EulerTourForest {
+    nodes: Vec<Node>,
+    /// Indices of removed nodes, which are reused.
+    free: Vec<usize>,
+    seed: u64,
+}
+
+impl EulerTourForest {
+    pub(crate) fn new() -> Self {
+        Self {
+            nodes: Vec::new(),
+            free: Vec::new(),
+            seed: 0x2545_f491_4f6c_dd1d,
+        }
+    }
+
+    fn next_priority(&mut self) -> u64 {
+        // xorshift64
+        self.seed ^= self.seed << 13;
+        self.seed ^= self.seed >> 7;
+        self.seed ^= self.seed << 17;
+        self.seed
+    }
+
+    fn alloc(&mut self, item: Item) -> usize {
+        let node = Node {
+            left: NIL,
+            right: NIL,
+            parent: NIL,
+            priority: self.next_priority(),
+            item,
+            flag: false,
+            vertices: usize::from(item.is_vertex()),
+            flagged_vertex: false,
+            flagged_arc: false,
+        };
+        match self.free.pop() {
+            Some(index) => {
+                self.nodes[index] = node;
+                index
+            }
+            None => {
+                self.nodes.push(node);
+                self.nodes.len() - 1
+            }
+        }
+    }
+
+    /// Adds a vertex in a tree on its own.
+    pub(crate) fn add_vertex(&mut self, vertex: usize) -> usize {
+        self.alloc(Item::Vertex(vertex))
+    }
+
+    pub(crate) fn item(&self, node: usize) -> Item {
+        self.nodes[node].item
+    }
+
+    /// The root of the treap containing a node, which identifies its tree.
+    pub(crate) fn root(&self, mut node: usize) -> usize {
+        while self.nodes[node].parent != NIL {
+            node = self.nodes[node].parent;
+        }
+        node
+    }
+
+    pub(crate) fn same_tree(&self, node1: usize, node2: usize) -> bool {
+        self.root(node1) == self.root(node2)
+    }
+
+    /// The number of vertices in the tree containing a node.
+    pub(crate) fn tree_size(&self, node: usize) -> usize {
+        self.nodes[self.root(node)].vertices
+    }
+
+    pub(crate) fn set_flag(&mut self, node: usize, flag: bool) {
+        self.nodes[node].flag = flag;
+        let mut current = node;
+        while current != NIL {
+            self.update(current);
+            current = self.nodes[current].parent;
+        }
+    }
+
+    /// All flagged vertex nodes, or all flagged arcs, in the tree containing a node.
+    pub(crate) fn flagged(&self, node: usize, vertices: bool) -> Vec<usize> {
+        let mut found = Vec::new();
+        let mut stack = vec![self.root(node)];
+        while let Some(current) = stack.pop() {
+            if current == NIL {
+                continue;
+            }
+            let node = &self.nodes[current];
+            let below = if vertices {
+                node.flagged_vertex
+            } else {
+                node.flagged_arc
+            };
+            if !below {
+                continue;
+            }
+            if node.flag && node.item.is_vertex() == vertices {
+                found.push(current);
+            }
+            stack.extend([node.left, node.right]);
+        }
+        found
+    }
+
+    /// Joins the trees of two vertex nodes with the edge `(u, v)` between them. Returns the
+    /// arcs of the edge, from `u` to `v` and back.
+    pub(crate) fn link(
+        &mut self,
+        vertex1: usize,
+        vertex2: usize,
+        (u, v): (usize, usize),
+    ) -> (usize, usize) {
+        debug_assert!(!self.same_tree(vertex1, vertex2));
+        let forward = self.alloc(Item::Arc(u, v));
+        let backward = self.alloc(Item::Arc(v, u));
+        let tour1 = self.reroot(vertex1);
+        let tour2 = self.reroot(vertex2);
+        let tour = self.merge(tour1, forward);
+        let tour = self.merge(tour, tour2);
+        let root = self.merge(tour, backward);
+        self.nodes[root].parent = NIL;
+        (forward, backward)
+    }
+
+    /// Removes the edge with the given arcs, splitting its tree in two. The arcs are freed.
+    pub(crate) fn cut(&mut self, forward: usize, backward: usize) {
+        let (before, _) = self.split_before(forward);
+        let (_, after) = self.split_after(forward);
+
+        if after != NIL && self.same_tree(backward, after) {
+            // before, forward, inner, backward, outer
+            let (inner, _) = self.split_before(backward);
+            let (_, outer) = self.split_after(backward);
+            let root = self.merge(before, outer);
+            self.detach(root);
+            self.detach(inner);
+        } else {
+            // outer, backward, inner, forward, after
+            let (outer, _) = self.split_before(backward);
+            let (_, inner) = self.split_after(backward);
+            let root = self.merge(outer, after);
+            self.detach(root);
+            self.detach(inner);
+        }
+
+        self.free.extend([forward, backward]);
+    }
+
+    /// Makes a vertex the start of the tour of its tree. Returns the new root of the treap.
+    fn reroot(&mut self, vertex: usize) -> usize {
+        let (before, from_vertex) = self.split_before(vertex);
+        let root = self.merge(from_vertex, before);
+        self.detach(root);
+        root
+    }
+
+    fn detach(&mut self, node: usize) {
+        if node != NIL {
+            self.nodes[node].parent = NIL;
+        }
+    }
+
+    fn update(&mut self, index: usize) {
+        let (left, right) = (self.nodes[index].left, self.nodes[index].right);
+        let node = &self.nodes[index];
+        let mut vertices = usize::from(node.item.is_vertex());
+        let mut flagged_vertex = node.flag && node.item.is_vertex();
+        let mut flagged_arc = node.flag && !node.item.is_vertex();
+        for child in [left, right] {
+            if child != NIL {
+                let child = &self.nodes[child];
+                vertices += child.vertices;
+                flagged_vertex |= child.flagged_vertex;
+                flagged_arc |= child.flagged_arc;
+            }
+        }
+        let node = &mut self.nodes[index];
+        node.vertices = vertices;
+        node.flagged_vertex = flagged_vertex;
+        node.flagged_arc = flagged_arc;
+    }
+
+    fn set_left(&mut self, node: usize, left: usize) {
+        self.nodes[node].left = left;
+        if left != NIL {
+            self.nodes[left].parent = node;
+        }
+    }
+
+    fn set_right(&mut self, node: usize, right: usize) {
+        self.nodes[node].right = right;
+        if right != NIL {
+            self.nodes[right].parent = node;
+        }
+    }
+
+    /// Concatenates two tours. Returns the root of the result, whose parent is not reset.
+    fn merge(&mut self, left: usize, right: usize) -> usize {
+        if left == NIL {
+            return right;
+        }
+        if right == NIL {
+            return left;
+        }
+        if self.nodes[left].priority > self.nodes[right].priority {
+            let merged = self.merge(self.nodes[left].right, right);
+            self.set_right(left, merged);
+            self.update(left);
+            left
+        } else {
+            let merged = self.merge(left, self.nodes[right].left);
+            self.set_left(right, merged);
+            self.update(right);
+            right
+        }
+    }
+
+    /// Splits the tour containing `node` into the part before it, and the part starting
+    /// with it. Both parts are detached.
+    fn split_before(&mut self, node: usize) -> (usize, usize) {
+        let left = self.nodes[node].left;
+        self.detach(left);
+        self.nodes[node].left = NIL;
+        self.update(node);
+        self.split_upwards(node, left, node)
+    }
+
+    /// Splits the tour containing `node` into the part ending with it, and the part after
+    /// it. Both parts are detached.
+    fn split_after(&mut self, node: usize) -> (usize, usize) {
+        let right = self.nodes[node].right;
+        self.detach(right);
+        self.nodes[node].right = NIL;
+        self.update(node);
+        self.split_upwards(node, node, right)
+    }
+
+    /// Walks up from `node`, whose subtree was already split into `left` and `right`,
+    /// adding the ancestors to either side.
+    fn split_upwards(&mut self, node: usize, mut left: usize, mut right: usize) -> (usize, usize) {
+        let mut current = node;
+        let mut parent = self.nodes[node].parent;
+        self.nodes[node].parent = NIL;
+        while parent != NIL {
+            let grandparent = self.nodes[parent].parent;
+            if self.nodes[parent].right == current {
+                self.set_right(parent, left);
+                self.update(parent);
+                left = parent;
+            } else {
+                self.set_left(parent, right);
+                self.update(parent);
+                right = parent;
+            }
+            self.nodes[parent].parent = NIL;
+            current = parent;
+            parent = grandparent;
+        }
+        self.detach(left);
+        self.detach(right);
+        (left, right)
+    }
+}
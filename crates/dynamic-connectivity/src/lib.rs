@@ -0,0 +1,266 @@
+//! Fully dynamic connectivity: a graph where edges can be both inserted and deleted, while
+//! answering whether two vertices are connected.
+//!
+//! A union find can only merge classes, so it can't handle deletions. This crate implements
+//! the algorithm of Holm, de Lichtenberg and Thorup, which takes amortized `O(log² n)` time
+//! per insertion and deletion, and `O(log n)` time per query.
+//!
+//! Every edge has a level, which only increases. The edges of level at least `i` form a
+//! forest `F_i` of spanning trees, where every tree of `F_i` has at most `n / 2^i` vertices.
+//! When a tree edge is deleted, a replacement edge is searched for from the highest level
+//! down, in the smaller of the two trees. Edges that are looked at without being a
+//! replacement move up a level, which pays for looking at them.
+
+mod ett;
+#[cfg(test)]
+mod tests;
+
+use ett::{EulerTourForest, Item};
+use std::collections::{HashMap, HashSet};
+
+/// An edge of the graph, stored under its endpoints in increasing order, see [`key`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Edge {
+    level: usize,
+    /// Whether the edge is in the spanning forest.
+    tree: bool,
+}
+
+/// The forest `F_i` of one level, and the non-tree edges of that level.
+#[derive(Debug, Clone)]
+struct Level {
+    /// The vertex node of every vertex in the Euler tour trees. Vertex nodes are flagged when
+    /// the vertex has non-tree edges of this level.
+    vertices: Vec<usize>,
+    /// The arcs of every tree edge in the forest, in both directions. The arc from the lowest
+    /// to the highest endpoint is flagged when the edge has this level.
+    arcs: HashMap<(usize, usize), usize>,
+    /// The other endpoints of the non-tree edges of this level, for every vertex.
+    non_tree: Vec<HashSet<usize>>,
+}
+
+/// A graph over the vertices `0..len`, where edges can be inserted and deleted.
+/// Parallel edges are not supported: inserting an edge that is already present does nothing.
+#[derive(Debug, Clone)]
+pub struct DynamicConnectivity {
+    len: usize,
+    forests: EulerTourForest,
+    /// Levels are added when an edge first reaches them.
+    levels: Vec<Level>,
+    edges: HashMap<(usize, usize), Edge>,
+    components: usize,
+}
+
+fn key(u: usize, v: usize) -> (usize, usize) {
+    (u.min(v), u.max(v))
+}
+
+impl DynamicConnectivity {
+    /// Creates a graph with the vertices `0..len` and no edges.
+    pub fn new(len: usize) -> Self {
+        let mut graph = Self {
+            len,
+            forests: EulerTourForest::new(),
+            levels: Vec::new(),
+            edges: HashMap::new(),
+            components: len,
+        };
+        graph.ensure_level(0);
+        graph
+    }
+
+    /// The number of vertices.
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// The number of edges.
+    pub fn num_edges(&self) -> usize {
+        self.edges.len()
+    }
+
+    /// The number of connected components.
+    pub fn num_components(&self) -> usize {
+        self.components
+    }
+
+    pub fn contains_edge(&self, u: usize, v: usize) -> bool {
+        self.edges.contains_key(&key(u, v))
+    }
+
+    /// Returns whether there is a path between two vertices.
+    ///
+    /// # Panics
+    ///
+    /// Panics if a vertex is not in the graph.
+    pub fn connected(&self, u: usize, v: usize) -> bool {
+        let vertices = &self.levels[0].vertices;
+        self.forests.same_tree(vertices[u], vertices[v])
+    }
+
+    /// Inserts an edge between two vertices. Returns false if the edge was already present.
+    ///
+    /// # Panics
+    ///
+    /// Panics if a vertex is not in the graph.
+    pub fn insert(&mut self, u: usize, v: usize) -> bool {
+        assert!(u < self.len && v < self.len, "vertex is not in the graph");
+        if self.contains_edge(u, v) {
+            return false;
+        }
+
+        let tree = !self.connected(u, v);
+        self.edges.insert(key(u, v), Edge { level: 0, tree });
+        if tree {
+            self.link(0, u, v);
+            self.components -= 1;
+        } else {
+            self.add_non_tree(0, u, v);
+        }
+        true
+    }
+
+    /// Deletes the edge between two vertices. Returns false if there was no such edge.
+    pub fn delete(&mut self, u: usize, v: usize) -> bool {
+        let Some(Edge { level, tree }) = self.edges.remove(&key(u, v)) else {
+            return false;
+        };
+        if !tree {
+            self.remove_non_tree(level, u, v);
+            return true;
+        }
+
+        for i in 0..=level {
+            self.cut(i, u, v);
+        }
+        for i in (0..=level).rev() {
+            if let Some((x, y)) = self.replace(i, u, v) {
+                self.edges.insert(
+                    key(x, y),
+                    Edge {
+                        level: i,
+                        tree: true,
+                    },
+                );
+                for j in 0..=i {
+                    self.link(j, x, y);
+                }
+                return true;
+            }
+        }
+        self.components += 1;
+        true
+    }
+
+    fn ensure_level(&mut self, level: usize) {
+        while self.levels.len() <= level {
+            let vertices = (0..self.len)
+                .map(|vertex| self.forests.add_vertex(vertex))
+                .collect();
+            self.levels.push(Level {
+                vertices,
+                arcs: HashMap::new(),
+                non_tree: vec![HashSet::new(); self.len],
+            });
+        }
+    }
+
+    /// Adds a tree edge to `F_i`, and to no other forest. If `i` is the level of the edge,
+    /// it is flagged.
+    fn link(&mut self, i: usize, u: usize, v: usize) {
+        let level = &mut self.levels[i];
+        let (forward, backward) = self
+            .forests
+            .link(level.vertices[u], level.vertices[v], (u, v));
+        level.arcs.insert((u, v), forward);
+        level.arcs.insert((v, u), backward);
+        if self.edges[&key(u, v)].level == i {
+            let lowest = level.arcs[&key(u, v)];
+            self.forests.set_flag(lowest, true);
+        }
+    }
+
+    fn cut(&mut self, i: usize, u: usize, v: usize) {
+        let level = &mut self.levels[i];
+        let forward = level
+            .arcs
+            .remove(&(u, v))
+            .expect("tree edge is in the forest");
+        let backward = level
+            .arcs
+            .remove(&(v, u))
+            .expect("tree edge is in the forest");
+        self.forests.cut(forward, backward);
+    }
+
+    fn add_non_tree(&mut self, i: usize, u: usize, v: usize) {
+        let level = &mut self.levels[i];
+        for (x, y) in [(u, v), (v, u)] {
+            level.non_tree[x].insert(y);
+            self.forests.set_flag(level.vertices[x], true);
+        }
+    }
+
+    fn remove_non_tree(&mut self, i: usize, u: usize, v: usize) {
+        let level = &mut self.levels[i];
+        for (x, y) in [(u, v), (v, u)] {
+            level.non_tree[x].remove(&y);
+            let flag = !level.non_tree[x].is_empty();
+            self.forests.set_flag(level.vertices[x], flag);
+        }
+    }
+
+    /// Searches the non-tree edges of level `i` for an edge reconnecting the trees of `u`
+    /// and `v` in `F_i`, after the tree edge between them was cut. Edges of level `i` in the
+    /// smaller tree move up a level. Returns the replacement, which is removed from the
+    /// non-tree edges.
+    fn replace(&mut self, i: usize, u: usize, v: usize) -> Option<(usize, usize)> {
+        let vertices = &self.levels[i].vertices;
+        let (node_u, node_v) = (vertices[u], vertices[v]);
+        let small = if self.forests.tree_size(node_u) <= self.forests.tree_size(node_v) {
+            node_u
+        } else {
+            node_v
+        };
+
+        // The smaller tree has at most half the vertices allowed at level `i`, so all its
+        // tree edges of level `i` can move up.
+        for arc in self.forests.flagged(small, false) {
+            let Item::Arc(x, y) = self.forests.item(arc) else {
+                unreachable!("only arcs are searched for")
+            };
+            self.forests.set_flag(arc, false);
+            self.ensure_level(i + 1);
+            self.edges
+                .get_mut(&key(x, y))
+                .expect("edge is present")
+                .level = i + 1;
+            self.link(i + 1, x, y);
+        }
+
+        for vertex_node in self.forests.flagged(small, true) {
+            let Item::Vertex(x) = self.forests.item(vertex_node) else {
+                unreachable!("only vertex nodes are searched for")
+            };
+            let neighbours: Vec<usize> = self.levels[i].non_tree[x].iter().copied().collect();
+            for y in neighbours {
+                self.remove_non_tree(i, x, y);
+                let node_y = self.levels[i].vertices[y];
+                if !self.forests.same_tree(small, node_y) {
+                    return Some((x, y));
+                }
+                self.ensure_level(i + 1);
+                self.edges
+                    .get_mut(&key(x, y))
+                    .expect("edge is present")
+                    .level = i + 1;
+                self.add_non_tree(i + 1, x, y);
+            }
+        }
+        None
+    }
+}
@@ -0,0 +1,78 @@
+use crate::DynamicConnectivity;
+use std::collections::HashSet;
+
+/// Whether two vertices are connected, by searching the graph.
+fn reachable(len: usize, edges: &HashSet<(usize, usize)>, u: usize, v: usize) -> bool {
+    let mut seen = vec![false; len];
+    let mut stack = vec![u];
+    seen[u] = true;
+    while let Some(x) = stack.pop() {
+        for &(a, b) in edges {
+            for (from, to) in [(a, b), (b, a)] {
+                if from == x && !seen[to] {
+                    seen[to] = true;
+                    stack.push(to);
+                }
+            }
+        }
+    }
+    seen[v]
+}
+
+#[test]
+pub fn insert_and_delete() {
+    let mut graph = DynamicConnectivity::new(5);
+    assert!(graph.insert(0, 1));
+    assert!(graph.insert(1, 2));
+    assert!(graph.insert(2, 0));
+    assert!(!graph.insert(1, 0));
+    assert!(graph.insert(3, 4));
+    assert_eq!(graph.num_components(), 2);
+    assert!(graph.connected(0, 2));
+    assert!(!graph.connected(0, 3));
+
+    // the cycle keeps the triangle connected
+    assert!(graph.delete(1, 0));
+    assert!(graph.connected(0, 1));
+    assert!(graph.delete(2, 1));
+    assert!(!graph.connected(0, 1));
+    assert!(graph.connected(0, 2));
+    assert!(!graph.delete(2, 1));
+    assert_eq!(graph.num_components(), 3);
+    assert_eq!(graph.num_edges(), 2);
+}
+
+#[test]
+pub fn matches_graph_search() {
+    let len = 24;
+    let mut graph = DynamicConnectivity::new(len);
+    let mut edges = HashSet::new();
+    let mut seed: u64 = 0x9e37_79b9_7f4a_7c15;
+    let mut random = |bound: usize| {
+        seed ^= seed << 13;
+        seed ^= seed >> 7;
+        seed ^= seed << 17;
+        (seed % bound as u64) as usize
+    };
+
+    for step in 0..4000 {
+        let (u, v) = (random(len), random(len));
+        let key = (u.min(v), u.max(v));
+        // insert more than delete at first, so that large components form
+        if random(10) < if step < 1000 { 7 } else { 4 } {
+            assert_eq!(graph.insert(u, v), edges.insert(key));
+        } else {
+            assert_eq!(graph.delete(u, v), edges.remove(&key));
+        }
+
+        let (a, b) = (random(len), random(len));
+        assert_eq!(graph.connected(a, b), reachable(len, &edges, a, b));
+    }
+
+    let components = (0..len)
+        .filter(|&v| (0..v).all(|u| !reachable(len, &edges, u, v)))
+        .count();
+    assert_eq!(graph.num_components(), components);
+    // a tree of level `i` has at most `len / 2^i` vertices
+    assert!(graph.levels.len() <= len.ilog2() as usize + 1);
+}
@@ -0,0 +1,43 @@
+use serde::{Deserialize, Serialize};
+use std::ops::Add;
+
+/// Values of vertices in a [`LinkCutTree`](crate::LinkCutTree), which can be combined along
+/// a path. `combine` must be associative and commutative.
+pub trait Aggregate: Clone {
+    fn combine(&self, other: &Self) -> Self;
+}
+
+/// No values, for forests that are only used for connectivity.
+impl Aggregate for () {
+    fn combine(&self, _: &Self) -> Self {}
+}
+
+/// Aggregates values by adding them.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+pub struct Sum<T>(pub T);
+
+impl<T: Add<Output = T> + Clone> Aggregate for Sum<T> {
+    fn combine(&self, other: &Self) -> Self {
+        Sum(self.0.clone() + other.0.clone())
+    }
+}
+
+/// Aggregates values by taking the largest one.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+pub struct Max<T>(pub T);
+
+impl<T: Ord + Clone> Aggregate for Max<T> {
+    fn combine(&self, other: &Self) -> Self {
+        self.max(other).clone()
+    }
+}
+
+/// Aggregates values by taking the smallest one.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+pub struct Min<T>(pub T);
+
+impl<T: Ord + Clone> Aggregate for Min<T> {
+    fn combine(&self, other: &Self) -> Self {
+        self.min(other).clone()
+    }
+}
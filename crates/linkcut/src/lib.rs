@@ -0,0 +1,299 @@
+//! Link-cut trees, after Sleator and Tarjan: a forest in which edges can be added and removed,
+//! answering which tree a vertex is in, and aggregating the values of the vertices on a path,
+//! in amortized logarithmic time.
+//!
+//! Every tree is split into preferred paths, each stored in a splay tree ordered by depth.
+//! Accessing a vertex makes the path from the root to it preferred, after which that path is a
+//! single splay tree whose root holds the aggregate of the path.
+
+mod aggregate;
+#[cfg(test)]
+mod tests;
+
+pub use aggregate::{Aggregate, Max, Min, Sum};
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+const NIL: usize = usize::MAX;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct Node<V> {
+    /// The children in the splay tree. The left child is closer to the root of the tree.
+    children: [usize; 2],
+    /// The parent in the splay tree, or for the root of a splay tree, the parent of the
+    /// topmost vertex of its path in the represented tree.
+    parent: usize,
+    /// Whether the subtree has to be mirrored, which reverses its path.
+    reversed: bool,
+    value: V,
+    /// The aggregate of the values in the subtree.
+    aggregate: V,
+}
+
+#[derive(Debug, Error, PartialEq, Eq)]
+pub enum LinkError {
+    #[error("the vertices are already in the same tree")]
+    AlreadyConnected,
+}
+
+#[derive(Debug, Error, PartialEq, Eq)]
+pub enum CutError {
+    #[error("there is no edge between the vertices")]
+    NotAnEdge,
+}
+
+/// A forest over the vertices `0..len`, where every vertex has a value of type `V`. Edges are
+/// undirected, so every vertex can be made the root of its tree with
+/// [`evert`](LinkCutTree::evert).
+///
+/// Aggregates must be commutative, since everting a tree reverses paths.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LinkCutTree<V> {
+    nodes: Vec<Node<V>>,
+}
+
+impl<V: Aggregate> Default for LinkCutTree<V> {
+    fn default() -> Self {
+        Self::new([])
+    }
+}
+
+impl<V: Aggregate> LinkCutTree<V> {
+    /// Creates a forest where every vertex is in a tree on its own, with the given values.
+    pub fn new(values: impl IntoIterator<Item = V>) -> Self {
+        let mut tree = Self { nodes: Vec::new() };
+        for value in values {
+            tree.add(value);
+        }
+        tree
+    }
+
+    /// The number of vertices.
+    pub fn len(&self) -> usize {
+        self.nodes.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.nodes.is_empty()
+    }
+
+    /// Adds a vertex in a tree on its own. Returns the new vertex.
+    pub fn add(&mut self, value: V) -> usize {
+        self.nodes.push(Node {
+            children: [NIL; 2],
+            parent: NIL,
+            reversed: false,
+            aggregate: value.clone(),
+            value,
+        });
+        self.nodes.len() - 1
+    }
+
+    /// # Panics
+    ///
+    /// Panics if the vertex is not in the forest.
+    pub fn value(&self, vertex: usize) -> &V {
+        &self.nodes[vertex].value
+    }
+
+    /// # Panics
+    ///
+    /// Panics if the vertex is not in the forest.
+    pub fn set_value(&mut self, vertex: usize, value: V) {
+        self.access(vertex);
+        self.nodes[vertex].value = value;
+        self.pull(vertex);
+    }
+
+    /// The root of the tree containing a vertex.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the vertex is not in the forest.
+    pub fn find_root(&mut self, vertex: usize) -> usize {
+        self.access(vertex);
+        let mut current = vertex;
+        loop {
+            self.push(current);
+            match self.nodes[current].children[0] {
+                NIL => break,
+                left => current = left,
+            }
+        }
+        // keeps finding the same root fast
+        self.splay(current);
+        current
+    }
+
+    /// Returns whether two vertices are in the same tree.
+    ///
+    /// # Panics
+    ///
+    /// Panics if a vertex is not in the forest.
+    pub fn connected(&mut self, vertex1: usize, vertex2: usize) -> bool {
+        self.find_root(vertex1) == self.find_root(vertex2)
+    }
+
+    /// Makes a vertex the root of its tree.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the vertex is not in the forest.
+    pub fn evert(&mut self, vertex: usize) {
+        self.access(vertex);
+        self.nodes[vertex].reversed ^= true;
+    }
+
+    /// Adds an edge between two vertices in different trees. The root of the tree of
+    /// `vertex2` stays the root.
+    ///
+    /// # Panics
+    ///
+    /// Panics if a vertex is not in the forest.
+    pub fn link(&mut self, vertex1: usize, vertex2: usize) -> Result<(), LinkError> {
+        if self.connected(vertex1, vertex2) {
+            return Err(LinkError::AlreadyConnected);
+        }
+        self.evert(vertex1);
+        self.nodes[vertex1].parent = vertex2;
+        Ok(())
+    }
+
+    /// Removes the edge between two vertices. The root of the tree stays the root of the part
+    /// containing it, and the endpoint in the other part becomes the root of that part.
+    ///
+    /// # Panics
+    ///
+    /// Panics if a vertex is not in the forest.
+    pub fn cut(&mut self, vertex1: usize, vertex2: usize) -> Result<(), CutError> {
+        let root = self.find_root(vertex2);
+        self.evert(vertex1);
+        self.access(vertex2);
+        // vertex1 is the root, so an edge means it's directly before vertex2 on the path
+        let [left, _] = self.nodes[vertex2].children;
+        if left == vertex1 {
+            self.push(vertex1);
+        }
+        if left != vertex1 || self.nodes[vertex1].children[1] != NIL {
+            self.evert(root);
+            return Err(CutError::NotAnEdge);
+        }
+        self.nodes[vertex2].children[0] = NIL;
+        self.nodes[vertex1].parent = NIL;
+        self.pull(vertex2);
+        self.evert(root);
+        Ok(())
+    }
+
+    /// The aggregate of the values of all vertices on the path between two vertices,
+    /// including both. Returns `None` if they are in different trees.
+    ///
+    /// # Panics
+    ///
+    /// Panics if a vertex is not in the forest.
+    pub fn path_aggregate(&mut self, vertex1: usize, vertex2: usize) -> Option<V> {
+        let root = self.find_root(vertex1);
+        if self.find_root(vertex2) != root {
+            return None;
+        }
+        self.evert(vertex1);
+        self.access(vertex2);
+        let aggregate = self.nodes[vertex2].aggregate.clone();
+        self.evert(root);
+        Some(aggregate)
+    }
+
+    fn is_splay_root(&self, node: usize) -> bool {
+        let parent = self.nodes[node].parent;
+        parent == NIL || !self.nodes[parent].children.contains(&node)
+    }
+
+    /// Applies a pending reversal to the children of a node.
+    fn push(&mut self, node: usize) {
+        if !self.nodes[node].reversed {
+            return;
+        }
+        self.nodes[node].reversed = false;
+        self.nodes[node].children.swap(0, 1);
+        for child in self.nodes[node].children {
+            if child != NIL {
+                self.nodes[child].reversed ^= true;
+            }
+        }
+    }
+
+    /// Recomputes the aggregate of a node from its children.
+    fn pull(&mut self, node: usize) {
+        let [left, right] = self.nodes[node].children;
+        let mut aggregate = self.nodes[node].value.clone();
+        if left != NIL {
+            aggregate = self.nodes[left].aggregate.combine(&aggregate);
+        }
+        if right != NIL {
+            aggregate = aggregate.combine(&self.nodes[right].aggregate);
+        }
+        self.nodes[node].aggregate = aggregate;
+    }
+
+    fn rotate(&mut self, node: usize) {
+        let parent = self.nodes[node].parent;
+        let grandparent = self.nodes[parent].parent;
+        let side = usize::from(self.nodes[parent].children[1] == node);
+
+        if !self.is_splay_root(parent) {
+            let parent_side = usize::from(self.nodes[grandparent].children[1] == parent);
+            self.nodes[grandparent].children[parent_side] = node;
+        }
+        self.nodes[node].parent = grandparent;
+
+        let moved = self.nodes[node].children[1 - side];
+        self.nodes[parent].children[side] = moved;
+        if moved != NIL {
+            self.nodes[moved].parent = parent;
+        }
+        self.nodes[node].children[1 - side] = parent;
+        self.nodes[parent].parent = node;
+
+        self.pull(parent);
+        self.pull(node);
+    }
+
+    /// Moves a node to the root of its splay tree.
+    fn splay(&mut self, node: usize) {
+        let mut ancestors = vec![node];
+        let mut current = node;
+        while !self.is_splay_root(current) {
+            current = self.nodes[current].parent;
+            ancestors.push(current);
+        }
+        for &ancestor in ancestors.iter().rev() {
+            self.push(ancestor);
+        }
+
+        while !self.is_splay_root(node) {
+            let parent = self.nodes[node].parent;
+            if !self.is_splay_root(parent) {
+                let grandparent = self.nodes[parent].parent;
+                let zig_zig = (self.nodes[grandparent].children[1] == parent)
+                    == (self.nodes[parent].children[1] == node);
+                self.rotate(if zig_zig { parent } else { node });
+            }
+            self.rotate(node);
+        }
+    }
+
+    /// Makes the path from the root of the tree to a vertex preferred, so that it's a single
+    /// splay tree with the vertex at its root and no vertices below it.
+    fn access(&mut self, vertex: usize) {
+        let mut last = NIL;
+        let mut current = vertex;
+        while current != NIL {
+            self.splay(current);
+            self.nodes[current].children[1] = last;
+            self.pull(current);
+            last = current;
+            current = self.nodes[current].parent;
+        }
+        self.splay(vertex);
+    }
+}
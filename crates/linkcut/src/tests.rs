@@ -0,0 +1,87 @@
+use crate::{CutError, LinkCutTree, LinkError, Max, Sum};
+use std::collections::HashSet;
+
+/// The vertices on the path between two vertices, by searching the forest.
+fn path(len: usize, edges: &HashSet<(usize, usize)>, from: usize, to: usize) -> Option<Vec<usize>> {
+    let mut previous = vec![None; len];
+    previous[from] = Some(from);
+    let mut stack = vec![from];
+    while let Some(x) = stack.pop() {
+        for &(a, b) in edges {
+            for (u, v) in [(a, b), (b, a)] {
+                if u == x && previous[v].is_none() {
+                    previous[v] = Some(x);
+                    stack.push(v);
+                }
+            }
+        }
+    }
+    previous[to]?;
+    let mut path = vec![to];
+    while *path.last().unwrap() != from {
+        path.push(previous[*path.last().unwrap()].unwrap());
+    }
+    Some(path)
+}
+
+#[test]
+pub fn link_cut_and_aggregate() {
+    let mut tree = LinkCutTree::new((0..6).map(Max));
+    tree.link(1, 0).unwrap();
+    tree.link(2, 1).unwrap();
+    tree.link(3, 1).unwrap();
+    tree.link(5, 4).unwrap();
+    assert_eq!(tree.link(0, 3), Err(LinkError::AlreadyConnected));
+
+    assert_eq!(tree.find_root(3), 0);
+    assert_eq!(tree.find_root(5), 4);
+    assert_eq!(tree.path_aggregate(2, 3), Some(Max(3)));
+    assert_eq!(tree.path_aggregate(2, 0), Some(Max(2)));
+    assert_eq!(tree.path_aggregate(2, 5), None);
+    // the root doesn't change by querying
+    assert_eq!(tree.find_root(2), 0);
+
+    assert_eq!(tree.cut(2, 3), Err(CutError::NotAnEdge));
+    tree.cut(1, 0).unwrap();
+    assert_eq!(tree.find_root(0), 0);
+    assert_eq!(tree.find_root(2), 1);
+    assert!(!tree.connected(0, 3));
+
+    tree.set_value(1, Max(10));
+    assert_eq!(tree.path_aggregate(3, 2), Some(Max(10)));
+    tree.evert(3);
+    assert_eq!(tree.find_root(2), 3);
+}
+
+#[test]
+pub fn matches_forest_search() {
+    let len = 30;
+    let mut tree = LinkCutTree::new((0..len as u64).map(|v| Sum(1 << v)));
+    let mut edges = HashSet::new();
+    let mut seed: u64 = 0x9e37_79b9_7f4a_7c15;
+    let mut random = |bound: usize| {
+        seed ^= seed << 13;
+        seed ^= seed >> 7;
+        seed ^= seed << 17;
+        (seed % bound as u64) as usize
+    };
+
+    for _ in 0..3000 {
+        let (u, v) = (random(len), random(len));
+        let connected = path(len, &edges, u, v).is_some();
+        if random(3) < 2 {
+            let linked = tree.link(u, v);
+            assert_eq!(linked.is_ok(), !connected);
+            if linked.is_ok() {
+                edges.insert((u.min(v), u.max(v)));
+            }
+        } else {
+            let cut = tree.cut(u, v);
+            assert_eq!(cut.is_ok(), edges.remove(&(u.min(v), u.max(v))));
+        }
+
+        let (a, b) = (random(len), random(len));
+        let expected = path(len, &edges, a, b).map(|path| Sum(path.iter().map(|v| 1 << v).sum()));
+        assert_eq!(tree.path_aggregate(a, b), expected);
+    }
+}
@@ -0,0 +1,242 @@
+//! Graph algorithms built on the union find.
+
+use crate::dense::VecUnionFind;
+use crate::extra::Values;
+use crate::generic::{UnionFind, UnionStatus};
+use crate::HashUnionFindByRank;
+use std::cmp::Ordering;
+use std::collections::{HashMap, HashSet};
+use std::convert::Infallible;
+use std::hash::Hash;
+
+/// Computes a minimum spanning forest of a weighted graph with Kruskal's algorithm: edges
+/// are taken from light to heavy, skipping edges between vertices that are already connected.
+///
+/// Returns the edges of the forest, ordered by weight. Edges with equal weight keep their
+/// order. Vertices are the endpoints of the edges.
+pub fn kruskal<T, W>(edges: impl IntoIterator<Item = (T, T, W)>) -> Vec<(T, T, W)>
+where
+    T: Hash + Eq + Clone,
+    W: Ord,
+{
+    kruskal_by(edges, W::cmp)
+}
+
+/// Like [`kruskal`], but weights are compared with `compare`, for example
+/// [`f64::total_cmp`] for floating point weights.
+pub fn kruskal_by<T, W>(
+    edges: impl IntoIterator<Item = (T, T, W)>,
+    mut compare: impl FnMut(&W, &W) -> Ordering,
+) -> Vec<(T, T, W)>
+where
+    T: Hash + Eq + Clone,
+{
+    let mut edges: Vec<(T, T, W)> = edges.into_iter().collect();
+    edges.sort_by(|(_, _, a), (_, _, b)| compare(a, b));
+
+    let mut uf = HashUnionFindByRank::empty();
+    let mut forest = Vec::new();
+    for (u, v, weight) in edges {
+        uf.get_or_insert_with(u.clone(), Default::default)
+            .expect("vertex was not present");
+        uf.get_or_insert_with(v.clone(), Default::default)
+            .expect("vertex was not present");
+        let status = uf.union_by_rank(&u, &v).expect("both vertices were added");
+        if status == UnionStatus::PerformedUnion {
+            forest.push((u, v, weight));
+        }
+    }
+    forest
+}
+
+/// Computes a minimum spanning forest of a weighted graph with Borůvka's algorithm. In every
+/// round, the cheapest edge leaving every class is found, after which all of these edges are
+/// added at once. Every round at least halves the number of classes.
+///
+/// The cheapest edge leaving a class is kept as the value of the class, see [`Values`].
+/// Returns the edges of the forest, in the order they were added. Edges of equal weight are
+/// ordered by their position, so the forest is the same as the one found by [`kruskal`].
+pub fn boruvka<T, W>(edges: impl IntoIterator<Item = (T, T, W)>) -> Vec<(T, T, W)>
+where
+    T: Hash + Eq + Clone,
+    W: Ord,
+{
+    boruvka_by(edges, W::cmp)
+}
+
+/// Like [`boruvka`], but weights are compared with `compare`.
+pub fn boruvka_by<T, W>(
+    edges: impl IntoIterator<Item = (T, T, W)>,
+    mut compare: impl FnMut(&W, &W) -> Ordering,
+) -> Vec<(T, T, W)>
+where
+    T: Hash + Eq + Clone,
+{
+    let mut edges: Vec<Option<(T, T, W)>> = edges.into_iter().map(Some).collect();
+    let vertices: Vec<T> = edges
+        .iter()
+        .flatten()
+        .flat_map(|(u, v, _)| [u.clone(), v.clone()])
+        .collect::<HashSet<T>>()
+        .into_iter()
+        .collect();
+    // The value of every class is the index of the cheapest edge leaving it, if any.
+    let mut uf: UnionFind<T, Option<usize>, Values<T, Option<usize>>> =
+        UnionFind::new(vertices).expect("union find of vertices");
+
+    let mut forest = Vec::new();
+    loop {
+        let mut leaving = false;
+        for (index, edge) in edges.iter().enumerate() {
+            let Some((u, v, weight)) = edge else {
+                continue;
+            };
+            let root_u = uf.find_shorten(u).expect("vertex was added");
+            let root_v = uf.find_shorten(v).expect("vertex was added");
+            if root_u == root_v {
+                continue;
+            }
+            leaving = true;
+            for root in [root_u, root_v] {
+                let cheapest = uf.value_mut(&root).expect("vertex was added");
+                // ties are broken by index, so the cheapest edges never form a cycle
+                let cheaper = match *cheapest {
+                    None => true,
+                    Some(other) => {
+                        let (_, _, other_weight) =
+                            edges[other].as_ref().expect("edge is not in the forest");
+                        compare(weight, other_weight).then(index.cmp(&other)) == Ordering::Less
+                    }
+                };
+                if cheaper {
+                    *cheapest = Some(index);
+                }
+            }
+        }
+        if !leaving {
+            break;
+        }
+
+        let roots: Vec<T> = uf.roots().cloned().collect();
+        let mut cheapest = Vec::with_capacity(roots.len());
+        for root in roots {
+            cheapest.extend(uf.value_mut(&root).expect("root is present").take());
+        }
+        for index in cheapest {
+            // both classes of an edge may have chosen it
+            let Some((u, v, _)) = &edges[index] else {
+                continue;
+            };
+            let status = uf
+                .union_values(u, v, |_, _| Ok::<_, Infallible>(None))
+                .expect("both vertices were added");
+            if status == UnionStatus::PerformedUnion {
+                forest.push(edges[index].take().expect("edge is not in the forest"));
+            }
+        }
+    }
+    forest
+}
+
+/// Clusters points with single-linkage clustering: two points are in the same cluster when
+/// they are connected by a chain of points, where every two consecutive points are closer
+/// than `threshold`. Compares all pairs of points, so takes quadratic time.
+///
+/// Returns the clusters as positions in `points`. Clusters are ordered by their first
+/// position, and positions within a cluster are increasing.
+pub fn single_linkage<P, D: PartialOrd>(
+    points: &[P],
+    mut dist: impl FnMut(&P, &P) -> D,
+    threshold: D,
+) -> Vec<Vec<usize>> {
+    let mut uf = VecUnionFind::with_len(points.len());
+    for (i, p) in points.iter().enumerate() {
+        for (j, q) in points.iter().enumerate().skip(i + 1) {
+            // already in the same cluster, so the distance doesn't matter
+            if uf.connected(&i, &j) == Some(true) {
+                continue;
+            }
+            if dist(p, q) < threshold {
+                uf.union_by_rank(&i, &j)
+                    .expect("points are in the union find");
+            }
+        }
+    }
+
+    let mut cluster_of = vec![None; points.len()];
+    let mut clusters: Vec<Vec<usize>> = Vec::new();
+    for i in 0..points.len() {
+        let root = uf.find_shorten(&i).expect("point is in the union find");
+        let cluster = *cluster_of[root].get_or_insert_with(|| {
+            clusters.push(Vec::new());
+            clusters.len() - 1
+        });
+        clusters[cluster].push(i);
+    }
+    clusters
+}
+
+/// Answers a batch of lowest common ancestor queries on a rooted tree with Tarjan's offline
+/// algorithm, in near-linear time. The tree is given by its root and `(parent, child)` edges.
+///
+/// The tree is traversed depth first. Every visited subtree is unioned into the class of its
+/// parent, and the value of the class is the vertex on the current path it hangs from, see
+/// [`Values`]. When a vertex is finished, the lowest common ancestor with every finished vertex
+/// is the value of the class of that vertex.
+///
+/// Returns the answers in the order of the queries, or `None` for queries with a vertex that
+/// is not in the tree. Vertices that can't be reached from `root` are not in the tree.
+///
+/// # Panics
+///
+/// Panics if the edges reachable from `root` don't form a tree, because a vertex has more
+/// than one parent or `root` has a parent.
+pub fn offline_lca<T: Hash + Eq + Clone>(
+    root: T,
+    edges: impl IntoIterator<Item = (T, T)>,
+    queries: &[(T, T)],
+) -> Vec<Option<T>> {
+    let mut children: HashMap<T, Vec<T>> = HashMap::new();
+    for (parent, child) in edges {
+        children.entry(parent).or_default().push(child);
+    }
+    let mut queries_at: HashMap<&T, Vec<(usize, &T)>> = HashMap::new();
+    for (index, (u, v)) in queries.iter().enumerate() {
+        queries_at.entry(u).or_default().push((index, v));
+        queries_at.entry(v).or_default().push((index, u));
+    }
+
+    let mut uf: UnionFind<T, T, Values<T, T>> = UnionFind::empty();
+    let mut finished = HashSet::new();
+    let mut answers = vec![None; queries.len()];
+
+    uf.add_with_extra(root.clone(), root.clone())
+        .expect("union find is empty");
+    // the path from the root, with the number of children visited of every vertex
+    let mut path = vec![(root, 0)];
+    while let Some((vertex, visited)) = path.last_mut() {
+        if let Some(child) = children.get(vertex).and_then(|c| c.get(*visited)) {
+            *visited += 1;
+            uf.add_with_extra(child.clone(), child.clone())
+                .expect("edges form a tree, so every vertex has at most one parent");
+            path.push((child.clone(), 0));
+            continue;
+        }
+
+        let (vertex, _) = path.pop().expect("path is not empty");
+        if let Some(queries) = queries_at.get(&vertex) {
+            for &(index, other) in queries {
+                if finished.contains(other) || *other == vertex {
+                    answers[index] = uf.value(other).cloned();
+                }
+            }
+        }
+        if let Some((parent, _)) = path.last() {
+            let parent = parent.clone();
+            uf.union_values(&parent, &vertex, |_, _| Ok::<_, Infallible>(parent.clone()))
+                .expect("both vertices were added");
+        }
+        finished.insert(vertex);
+    }
+    answers
+}
@@ -0,0 +1,238 @@
+//! Replays a workload against a union find, and reports throughput, latencies and memory use.
+
+use clap::{Parser, ValueEnum};
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::error::Error;
+use std::fs::File;
+use std::io::{BufRead, BufReader};
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::{Duration, Instant};
+use unionfind::generic::UnionFind;
+use unionfind::union::infallible;
+use unionfind::HashUnionFindByRank;
+
+/// Keeps track of the number of allocated bytes, and the maximum since the last reset.
+struct CountingAllocator;
+
+static ALLOCATED: AtomicUsize = AtomicUsize::new(0);
+static PEAK: AtomicUsize = AtomicUsize::new(0);
+
+unsafe impl GlobalAlloc for CountingAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        let ptr = System.alloc(layout);
+        if !ptr.is_null() {
+            let allocated = ALLOCATED.fetch_add(layout.size(), Ordering::Relaxed) + layout.size();
+            PEAK.fetch_max(allocated, Ordering::Relaxed);
+        }
+        ptr
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout);
+        ALLOCATED.fetch_sub(layout.size(), Ordering::Relaxed);
+    }
+}
+
+#[global_allocator]
+static GLOBAL: CountingAllocator = CountingAllocator;
+
+#[derive(Debug, Clone, Copy, ValueEnum)]
+enum Strategy {
+    /// Union by rank.
+    Rank,
+    /// Always make the class of the second element point to the first.
+    First,
+}
+
+#[derive(Parser, Debug)]
+#[command(about)]
+struct Args {
+    /// An operation log to replay. Every line is `add <x>`, `union <x> <y>` or `find <x>`,
+    /// where elements are integers. Elements are added on first use.
+    #[arg(required_unless_present = "synthetic")]
+    log: Option<PathBuf>,
+
+    /// Generate a workload instead, given as `<elements>:<operations>:<fraction of unions>`.
+    #[arg(long, conflicts_with = "log")]
+    synthetic: Option<String>,
+
+    /// The seed for synthetic workloads.
+    #[arg(long, default_value_t = 42)]
+    seed: u64,
+
+    #[arg(short, long, value_enum, default_value_t = Strategy::Rank)]
+    strategy: Strategy,
+
+    /// Limit the number of parent pointers rewritten by path shortening per operation.
+    #[arg(long)]
+    compression_budget: Option<usize>,
+}
+
+#[derive(Debug, Clone, Copy)]
+enum Op {
+    Add(u64),
+    Union(u64, u64),
+    Find(u64),
+}
+
+fn parse_log(path: &PathBuf) -> Result<Vec<Op>, Box<dyn Error>> {
+    let mut ops = Vec::new();
+    for (idx, line) in BufReader::new(File::open(path)?).lines().enumerate() {
+        let line = line?;
+        let words: Vec<&str> = line.split_whitespace().collect();
+        let op = match words.as_slice() {
+            [] => continue,
+            [comment, ..] if comment.starts_with('#') => continue,
+            ["add", x] => Op::Add(x.parse()?),
+            ["union", x, y] => Op::Union(x.parse()?, y.parse()?),
+            ["find", x] => Op::Find(x.parse()?),
+            _ => return Err(format!("line {}: unknown operation `{line}`", idx + 1).into()),
+        };
+        ops.push(op);
+    }
+    Ok(ops)
+}
+
+fn synthetic(spec: &str, seed: u64) -> Result<Vec<Op>, Box<dyn Error>> {
+    let [elements, operations, unions] = spec.split(':').collect::<Vec<_>>()[..] else {
+        return Err("expected `<elements>:<operations>:<fraction of unions>`".into());
+    };
+    let elements: u64 = elements.parse()?;
+    let operations: usize = operations.parse()?;
+    let unions: f64 = unions.parse()?;
+    if elements == 0 {
+        return Err("there must be at least one element".into());
+    }
+
+    // xorshift, so workloads are reproducible without depending on a random number generator
+    let mut seed = seed.max(1);
+    let mut next = move || {
+        seed ^= seed << 13;
+        seed ^= seed >> 7;
+        seed ^= seed << 17;
+        seed
+    };
+
+    let mut ops: Vec<Op> = (0..elements).map(Op::Add).collect();
+    for _ in 0..operations {
+        let is_union = (next() as f64 / u64::MAX as f64) < unions;
+        let x = next() % elements;
+        ops.push(if is_union {
+            Op::Union(x, next() % elements)
+        } else {
+            Op::Find(x)
+        });
+    }
+    Ok(ops)
+}
+
+trait Backend {
+    fn ensure(&mut self, x: u64);
+    fn union(&mut self, x: u64, y: u64);
+    fn find(&mut self, x: u64) -> u64;
+}
+
+impl Backend for HashUnionFindByRank<u64> {
+    fn ensure(&mut self, x: u64) {
+        if self.find_shorten(&x).is_none() {
+            self.add(x).expect("element was not present");
+        }
+    }
+
+    fn union(&mut self, x: u64, y: u64) {
+        self.ensure(x);
+        self.ensure(y);
+        self.union_by_rank(&x, &y).expect("elements were added");
+    }
+
+    fn find(&mut self, x: u64) -> u64 {
+        self.ensure(x);
+        self.find_shorten(&x).expect("element was added")
+    }
+}
+
+impl Backend for UnionFind<u64, ()> {
+    fn ensure(&mut self, x: u64) {
+        if self.find_shorten(&x).is_none() {
+            self.add(x).expect("element was not present");
+        }
+    }
+
+    fn union(&mut self, x: u64, y: u64) {
+        self.ensure(x);
+        self.ensure(y);
+        self.union_by(&x, &y, infallible(|a, _| a))
+            .expect("elements were added");
+    }
+
+    fn find(&mut self, x: u64) -> u64 {
+        self.ensure(x);
+        self.find_shorten(&x).expect("element was added")
+    }
+}
+
+fn percentile(sorted: &[Duration], p: f64) -> Duration {
+    if sorted.is_empty() {
+        return Duration::ZERO;
+    }
+    let idx = ((sorted.len() - 1) as f64 * p).round() as usize;
+    sorted[idx]
+}
+
+fn main() -> Result<(), Box<dyn Error>> {
+    let args = Args::parse();
+    let ops = match (&args.log, &args.synthetic) {
+        (Some(path), _) => parse_log(path)?,
+        (None, Some(spec)) => synthetic(spec, args.seed)?,
+        (None, None) => unreachable!("clap requires a log or a synthetic workload"),
+    };
+
+    let mut backend: Box<dyn Backend> = match args.strategy {
+        Strategy::Rank => {
+            let mut uf = HashUnionFindByRank::empty();
+            uf.set_compression_budget(args.compression_budget);
+            Box::new(uf)
+        }
+        Strategy::First => {
+            let mut uf = UnionFind::<u64, ()>::empty();
+            uf.set_compression_budget(args.compression_budget);
+            Box::new(uf)
+        }
+    };
+
+    let mut latencies = Vec::with_capacity(ops.len());
+    let baseline = ALLOCATED.load(Ordering::Relaxed);
+    PEAK.store(baseline, Ordering::Relaxed);
+
+    let start = Instant::now();
+    for op in &ops {
+        let op_start = Instant::now();
+        match *op {
+            Op::Add(x) => backend.ensure(x),
+            Op::Union(x, y) => backend.union(x, y),
+            Op::Find(x) => {
+                backend.find(x);
+            }
+        }
+        latencies.push(op_start.elapsed());
+    }
+    let elapsed = start.elapsed();
+
+    // the latencies were allocated up front, so this is only the memory used by the union find
+    let peak = PEAK.load(Ordering::Relaxed) - baseline;
+    let retained = ALLOCATED.load(Ordering::Relaxed).saturating_sub(baseline);
+
+    latencies.sort_unstable();
+    println!("operations: {}", ops.len());
+    println!("elapsed:    {elapsed:?}");
+    println!(
+        "throughput: {:.0} ops/s",
+        ops.len() as f64 / elapsed.as_secs_f64()
+    );
+    for (name, p) in [("p50", 0.5), ("p90", 0.9), ("p99", 0.99), ("max", 1.0)] {
+        println!("latency {name}: {:?}", percentile(&latencies, p));
+    }
+    println!("memory:     {retained} bytes retained, {peak} bytes peak");
+    Ok(())
+}
@@ -0,0 +1,141 @@
+//! Computes the connected components of a graph given as an edge list.
+
+use clap::{Parser, ValueEnum};
+use std::error::Error;
+use std::fs::File;
+use std::io::{self, BufRead, BufReader, BufWriter, Write};
+use std::path::PathBuf;
+use unionfind::algorithms::kruskal_by;
+use unionfind::external::{Edge, EdgeListError, Edges};
+use unionfind::HashUnionFindByRank;
+
+#[derive(Debug, Clone, Copy, ValueEnum)]
+enum Format {
+    Csv,
+    Json,
+}
+
+#[derive(Parser, Debug)]
+#[command(about)]
+struct Args {
+    /// The edge list to read, one edge per line. Reads from stdin when omitted.
+    input: Option<PathBuf>,
+
+    /// Where to write the result. Writes to stdout when omitted.
+    #[arg(short, long)]
+    output: Option<PathBuf>,
+
+    /// The delimiter between columns. By default, any whitespace or a comma.
+    #[arg(short, long)]
+    delimiter: Option<char>,
+
+    /// Read weights from the third column, and output a minimum spanning forest
+    /// (computed with Kruskal's algorithm) instead of the components.
+    #[arg(short, long)]
+    kruskal: bool,
+
+    #[arg(short, long, value_enum, default_value_t = Format::Csv)]
+    format: Format,
+}
+
+fn add_vertex(uf: &mut HashUnionFindByRank<String>, vertex: &String) {
+    if uf.find_shorten(vertex).is_none() {
+        uf.add(vertex.clone()).expect("vertex was not present");
+    }
+}
+
+type EdgeResult = Result<Edge<String>, EdgeListError>;
+
+fn components(
+    edges: impl Iterator<Item = EdgeResult>,
+    format: Format,
+    mut output: impl Write,
+) -> Result<(), Box<dyn Error>> {
+    let mut uf = HashUnionFindByRank::empty();
+    for edge in edges {
+        let Edge { u, v, .. } = edge?;
+        add_vertex(&mut uf, &u);
+        add_vertex(&mut uf, &v);
+        uf.union_by_rank(&u, &v).expect("both vertices were added");
+    }
+
+    let vertices: Vec<String> = uf.keys().collect();
+    let labels = vertices.into_iter().map(|vertex| {
+        let component = uf.find_shorten(&vertex).expect("vertex was added");
+        (vertex, component)
+    });
+
+    match format {
+        Format::Csv => {
+            for (vertex, component) in labels {
+                writeln!(output, "{vertex},{component}")?;
+            }
+        }
+        Format::Json => {
+            let labels: serde_json::Map<_, _> = labels
+                .map(|(vertex, component)| (vertex, component.into()))
+                .collect();
+            serde_json::to_writer(&mut output, &labels)?;
+            writeln!(output)?;
+        }
+    }
+    Ok(())
+}
+
+fn kruskal(
+    edges: impl Iterator<Item = EdgeResult>,
+    format: Format,
+    mut output: impl Write,
+) -> Result<(), Box<dyn Error>> {
+    let edges = edges.collect::<Result<Vec<_>, _>>()?;
+    if let Some(edge) = edges.iter().find(|edge| edge.weight.is_none()) {
+        return Err(format!("edge {} {} has no weight", edge.u, edge.v).into());
+    }
+    let edges = edges
+        .into_iter()
+        .map(|Edge { u, v, weight }| (u, v, weight.expect("every edge has a weight")));
+    let forest = kruskal_by(edges, f64::total_cmp);
+
+    match format {
+        Format::Csv => {
+            for (u, v, weight) in forest {
+                writeln!(output, "{u},{v},{weight}")?;
+            }
+        }
+        Format::Json => {
+            let forest: Vec<_> = forest
+                .into_iter()
+                .map(|(u, v, weight)| serde_json::json!([u, v, weight]))
+                .collect();
+            serde_json::to_writer(&mut output, &forest)?;
+            writeln!(output)?;
+        }
+    }
+    Ok(())
+}
+
+fn main() -> Result<(), Box<dyn Error>> {
+    let args = Args::parse();
+
+    let input: Box<dyn BufRead> = match &args.input {
+        Some(path) => Box::new(BufReader::new(File::open(path)?)),
+        None => Box::new(io::stdin().lock()),
+    };
+    let mut output: Box<dyn Write> = match &args.output {
+        Some(path) => Box::new(BufWriter::new(File::create(path)?)),
+        None => Box::new(BufWriter::new(io::stdout().lock())),
+    };
+
+    let mut edges = Edges::new(input);
+    if let Some(delimiter) = args.delimiter {
+        edges = edges.with_delimiter(delimiter);
+    }
+
+    if args.kruskal {
+        kruskal(edges.with_weights(), args.format, &mut output)?;
+    } else {
+        components(edges, args.format, &mut output)?;
+    }
+    output.flush()?;
+    Ok(())
+}
@@ -0,0 +1,108 @@
+use crate::forest::SpanningForest;
+use crate::generic::UnionStatus;
+use crate::weighted::{Parity, UnionWithDiffError, WeightedUnionFind};
+use std::hash::Hash;
+
+/// The result of adding an edge to a [`BipartitenessChecker`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Verdict<T> {
+    /// The graph is still bipartite after adding the edge.
+    Bipartite,
+    /// This edge is the first edge that made the graph non-bipartite.
+    ///
+    /// When explanations are enabled, `odd_cycle` contains the vertices of an odd cycle
+    /// in the graph, starting at the first and ending at the second endpoint of the edge.
+    BecameNonBipartite { odd_cycle: Option<Vec<T>> },
+    /// The graph was already not bipartite before this edge was added.
+    NonBipartite,
+}
+
+/// Checks whether a graph is bipartite while its edges are streamed in.
+///
+/// Vertices are added automatically the first time they appear in an edge.
+#[derive(Debug, Clone)]
+pub struct BipartitenessChecker<T: Hash + Eq> {
+    /// Tracks for every vertex whether it is on the same side as the representative of its component.
+    uf: WeightedUnionFind<T, Parity>,
+    /// The edges that merged two components, only kept when explanations are enabled.
+    forest: Option<SpanningForest<T, ()>>,
+    /// The first edge that made the graph non-bipartite.
+    first_odd_edge: Option<(T, T)>,
+}
+
+impl<T: Hash + Eq + Clone> Default for BipartitenessChecker<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T: Hash + Eq + Clone> BipartitenessChecker<T> {
+    /// Creates a checker without explanations.
+    pub fn new() -> Self {
+        Self {
+            uf: WeightedUnionFind::new(),
+            forest: None,
+            first_odd_edge: None,
+        }
+    }
+
+    /// Creates a checker which reports an odd cycle when the graph becomes non-bipartite.
+    /// This requires remembering the edges that connected components.
+    pub fn with_explanations() -> Self {
+        Self {
+            forest: Some(SpanningForest::new()),
+            ..Self::new()
+        }
+    }
+
+    /// Adds an edge to the graph, and reports whether the graph is still bipartite.
+    pub fn add_edge(&mut self, u: T, v: T) -> Verdict<T> {
+        if self.first_odd_edge.is_some() {
+            return Verdict::NonBipartite;
+        }
+
+        self.uf.add(u.clone());
+        self.uf.add(v.clone());
+
+        match self.uf.union_different(&u, &v) {
+            Ok(UnionStatus::PerformedUnion) => {
+                if let Some(forest) = &mut self.forest {
+                    forest.add_edge(u, v, ());
+                }
+                Verdict::Bipartite
+            }
+            Ok(UnionStatus::AlreadyEquivalent) => Verdict::Bipartite,
+            Err(UnionWithDiffError::Contradiction { .. }) => {
+                let odd_cycle = self.forest.as_ref().map(|forest| {
+                    forest
+                        .path_elements(&u, &v)
+                        .expect("vertices in the same component are connected in the forest")
+                });
+
+                self.first_odd_edge = Some((u, v));
+                Verdict::BecameNonBipartite { odd_cycle }
+            }
+            Err(UnionWithDiffError::Elem1NotFound | UnionWithDiffError::Elem2NotFound) => {
+                unreachable!("both vertices were added")
+            }
+        }
+    }
+
+    /// Returns whether the graph is bipartite.
+    pub fn is_bipartite(&self) -> bool {
+        self.first_odd_edge.is_none()
+    }
+
+    /// Returns the first edge that made the graph non-bipartite, if any.
+    pub fn first_odd_edge(&self) -> Option<&(T, T)> {
+        self.first_odd_edge.as_ref()
+    }
+
+    /// Returns whether two vertices have to be on the same side of the bipartition,
+    /// or `None` when they are not in the same component.
+    ///
+    /// Only meaningful while the graph is bipartite.
+    pub fn same_side(&mut self, u: &T, v: &T) -> Option<bool> {
+        self.uf.same_side(u, v)
+    }
+}
@@ -0,0 +1,198 @@
+use std::collections::HashMap;
+use std::hash::Hash;
+
+/// Maintains the number of bridges of a graph while edges are added. A bridge is an
+/// edge whose removal disconnects the graph.
+///
+/// Uses the online algorithm with two union finds: one for the connected components and one
+/// for the 2-edge-connected components (components without bridges), together with a spanning
+/// forest of the 2-edge-connected components. Adding an edge takes amortized `O(log n)` time.
+#[derive(Debug, Clone)]
+pub struct OnlineBridges<T: Hash + Eq> {
+    indices: HashMap<T, usize>,
+
+    /// Union find over the 2-edge-connected components.
+    two_edge: Vec<usize>,
+    /// Union find over the connected components. Only meaningful for representatives
+    /// of 2-edge-connected components.
+    connected: Vec<usize>,
+    /// For representatives of connected components, the number of 2-edge-connected
+    /// components in them.
+    connected_size: Vec<usize>,
+    /// The parent of every 2-edge-connected component in the spanning forest.
+    forest_parent: Vec<Option<usize>>,
+
+    /// Used to find lowest common ancestors in the forest.
+    last_visit: Vec<usize>,
+    lca_iteration: usize,
+
+    bridges: usize,
+}
+
+impl<T: Hash + Eq> Default for OnlineBridges<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T: Hash + Eq> OnlineBridges<T> {
+    pub fn new() -> Self {
+        Self {
+            indices: HashMap::new(),
+            two_edge: Vec::new(),
+            connected: Vec::new(),
+            connected_size: Vec::new(),
+            forest_parent: Vec::new(),
+            last_visit: Vec::new(),
+            lca_iteration: 0,
+            bridges: 0,
+        }
+    }
+
+    /// The number of bridges in the graph.
+    pub fn bridge_count(&self) -> usize {
+        self.bridges
+    }
+
+    /// Adds a vertex without edges. Returns false if the vertex was already present.
+    pub fn add_vertex(&mut self, vertex: T) -> bool {
+        if self.indices.contains_key(&vertex) {
+            return false;
+        }
+        self.index(vertex);
+        true
+    }
+
+    fn index(&mut self, vertex: T) -> usize {
+        let next = self.indices.len();
+        let index = *self.indices.entry(vertex).or_insert(next);
+        if index == next {
+            self.two_edge.push(index);
+            self.connected.push(index);
+            self.connected_size.push(1);
+            self.forest_parent.push(None);
+            self.last_visit.push(0);
+        }
+        index
+    }
+
+    fn find_two_edge(&mut self, v: usize) -> usize {
+        let mut root = v;
+        while self.two_edge[root] != root {
+            root = self.two_edge[root];
+        }
+
+        let mut current = v;
+        while current != root {
+            let next = self.two_edge[current];
+            self.two_edge[current] = root;
+            current = next;
+        }
+        root
+    }
+
+    fn find_connected(&mut self, v: usize) -> usize {
+        let v = self.find_two_edge(v);
+        let parent = self.connected[v];
+        if parent == v {
+            return v;
+        }
+        let root = self.find_connected(parent);
+        self.connected[v] = root;
+        root
+    }
+
+    /// Reroots the tree of the spanning forest containing `v` at `v`.
+    fn make_root(&mut self, v: usize) {
+        let root = v;
+        let mut child = None;
+        let mut current = Some(v);
+        while let Some(v) = current {
+            let parent = self.forest_parent[v].map(|p| self.find_two_edge(p));
+            self.forest_parent[v] = child;
+            self.connected[v] = root;
+            child = Some(v);
+            current = parent;
+        }
+        let child = child.expect("the loop runs at least once");
+        self.connected_size[root] = self.connected_size[child];
+    }
+
+    /// Merges all 2-edge-connected components on the forest path between `a` and `b`,
+    /// since the edge between them closes a cycle.
+    fn merge_path(&mut self, a: usize, b: usize) {
+        self.lca_iteration += 1;
+        let mut path_a = Vec::new();
+        let mut path_b = Vec::new();
+        let mut a = Some(a);
+        let mut b = Some(b);
+
+        let lca = 'search: loop {
+            for (current, path) in [(&mut a, &mut path_a), (&mut b, &mut path_b)] {
+                if let Some(v) = *current {
+                    let v = self.find_two_edge(v);
+                    path.push(v);
+                    if self.last_visit[v] == self.lca_iteration {
+                        break 'search v;
+                    }
+                    self.last_visit[v] = self.lca_iteration;
+                    *current = self.forest_parent[v];
+                }
+            }
+        };
+
+        for path in [path_a, path_b] {
+            for v in path {
+                self.two_edge[v] = lca;
+                if v == lca {
+                    break;
+                }
+                self.bridges -= 1;
+            }
+        }
+    }
+
+    /// Adds an edge to the graph. Vertices that weren't added before are added.
+    pub fn add_edge(&mut self, u: T, v: T) {
+        let u = self.index(u);
+        let v = self.index(v);
+        let mut a = self.find_two_edge(u);
+        let mut b = self.find_two_edge(v);
+        if a == b {
+            return;
+        }
+
+        let mut ca = self.find_connected(a);
+        let mut cb = self.find_connected(b);
+        if ca != cb {
+            self.bridges += 1;
+            if self.connected_size[ca] > self.connected_size[cb] {
+                std::mem::swap(&mut a, &mut b);
+                std::mem::swap(&mut ca, &mut cb);
+            }
+            self.make_root(a);
+            self.forest_parent[a] = Some(b);
+            self.connected[a] = b;
+            self.connected_size[cb] += self.connected_size[a];
+        } else {
+            self.merge_path(a, b);
+        }
+    }
+
+    /// Returns whether two vertices are connected.
+    pub fn connected(&mut self, u: &T, v: &T) -> bool {
+        match (self.indices.get(u), self.indices.get(v)) {
+            (Some(&u), Some(&v)) => self.find_connected(u) == self.find_connected(v),
+            _ => false,
+        }
+    }
+
+    /// Returns whether two vertices are connected by a path that remains after
+    /// removing any single edge, i.e. whether they are connected without bridges.
+    pub fn two_edge_connected(&mut self, u: &T, v: &T) -> bool {
+        match (self.indices.get(u), self.indices.get(v)) {
+            (Some(&u), Some(&v)) => self.find_two_edge(u) == self.find_two_edge(v),
+            _ => false,
+        }
+    }
+}
@@ -0,0 +1,111 @@
+use crate::extra::{ByRank, MergeExtra};
+use crate::generic::{UnionByRankError, UnionError, UnionFind, UnionStatus};
+use crate::mapping::Mapping;
+use crate::union::Union;
+use std::cell::{Ref, RefCell, RefMut};
+use std::collections::HashMap;
+use std::hash::Hash;
+
+/// A [`UnionFind`] that shortens paths during finds through a shared reference, using
+/// interior mutability. This is useful when the union find is kept behind a shared reference,
+/// where [`UnionFind::find`] could not shorten paths.
+///
+/// Like a [`RefCell`], this can't be shared between threads, see
+/// [`SyncUnionFind`](crate::sync::SyncUnionFind) for that. Finds never panic: when the union
+/// find is already borrowed, they fall back to a find without path shortening. Unions borrow
+/// the union find mutably, and panic if it is borrowed through [`borrow`](CellUnionFind::borrow)
+/// at the same time.
+#[derive(Debug, Clone)]
+pub struct CellUnionFind<T: Hash + Eq, V, E = (), M = HashMap<T, T>> {
+    inner: RefCell<UnionFind<T, V, E, M>>,
+}
+
+impl<T: Hash + Eq, V, E, M> From<UnionFind<T, V, E, M>> for CellUnionFind<T, V, E, M> {
+    fn from(uf: UnionFind<T, V, E, M>) -> Self {
+        Self::new(uf)
+    }
+}
+
+impl<T: Hash + Eq, V, E, M> CellUnionFind<T, V, E, M> {
+    pub fn new(uf: UnionFind<T, V, E, M>) -> Self {
+        Self {
+            inner: RefCell::new(uf),
+        }
+    }
+
+    /// Borrows the union find, for operations that are not passed through.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the union find is currently borrowed mutably.
+    pub fn borrow(&self) -> Ref<'_, UnionFind<T, V, E, M>> {
+        self.inner.borrow()
+    }
+
+    /// Borrows the union find mutably.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the union find is currently borrowed.
+    pub fn borrow_mut(&self) -> RefMut<'_, UnionFind<T, V, E, M>> {
+        self.inner.borrow_mut()
+    }
+
+    pub fn get_mut(&mut self) -> &mut UnionFind<T, V, E, M> {
+        self.inner.get_mut()
+    }
+
+    pub fn into_inner(self) -> UnionFind<T, V, E, M> {
+        self.inner.into_inner()
+    }
+}
+
+impl<T: Hash + Eq + Clone, V, E, M: Mapping<T, T>> CellUnionFind<T, V, E, M> {
+    /// Find an element in the union find, shortening the path to it like
+    /// [`UnionFind::find_shorten`]. When the union find is already borrowed, the path is
+    /// not shortened.
+    pub fn find(&self, elem: &T) -> Option<T> {
+        match self.inner.try_borrow_mut() {
+            Ok(mut uf) => uf.find_shorten(elem),
+            Err(_) => self.inner.borrow().find(elem),
+        }
+    }
+
+    /// Returns whether two elements are in the same class, or `None` if either of them
+    /// is not in the union find. Shortens paths like [`find`](CellUnionFind::find).
+    pub fn connected(&self, elem1: &T, elem2: &T) -> Option<bool> {
+        Some(self.find(elem1)? == self.find(elem2)?)
+    }
+
+    /// See [`UnionFind::union_by`].
+    ///
+    /// # Panics
+    ///
+    /// Panics if the union find is currently borrowed.
+    pub fn union_by<U: Union<T>>(
+        &self,
+        elem1: &T,
+        elem2: &T,
+        union: U,
+    ) -> Result<UnionStatus, UnionError<U::Err, E::MergeError>>
+    where
+        E: MergeExtra<T>,
+    {
+        self.inner.borrow_mut().union_by(elem1, elem2, union)
+    }
+}
+
+impl<T: Hash + Eq + Clone, V, R, M> CellUnionFind<T, V, ByRank<T, R>, M>
+where
+    R: Mapping<T, usize>,
+    M: Mapping<T, T>,
+{
+    /// See [`UnionFind::union_by_rank`].
+    ///
+    /// # Panics
+    ///
+    /// Panics if the union find is currently borrowed.
+    pub fn union_by_rank(&self, elem1: &T, elem2: &T) -> Result<UnionStatus, UnionByRankError> {
+        self.inner.borrow_mut().union_by_rank(elem1, elem2)
+    }
+}
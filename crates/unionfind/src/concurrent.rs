@@ -0,0 +1,151 @@
+//! A lock-free union find over the keys `0..len`, which can be shared between threads.
+
+use crate::generic::{UnionError, UnionStatus};
+use std::convert::Infallible;
+
+// Like in `sync`, loom replaces the atomics with `--cfg loom`, to check every interleaving of
+// concurrent finds and unions.
+#[cfg(loom)]
+use loom::sync::atomic::{AtomicU32, AtomicUsize, Ordering};
+#[cfg(not(loom))]
+use std::sync::atomic::{AtomicU32, AtomicUsize, Ordering};
+
+/// A union find over the keys `0..len` that can be used by many threads at the same time,
+/// without locking. This is useful for computing the connected components of very large
+/// graphs, by splitting the edges between threads.
+///
+/// Follows Jayanti and Tarjan: finds halve paths with compare-and-swap, and unions link the
+/// root with the lowest priority below the other one, retrying when another thread changed
+/// either root in the meantime. Priorities are a fixed pseudo-random permutation of the keys,
+/// which keeps trees shallow for every order of unions.
+#[derive(Debug)]
+pub struct ConcurrentUnionFind {
+    parent: Vec<AtomicU32>,
+    sets: AtomicUsize,
+}
+
+/// A pseudo-random priority of a key. This is a bijection, so different keys never have
+/// the same priority.
+fn priority(key: u32) -> u32 {
+    let mut x = key.wrapping_mul(0x9e37_79b9);
+    x ^= x >> 16;
+    x = x.wrapping_mul(0x85eb_ca6b);
+    x ^ (x >> 13)
+}
+
+impl ConcurrentUnionFind {
+    /// Creates a union find with the keys `0..len`, every key in a class on its own.
+    pub fn new(len: u32) -> Self {
+        Self {
+            parent: (0..len).map(AtomicU32::new).collect(),
+            sets: AtomicUsize::new(len as usize),
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.parent.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.parent.is_empty()
+    }
+
+    /// The number of classes. While other threads are making unions, this may already be
+    /// outdated when it is returned.
+    pub fn num_sets(&self) -> usize {
+        self.sets.load(Ordering::Relaxed)
+    }
+
+    fn parent(&self, key: u32) -> u32 {
+        self.parent[key as usize].load(Ordering::Acquire)
+    }
+
+    /// Find the representative of a key, halving the path to it.
+    ///
+    /// While other threads are making unions, the returned key may already have stopped
+    /// being a representative when it is returned.
+    pub fn find(&self, key: u32) -> Option<u32> {
+        if key as usize >= self.len() {
+            return None;
+        }
+        let mut current = key;
+        loop {
+            let parent = self.parent(current);
+            let grandparent = self.parent(parent);
+            if parent == grandparent {
+                return Some(parent);
+            }
+            // Failing is fine, another thread already changed the parent to a later ancestor.
+            let _ = self.parent[current as usize].compare_exchange(
+                parent,
+                grandparent,
+                Ordering::AcqRel,
+                Ordering::Acquire,
+            );
+            current = grandparent;
+        }
+    }
+
+    /// Returns whether two keys are in the same class, or `None` if either of them
+    /// is not in the union find.
+    pub fn connected(&self, key1: u32, key2: u32) -> Option<bool> {
+        loop {
+            let root1 = self.find(key1)?;
+            let root2 = self.find(key2)?;
+            if root1 == root2 {
+                return Some(true);
+            }
+            // If root1 is still a representative, both keys were in different classes when
+            // root2 was found.
+            if self.parent(root1) == root1 {
+                return Some(false);
+            }
+        }
+    }
+
+    /// Union the classes of two keys.
+    pub fn union(&self, key1: u32, key2: u32) -> Result<UnionStatus, UnionError<Infallible>> {
+        loop {
+            let root1 = self.find(key1).ok_or(UnionError::Elem1NotFound)?;
+            let root2 = self.find(key2).ok_or(UnionError::Elem2NotFound)?;
+            if root1 == root2 {
+                return Ok(UnionStatus::AlreadyEquivalent);
+            }
+
+            let (child, root) = if priority(root1) < priority(root2) {
+                (root1, root2)
+            } else {
+                (root2, root1)
+            };
+            // Fails if another thread linked the child in the meantime, in which case
+            // the representatives have to be found again.
+            let linked = self.parent[child as usize]
+                .compare_exchange(child, root, Ordering::AcqRel, Ordering::Acquire)
+                .is_ok();
+            if linked {
+                self.sets.fetch_sub(1, Ordering::Relaxed);
+                return Ok(UnionStatus::PerformedUnion);
+            }
+        }
+    }
+
+    /// Returns the representative of every key, indexed by key. Performs no path shortening.
+    ///
+    /// Takes a mutable reference, so that no unions are made at the same time.
+    pub fn roots(&mut self) -> Vec<u32> {
+        let parent: Vec<u32> = self
+            .parent
+            .iter()
+            .map(|parent| parent.load(Ordering::Relaxed))
+            .collect();
+        (0..parent.len())
+            .map(|key| {
+                let mut current = key as u32;
+                while parent[current as usize] != current {
+                    current = parent[current as usize];
+                }
+                current
+            })
+            .collect()
+    }
+}
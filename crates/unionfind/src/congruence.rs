@@ -0,0 +1,173 @@
+//! Congruence closure over terms built from uninterpreted function symbols.
+//!
+//! Besides the equalities that are asserted, two applications of the same function are equal
+//! when their arguments are equal: from `a = c` follows `f(a) = f(c)`. A [`CongruenceClosure`]
+//! keeps track of all equalities that follow this way.
+
+use crate::dense::VecUnionFind;
+use std::collections::HashMap;
+use std::hash::Hash;
+
+/// A term in a [`CongruenceClosure`], which is a function symbol applied to argument terms.
+/// Constants are functions without arguments.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Term(usize);
+
+/// Decides which equalities between terms follow from asserted equalities, where function
+/// symbols of type `S` are uninterpreted: the only thing known about them is that equal
+/// arguments give equal results.
+///
+/// Terms are shared, so creating the same term twice returns the same [`Term`]. Every class
+/// of equal terms keeps the applications that use one of its terms as an argument. When two
+/// classes are merged, only the applications using the class that is merged into the other
+/// are checked for new congruences, through a table of applications keyed by their symbol
+/// and the representatives of their arguments.
+#[derive(Debug, Clone)]
+pub struct CongruenceClosure<S> {
+    /// The symbol and arguments of every term.
+    terms: Vec<(S, Vec<Term>)>,
+    shared: HashMap<(S, Vec<Term>), Term>,
+    classes: VecUnionFind,
+    /// For every representative, the applications with an argument in its class.
+    uses: Vec<Vec<Term>>,
+    /// An application for every symbol and representatives of arguments. Entries with
+    /// representatives that were merged into another class are outdated, but since those
+    /// never become representatives again, they are never looked up.
+    signatures: HashMap<(S, Vec<usize>), Term>,
+    pending: Vec<(Term, Term)>,
+}
+
+impl<S: Hash + Eq + Clone> Default for CongruenceClosure<S> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<S: Hash + Eq + Clone> CongruenceClosure<S> {
+    pub fn new() -> Self {
+        Self {
+            terms: Vec::new(),
+            shared: HashMap::new(),
+            classes: VecUnionFind::with_len(0),
+            uses: Vec::new(),
+            signatures: HashMap::new(),
+            pending: Vec::new(),
+        }
+    }
+
+    /// The number of distinct terms created.
+    pub fn len(&self) -> usize {
+        self.terms.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.terms.is_empty()
+    }
+
+    /// The number of classes of equal terms.
+    pub fn num_classes(&self) -> usize {
+        self.classes.num_sets()
+    }
+
+    /// Returns the constant `symbol`.
+    pub fn constant(&mut self, symbol: S) -> Term {
+        self.term(symbol, &[])
+    }
+
+    /// Returns the application of `symbol` to `args`. When an application of `symbol` to equal
+    /// arguments already exists, both are equal.
+    ///
+    /// # Panics
+    ///
+    /// Panics if an argument was not created by this congruence closure.
+    pub fn term(&mut self, symbol: S, args: &[Term]) -> Term {
+        let key = (symbol, args.to_vec());
+        if let Some(&term) = self.shared.get(&key) {
+            return term;
+        }
+
+        let term = Term(self.classes.push());
+        self.uses.push(Vec::new());
+        for &arg in args {
+            let root = self.root(arg);
+            self.uses[root].push(term);
+        }
+        self.terms.push(key.clone());
+        self.shared.insert(key, term);
+
+        self.insert_signature(term);
+        self.propagate();
+        term
+    }
+
+    /// The symbol and arguments of a term.
+    pub fn get(&self, term: Term) -> Option<(&S, &[Term])> {
+        let (symbol, args) = self.terms.get(term.0)?;
+        Some((symbol, args))
+    }
+
+    /// The representative of the class of a term.
+    pub fn find(&mut self, term: Term) -> Term {
+        Term(self.root(term))
+    }
+
+    /// Returns whether two terms are equal, following from the asserted equalities.
+    pub fn are_equal(&mut self, term1: Term, term2: Term) -> bool {
+        self.root(term1) == self.root(term2)
+    }
+
+    /// Asserts that two terms are equal, together with all equalities between applications
+    /// that follow from it.
+    pub fn assert_equal(&mut self, term1: Term, term2: Term) {
+        self.pending.push((term1, term2));
+        self.propagate();
+    }
+
+    fn root(&mut self, term: Term) -> usize {
+        self.classes
+            .find_shorten(&term.0)
+            .expect("term was created by this congruence closure")
+    }
+
+    fn signature(&mut self, term: Term) -> (S, Vec<usize>) {
+        let (symbol, args) = self.terms[term.0].clone();
+        let args = args.into_iter().map(|arg| self.root(arg)).collect();
+        (symbol, args)
+    }
+
+    /// Adds the signature of an application to the table, or, when an application with the
+    /// same signature is already there, records that both are equal.
+    fn insert_signature(&mut self, term: Term) {
+        let signature = self.signature(term);
+        match self.signatures.get(&signature) {
+            Some(&other) => self.pending.push((term, other)),
+            None => {
+                self.signatures.insert(signature, term);
+            }
+        }
+    }
+
+    fn propagate(&mut self) {
+        while let Some((term1, term2)) = self.pending.pop() {
+            let root1 = self.root(term1);
+            let root2 = self.root(term2);
+            if root1 == root2 {
+                continue;
+            }
+            self.classes
+                .union_by_rank(&root1, &root2)
+                .expect("roots are in the union find");
+            let (from, into) = if self.classes.find(&root1) == Some(root1) {
+                (root2, root1)
+            } else {
+                (root1, root2)
+            };
+
+            let moved = std::mem::take(&mut self.uses[from]);
+            for &term in &moved {
+                self.insert_signature(term);
+            }
+            self.uses[into].extend(moved);
+        }
+    }
+}
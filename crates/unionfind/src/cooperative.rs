@@ -0,0 +1,129 @@
+//! Bulk operations for async code, available with the `async` feature.
+//!
+//! Large bulk operations can take a long time, during which an async executor can't run other
+//! tasks on the same thread. The operations in this module yield to the executor every `budget`
+//! operations, so they can run on the executor directly instead of on a blocking thread.
+//! They don't depend on a specific executor.
+//!
+//! Dropping the future returned by one of these operations stops it. Operations performed
+//! before that are kept, just like when the operation fails halfway through.
+
+use crate::extra::{ByRank, GrowableExtra};
+use crate::generic::{AddErrorSimple, UnionByRankError, UnionFind, UnionStatus};
+use crate::progress::BulkError;
+use std::collections::HashMap;
+use std::future::Future;
+use std::hash::Hash;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+
+/// Returns `Pending` once, after asking to be polled again.
+struct YieldNow {
+    yielded: bool,
+}
+
+impl Future for YieldNow {
+    type Output = ();
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<()> {
+        if self.yielded {
+            return Poll::Ready(());
+        }
+        self.yielded = true;
+        cx.waker().wake_by_ref();
+        Poll::Pending
+    }
+}
+
+/// Counts operations, and yields to the executor every `budget` operations.
+struct Budget {
+    budget: usize,
+    remaining: usize,
+}
+
+impl Budget {
+    fn new(budget: usize) -> Self {
+        let budget = budget.max(1);
+        Self {
+            budget,
+            remaining: budget,
+        }
+    }
+
+    async fn spend(&mut self) {
+        self.remaining -= 1;
+        if self.remaining == 0 {
+            self.remaining = self.budget;
+            YieldNow { yielded: false }.await;
+        }
+    }
+}
+
+impl<T: Hash + Eq + Clone, V, E> UnionFind<T, V, E> {
+    /// Finds the representatives of all elements, like [`find_shorten`](UnionFind::find_shorten),
+    /// yielding to the executor every `budget` elements.
+    pub async fn find_all_async(
+        &mut self,
+        elems: impl IntoIterator<Item = T>,
+        budget: usize,
+    ) -> Vec<Option<T>> {
+        let mut budget = Budget::new(budget);
+        let mut roots = Vec::new();
+        for elem in elems {
+            roots.push(self.find_shorten(&elem));
+            budget.spend().await;
+        }
+        roots
+    }
+}
+
+impl<T: Hash + Eq + Clone, V: Default, E: GrowableExtra<T, V>> UnionFind<T, V, E> {
+    /// Adds every element that is not in the union find yet, each in a class on its own,
+    /// yielding to the executor every `budget` elements. Returns how many elements were added.
+    ///
+    /// When an element can't be added, the elements added up to that point are kept, and the
+    /// index of the failing element is returned with the error.
+    pub async fn extend_async(
+        &mut self,
+        elems: impl IntoIterator<Item = T>,
+        budget: usize,
+    ) -> Result<usize, BulkError<AddErrorSimple<T, V, HashMap<T, T>, E>>> {
+        let mut budget = Budget::new(budget);
+        let mut added = 0;
+        for (index, elem) in elems.into_iter().enumerate() {
+            if !self.raw().contains_key(&elem) {
+                self.add(elem)
+                    .map_err(|error| BulkError::Item { index, error })?;
+                added += 1;
+            }
+            budget.spend().await;
+        }
+        Ok(added)
+    }
+}
+
+impl<T: Hash + Eq + Clone, V> UnionFind<T, V, ByRank<T>> {
+    /// Like [`union_all_by_rank_with_progress`](UnionFind::union_all_by_rank_with_progress),
+    /// but yields to the executor every `budget` unions instead of reporting progress.
+    ///
+    /// Since the operation is cancelled by dropping the future, it never returns
+    /// [`BulkError::Cancelled`].
+    pub async fn union_all_by_rank_async(
+        &mut self,
+        pairs: impl IntoIterator<Item = (T, T)>,
+        budget: usize,
+    ) -> Result<usize, BulkError<UnionByRankError>> {
+        let mut budget = Budget::new(budget);
+        let mut merges = 0;
+        for (index, (elem1, elem2)) in pairs.into_iter().enumerate() {
+            let status = self
+                .union_by_rank(&elem1, &elem2)
+                .map_err(|error| BulkError::Item { index, error })?;
+            if status == UnionStatus::PerformedUnion {
+                merges += 1;
+            }
+            budget.spend().await;
+        }
+        Ok(merges)
+    }
+}
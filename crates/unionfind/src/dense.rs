@@ -0,0 +1,65 @@
+//! A union find for contiguous integer keys, stored in plain vectors.
+//!
+//! When the elements are the integers `0..n`, a [`VecUnionFind`] is much faster and smaller
+//! than a [`UnionFind`] backed by a `HashMap`: finds need no hashing, and every element takes
+//! one `usize` for its parent and one for its rank.
+
+use crate::extra::ByRank;
+use crate::generic::UnionFind;
+
+/// A union find over the keys `0..len`, using union by rank, with the parents and ranks
+/// stored in vectors. Keys are added in increasing order with [`push`](VecUnionFind::push),
+/// or with [`add`](UnionFind::add), which fails for any key but the next one.
+pub type VecUnionFind = UnionFind<usize, usize, ByRank<usize, Vec<usize>>, Vec<usize>>;
+
+impl VecUnionFind {
+    /// Creates a union find with the keys `0..len`, every key in a class on its own.
+    pub fn with_len(len: usize) -> Self {
+        Self::new(0..len).expect("keys are added in order")
+    }
+
+    /// Adds the next key, which is equal to the current [`len`](UnionFind::len),
+    /// in a class on its own. Returns the new key.
+    pub fn push(&mut self) -> usize {
+        let key = self.len();
+        self.add(key).expect("the next key can always be added");
+        key
+    }
+}
+
+/// Converts from the union find of [`petgraph`], keeping all classes. Takes linear time.
+#[cfg(feature = "petgraph")]
+impl<K: petgraph::graph::IndexType> From<petgraph::unionfind::UnionFind<K>> for VecUnionFind {
+    fn from(uf: petgraph::unionfind::UnionFind<K>) -> Self {
+        let labels = uf.into_labeling();
+        let mut converted = Self::with_len(labels.len());
+        for (key, root) in labels.into_iter().enumerate() {
+            converted
+                .union_by_rank(&key, &root.index())
+                .expect("keys are in the union find");
+        }
+        converted
+    }
+}
+
+/// Converts to the union find of [`petgraph`], keeping all classes. Takes linear time.
+///
+/// # Panics
+///
+/// Panics if a key does not fit in `K`.
+#[cfg(feature = "petgraph")]
+impl<K: petgraph::graph::IndexType> From<VecUnionFind> for petgraph::unionfind::UnionFind<K> {
+    fn from(uf: VecUnionFind) -> Self {
+        // `IndexType::new` truncates keys that don't fit
+        let index = |key: usize| {
+            let index = K::new(key);
+            assert_eq!(index.index(), key, "key does not fit in the index type");
+            index
+        };
+        let mut converted = petgraph::unionfind::UnionFind::new(uf.len());
+        for (key, root) in uf.iter() {
+            converted.union(index(key), index(*root));
+        }
+        converted
+    }
+}
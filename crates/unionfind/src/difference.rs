@@ -0,0 +1,123 @@
+use crate::forest::SpanningForest;
+use crate::generic::UnionStatus;
+use crate::weighted::{Group, UnionWithDiffError, WeightedUnionFind};
+use std::collections::HashMap;
+use std::fmt::Debug;
+use std::hash::Hash;
+use thiserror::Error;
+
+/// A single difference constraint `x - y = c`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Constraint<T, W> {
+    pub x: T,
+    pub y: T,
+    pub c: W,
+}
+
+/// Returned when a constraint contradicts the constraints added before it.
+#[derive(Debug, Error, PartialEq, Eq)]
+#[error("the difference constraints are inconsistent")]
+pub struct Inconsistency<T, W> {
+    /// A cycle of constraints which can't all be satisfied at once. The last constraint
+    /// is the one that was rejected, the others were added before. The witness is minimal:
+    /// removing any one of the constraints makes the rest satisfiable.
+    pub witness: Vec<Constraint<T, W>>,
+}
+
+/// A solver for systems of difference constraints of the form `x - y = c`.
+///
+/// Constraints are added one by one, and every constraint is checked against all constraints
+/// added before. A constraint that contradicts earlier constraints is rejected, and
+/// an explanation of the contradiction is returned, after which the solver is still usable.
+#[derive(Debug, Clone)]
+pub struct DifferenceConstraints<T: Hash + Eq, W> {
+    uf: WeightedUnionFind<T, W>,
+    /// The constraints that merged two classes. Every other accepted constraint
+    /// is implied by these.
+    forest: SpanningForest<T, Constraint<T, W>>,
+}
+
+impl<T: Hash + Eq + Clone, W> Default for DifferenceConstraints<T, W> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T: Hash + Eq + Clone, W> DifferenceConstraints<T, W> {
+    pub fn new() -> Self {
+        Self {
+            uf: WeightedUnionFind::new(),
+            forest: SpanningForest::new(),
+        }
+    }
+}
+
+impl<T, W> DifferenceConstraints<T, W>
+where
+    T: Hash + Eq + Clone + Debug,
+    W: Group + Copy + Debug,
+{
+    /// Adds the constraint `x - y = c`. Variables that weren't seen before are added automatically.
+    ///
+    /// When the constraint contradicts earlier constraints, it is not added, and
+    /// the cycle of constraints causing the contradiction is returned.
+    pub fn add_constraint(&mut self, x: T, y: T, c: W) -> Result<(), Inconsistency<T, W>> {
+        self.uf.add(x.clone());
+        self.uf.add(y.clone());
+
+        match self.uf.union_with_diff(&x, &y, c) {
+            Ok(status) => {
+                if status == UnionStatus::PerformedUnion {
+                    self.forest.add_edge(x.clone(), y.clone(), Constraint { x, y, c });
+                }
+                Ok(())
+            }
+            Err(UnionWithDiffError::Contradiction { .. }) => {
+                let mut witness: Vec<_> = self
+                    .forest
+                    .path(&x, &y)
+                    .expect("elements in the same class are connected in the forest")
+                    .into_iter()
+                    .cloned()
+                    .collect();
+                witness.push(Constraint { x, y, c });
+
+                Err(Inconsistency { witness })
+            }
+            Err(UnionWithDiffError::Elem1NotFound | UnionWithDiffError::Elem2NotFound) => {
+                unreachable!("both variables were added")
+            }
+        }
+    }
+
+    /// Adds every constraint in order, stopping at the first one that contradicts earlier constraints.
+    pub fn add_constraints(
+        &mut self,
+        constraints: impl IntoIterator<Item = Constraint<T, W>>,
+    ) -> Result<(), Inconsistency<T, W>> {
+        for Constraint { x, y, c } in constraints {
+            self.add_constraint(x, y, c)?;
+        }
+
+        Ok(())
+    }
+
+    /// Returns `x - y` if it is determined by the constraints so far.
+    pub fn diff(&mut self, x: &T, y: &T) -> Option<W> {
+        self.uf.diff(x, y)
+    }
+
+    /// Returns a satisfying assignment for every variable. The variables are grouped in
+    /// classes of variables constrained relative to each other. Within each class, the
+    /// representative of the class is assigned zero, and all other variables are assigned
+    /// relative to it.
+    pub fn solution(&self) -> HashMap<T, W> {
+        self.uf
+            .keys()
+            .map(|var| {
+                let (_, weight) = self.uf.find(var).expect("keys are in the union find");
+                (var.clone(), weight)
+            })
+            .collect()
+    }
+}
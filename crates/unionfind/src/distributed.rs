@@ -0,0 +1,206 @@
+//! Connected components over vertex partitions owned by separate workers, which may live in
+//! separate processes or on separate machines.
+//!
+//! This module only implements the protocol, sending the messages is left to the caller.
+//! All messages implement [`Serialize`] and [`Deserialize`]. A run goes as follows:
+//!
+//! 1. Every vertex is owned by the worker returned by [`partition_of`].
+//!    Every edge is given to [`Worker::add_edge`] of the owners of *both* its endpoints.
+//! 2. Every worker sends its [`BoundaryReport`] to the [`Coordinator`], which resolves
+//!    classes that span several partitions.
+//! 3. The coordinator sends every worker its [`Relabel`] message, after which
+//!    [`Worker::component`] returns the same label for all vertices in a component,
+//!    across all workers.
+
+use crate::HashUnionFindByRank;
+use serde::{Deserialize, Serialize};
+use std::collections::hash_map::DefaultHasher;
+use std::collections::HashMap;
+use std::hash::{Hash, Hasher};
+
+/// The partition owning `vertex`, out of `partitions` partitions.
+///
+/// The result only depends on the hash of `vertex`, so it is consistent across processes
+/// running the same build.
+pub fn partition_of<T: Hash>(vertex: &T, partitions: usize) -> usize {
+    assert!(partitions > 0, "there must be at least one partition");
+    let mut hasher = DefaultHasher::new();
+    vertex.hash(&mut hasher);
+    (hasher.finish() % partitions as u64) as usize
+}
+
+/// Sent by a worker to the coordinator once all its edges were added.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct BoundaryReport<T> {
+    pub partition: usize,
+    /// Every local vertex with an edge to another partition, together with its local representative.
+    pub boundary: Vec<(T, T)>,
+    /// The edges between a local vertex and a vertex of another partition.
+    pub edges: Vec<(T, T)>,
+}
+
+/// Sent by the coordinator to a worker, mapping local representatives to global labels.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct Relabel<T> {
+    pub labels: Vec<(T, T)>,
+}
+
+/// Owns the vertices of a single partition, and unions them locally.
+#[derive(Debug, Clone)]
+pub struct Worker<T: Hash + Eq> {
+    partition: usize,
+    partitions: usize,
+    local: HashUnionFindByRank<T>,
+    edges: Vec<(T, T)>,
+    labels: HashMap<T, T>,
+}
+
+impl<T: Hash + Eq + Clone> Worker<T> {
+    pub fn new(partition: usize, partitions: usize) -> Self {
+        assert!(partition < partitions, "partition out of range");
+        Self {
+            partition,
+            partitions,
+            local: HashUnionFindByRank::empty(),
+            edges: Vec::new(),
+            labels: HashMap::new(),
+        }
+    }
+
+    pub fn partition(&self) -> usize {
+        self.partition
+    }
+
+    fn owns(&self, vertex: &T) -> bool {
+        partition_of(vertex, self.partitions) == self.partition
+    }
+
+    fn add_vertex(&mut self, vertex: &T) {
+        if self.local.find_shorten(vertex).is_none() {
+            self.local
+                .add(vertex.clone())
+                .expect("vertex was not present");
+        }
+    }
+
+    /// Adds an edge with at least one endpoint owned by this worker.
+    /// Returns false, and ignores the edge, if neither endpoint is owned by this worker.
+    pub fn add_edge(&mut self, u: T, v: T) -> bool {
+        match (self.owns(&u), self.owns(&v)) {
+            (true, true) => {
+                self.add_vertex(&u);
+                self.add_vertex(&v);
+                self.local
+                    .union_by_rank(&u, &v)
+                    .expect("both vertices were added");
+            }
+            (true, false) => {
+                self.add_vertex(&u);
+                self.edges.push((u, v));
+            }
+            (false, true) => {
+                self.add_vertex(&v);
+                self.edges.push((v, u));
+            }
+            (false, false) => return false,
+        }
+        true
+    }
+
+    /// The report to send to the coordinator after all edges were added.
+    pub fn boundary_report(&mut self) -> BoundaryReport<T> {
+        let mut boundary = Vec::new();
+        for (u, _) in &self.edges {
+            let root = self.local.find_shorten(u).expect("vertex was added");
+            boundary.push((u.clone(), root));
+        }
+        BoundaryReport {
+            partition: self.partition,
+            boundary,
+            edges: self.edges.clone(),
+        }
+    }
+
+    /// Applies the labels sent by the coordinator.
+    pub fn apply(&mut self, relabel: Relabel<T>) {
+        self.labels.extend(relabel.labels);
+    }
+
+    /// The label of the component of a local vertex, or `None` if the vertex is not known
+    /// to this worker. Labels are only consistent across workers after the [`Relabel`]
+    /// from the coordinator was applied.
+    pub fn component(&mut self, vertex: &T) -> Option<T> {
+        let root = self.local.find_shorten(vertex)?;
+        Some(self.labels.get(&root).cloned().unwrap_or(root))
+    }
+
+    /// All local vertices with the label of their component.
+    pub fn components(&mut self) -> Vec<(T, T)> {
+        let vertices: Vec<T> = self.local.keys().collect();
+        vertices
+            .into_iter()
+            .map(|vertex| {
+                let label = self.component(&vertex).expect("vertex is local");
+                (vertex, label)
+            })
+            .collect()
+    }
+}
+
+/// Resolves the classes spanning several partitions from the reports of all workers.
+#[derive(Debug, Clone)]
+pub struct Coordinator<T: Hash + Eq> {
+    partitions: usize,
+    global: HashUnionFindByRank<T>,
+    /// The local representatives reported by every partition.
+    roots: Vec<Vec<T>>,
+}
+
+impl<T: Hash + Eq + Clone> Coordinator<T> {
+    pub fn new(partitions: usize) -> Self {
+        Self {
+            partitions,
+            global: HashUnionFindByRank::empty(),
+            roots: vec![Vec::new(); partitions],
+        }
+    }
+
+    fn union(&mut self, a: T, b: T) {
+        for vertex in [&a, &b] {
+            if self.global.find_shorten(vertex).is_none() {
+                self.global
+                    .add(vertex.clone())
+                    .expect("vertex was not present");
+            }
+        }
+        self.global
+            .union_by_rank(&a, &b)
+            .expect("both vertices were added");
+    }
+
+    /// Merges the classes connected by the boundary edges of a worker.
+    pub fn receive(&mut self, report: BoundaryReport<T>) {
+        assert!(report.partition < self.partitions, "partition out of range");
+        for (vertex, root) in report.boundary {
+            self.roots[report.partition].push(root.clone());
+            self.union(vertex, root);
+        }
+        for (u, v) in report.edges {
+            self.union(u, v);
+        }
+    }
+
+    /// The labels for a partition, once the reports of all workers were received.
+    pub fn relabel(&mut self, partition: usize) -> Relabel<T> {
+        let mut labels = HashMap::new();
+        for root in &self.roots[partition] {
+            let label = self.global.find_shorten(root).expect("root was added");
+            labels.insert(root.clone(), label);
+        }
+        Relabel {
+            labels: labels.into_iter().collect(),
+        }
+    }
+}
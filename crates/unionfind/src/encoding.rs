@@ -0,0 +1,231 @@
+//! A compact binary encoding of partitions, for sending the classes of many elements over
+//! the network. It is usually several times smaller than serializing a union find with serde.
+//!
+//! A partition of `n` elements is described by labels: the label of an element is the index
+//! of its class, where classes are numbered in the order in which their first element occurs.
+//! The positions of the first elements of the classes form an increasing sequence, which is
+//! stored with Elias-Fano coding. The labels of all other elements are stored as varints.
+
+use crate::generic::UnionFind;
+use std::collections::HashMap;
+use std::hash::Hash;
+use thiserror::Error;
+
+#[derive(Debug, Error, PartialEq, Eq)]
+pub enum DecodeError {
+    #[error("the encoding ended unexpectedly")]
+    Truncated,
+
+    #[error("a varint does not fit in 64 bits")]
+    VarintOverflow,
+
+    #[error("the label of element {0} refers to a class that does not exist yet")]
+    InvalidLabel(usize),
+
+    #[error("the positions of the first elements of the classes are not valid")]
+    InvalidClassStarts,
+
+    #[error("the encoding has {0} unused trailing bytes")]
+    TrailingBytes(usize),
+}
+
+/// Renumbers arbitrary class labels, so that classes are numbered in the order in which
+/// their first element occurs.
+pub fn normalize_labels<L: Hash + Eq>(labels: impl IntoIterator<Item = L>) -> Vec<usize> {
+    let mut numbers = HashMap::new();
+    labels
+        .into_iter()
+        .map(|label| {
+            let next = numbers.len();
+            *numbers.entry(label).or_insert(next)
+        })
+        .collect()
+}
+
+/// Encodes the classes of `elems` in a union find. Decoding gives the label of every element
+/// of `elems`, in the same order. Elements are not encoded, only which elements are equivalent.
+///
+/// # Panics
+///
+/// Panics if one of the elements is not in the union find.
+pub fn encode_partition<T: Hash + Eq + Clone, V, E>(
+    uf: &UnionFind<T, V, E>,
+    elems: &[T],
+) -> Vec<u8> {
+    let labels = normalize_labels(elems.iter().map(|elem| &uf[elem]));
+    encode_labels(&labels)
+}
+
+/// Encodes labels that are numbered in the order in which classes first occur, as returned
+/// by [`normalize_labels`].
+///
+/// # Panics
+///
+/// Panics if the labels are not normalized.
+pub fn encode_labels(labels: &[usize]) -> Vec<u8> {
+    let mut starts = Vec::new();
+    let mut rest = Vec::new();
+    for (position, &label) in labels.iter().enumerate() {
+        assert!(label <= starts.len(), "labels are not normalized");
+        if label == starts.len() {
+            starts.push(position as u64);
+        } else {
+            rest.push(label as u64);
+        }
+    }
+
+    let mut out = Vec::new();
+    write_varint(&mut out, labels.len() as u64);
+    write_varint(&mut out, starts.len() as u64);
+    write_elias_fano(&mut out, &starts, labels.len() as u64);
+    for label in rest {
+        write_varint(&mut out, label);
+    }
+    out
+}
+
+/// Decodes labels encoded with [`encode_labels`] or [`encode_partition`].
+pub fn decode_labels(bytes: &[u8]) -> Result<Vec<usize>, DecodeError> {
+    let mut input = bytes;
+    let len = read_varint(&mut input)?;
+    let classes = read_varint(&mut input)?;
+    // every element takes at least one bit
+    if len > bytes.len() as u64 * 8 {
+        return Err(DecodeError::Truncated);
+    }
+    if classes > len || (len > 0 && classes == 0) {
+        return Err(DecodeError::InvalidClassStarts);
+    }
+    let starts = read_elias_fano(&mut input, classes, len)?;
+    if starts.first().is_some_and(|&first| first != 0) {
+        return Err(DecodeError::InvalidClassStarts);
+    }
+
+    let mut labels = Vec::with_capacity(len as usize);
+    let mut starts = starts.into_iter().peekable();
+    let mut seen = 0;
+    for position in 0..len {
+        if starts.peek() == Some(&position) {
+            starts.next();
+            labels.push(seen);
+            seen += 1;
+        } else {
+            let label = read_varint(&mut input)?;
+            if label >= seen as u64 {
+                return Err(DecodeError::InvalidLabel(position as usize));
+            }
+            labels.push(label as usize);
+        }
+    }
+
+    if !input.is_empty() {
+        return Err(DecodeError::TrailingBytes(input.len()));
+    }
+    Ok(labels)
+}
+
+fn write_varint(out: &mut Vec<u8>, mut value: u64) {
+    while value >= 0x80 {
+        out.push(value as u8 | 0x80);
+        value >>= 7;
+    }
+    out.push(value as u8);
+}
+
+fn read_varint(input: &mut &[u8]) -> Result<u64, DecodeError> {
+    let mut value = 0u64;
+    for shift in (0..64).step_by(7) {
+        let (&byte, rest) = input.split_first().ok_or(DecodeError::Truncated)?;
+        *input = rest;
+        let bits = (byte & 0x7f) as u64;
+        if shift == 63 && bits > 1 {
+            return Err(DecodeError::VarintOverflow);
+        }
+        value |= bits << shift;
+        if byte & 0x80 == 0 {
+            return Ok(value);
+        }
+    }
+    Err(DecodeError::VarintOverflow)
+}
+
+/// The number of low bits stored explicitly for every value in an Elias-Fano coding
+/// of `count` increasing values below `universe`.
+fn low_bit_count(count: u64, universe: u64) -> u32 {
+    universe
+        .checked_div(count)
+        .map_or(0, |ratio| ratio.max(1).ilog2())
+}
+
+/// The number of bits in the unary coded high parts.
+fn high_bit_count(count: u64, universe: u64, low_bits: u32) -> u64 {
+    count + (universe >> low_bits) + 1
+}
+
+fn bytes_for(bits: u64) -> usize {
+    bits.div_ceil(8) as usize
+}
+
+/// Writes increasing values below `universe`. The low bits of every value are packed,
+/// followed by the high bits of every value in unary.
+fn write_elias_fano(out: &mut Vec<u8>, values: &[u64], universe: u64) {
+    let count = values.len() as u64;
+    let low_bits = low_bit_count(count, universe);
+
+    let mut low = vec![0u8; bytes_for(count * low_bits as u64)];
+    let mut high = vec![0u8; bytes_for(high_bit_count(count, universe, low_bits))];
+    for (i, &value) in values.iter().enumerate() {
+        for bit in 0..low_bits {
+            if (value >> bit) & 1 == 1 {
+                set_bit(&mut low, i as u64 * low_bits as u64 + bit as u64);
+            }
+        }
+        set_bit(&mut high, (value >> low_bits) + i as u64);
+    }
+
+    out.extend(low);
+    out.extend(high);
+}
+
+fn read_elias_fano(input: &mut &[u8], count: u64, universe: u64) -> Result<Vec<u64>, DecodeError> {
+    let low_bits = low_bit_count(count, universe);
+    let low_len = bytes_for(count * low_bits as u64);
+    let high_len = bytes_for(high_bit_count(count, universe, low_bits));
+    if input.len() < low_len + high_len {
+        return Err(DecodeError::Truncated);
+    }
+    let (low, rest) = input.split_at(low_len);
+    let (high, rest) = rest.split_at(high_len);
+    *input = rest;
+
+    let mut values = Vec::with_capacity(count as usize);
+    let mut position = 0u64;
+    while (values.len() as u64) < count {
+        if position >= high.len() as u64 * 8 {
+            return Err(DecodeError::InvalidClassStarts);
+        }
+        if get_bit(high, position) {
+            let i = values.len() as u64;
+            let mut value = (position - i) << low_bits;
+            for bit in 0..low_bits {
+                if get_bit(low, i * low_bits as u64 + bit as u64) {
+                    value |= 1 << bit;
+                }
+            }
+            if value >= universe || values.last().is_some_and(|&last| last >= value) {
+                return Err(DecodeError::InvalidClassStarts);
+            }
+            values.push(value);
+        }
+        position += 1;
+    }
+    Ok(values)
+}
+
+fn set_bit(bytes: &mut [u8], bit: u64) {
+    bytes[(bit / 8) as usize] |= 1 << (bit % 8);
+}
+
+fn get_bit(bytes: &[u8], bit: u64) -> bool {
+    (bytes[(bit / 8) as usize] >> (bit % 8)) & 1 == 1
+}
@@ -0,0 +1,173 @@
+use crate::extra::{ByRank, GrowableExtra, MergeExtra, Values};
+use crate::generic::{AddErrorSimple, UnionByRankError, UnionError, UnionFind, UnionStatus};
+use crate::mapping::{GrowableMapping, Mapping};
+use crate::union::Union;
+use std::hash::{BuildHasher, Hash};
+
+/// An element of a [`UnionFind`] that was looked up once with [`entry`](UnionFind::entry),
+/// and is either in the union find already or can be added to it.
+pub enum Entry<'a, T: Hash + Eq, V, E, M> {
+    Occupied(OccupiedEntry<'a, T, V, E, M>),
+    Vacant(VacantEntry<'a, T, V, E, M>),
+}
+
+type AddedEntry<'a, T, V, E, M> = Result<OccupiedEntry<'a, T, V, E, M>, AddErrorSimple<T, V, M, E>>;
+
+/// An element that is in the union find, together with its representative.
+pub struct OccupiedEntry<'a, T: Hash + Eq, V, E, M> {
+    uf: &'a mut UnionFind<T, V, E, M>,
+    elem: T,
+    root: T,
+}
+
+/// An element that is not in the union find yet.
+pub struct VacantEntry<'a, T: Hash + Eq, V, E, M> {
+    uf: &'a mut UnionFind<T, V, E, M>,
+    elem: T,
+}
+
+impl<T: Hash + Eq + Clone, V, E, M: GrowableMapping<T, T>> UnionFind<T, V, E, M> {
+    /// Looks up an element, so that it can be added if it is missing and then unioned with
+    /// other elements, without finding it again for every operation.
+    pub fn entry(&mut self, elem: T) -> Entry<'_, T, V, E, M> {
+        match self.find_shorten(&elem) {
+            Some(root) => Entry::Occupied(OccupiedEntry {
+                uf: self,
+                elem,
+                root,
+            }),
+            None => Entry::Vacant(VacantEntry { uf: self, elem }),
+        }
+    }
+}
+
+impl<T: Hash + Eq, V, E, M> Entry<'_, T, V, E, M> {
+    /// The element that was looked up.
+    pub fn key(&self) -> &T {
+        match self {
+            Entry::Occupied(entry) => &entry.elem,
+            Entry::Vacant(entry) => &entry.elem,
+        }
+    }
+}
+
+impl<'a, T: Hash + Eq + Clone, V, E, M: GrowableMapping<T, T>> Entry<'a, T, V, E, M>
+where
+    E: GrowableExtra<T, V>,
+{
+    /// Adds the element in a class on its own if it is missing.
+    pub fn or_add(self) -> AddedEntry<'a, T, V, E, M>
+    where
+        V: Default,
+    {
+        self.or_add_with(V::default)
+    }
+
+    /// Like [`or_add`](Entry::or_add), but the extra information for the element is only
+    /// computed by calling `make_extra` when the element is actually added.
+    pub fn or_add_with(self, make_extra: impl FnOnce() -> V) -> AddedEntry<'a, T, V, E, M> {
+        match self {
+            Entry::Occupied(entry) => Ok(entry),
+            Entry::Vacant(entry) => entry.add_with_extra(make_extra()),
+        }
+    }
+}
+
+impl<'a, T: Hash + Eq + Clone, V, E, M: Mapping<T, T>> OccupiedEntry<'a, T, V, E, M> {
+    pub fn key(&self) -> &T {
+        &self.elem
+    }
+
+    /// The representative of the class of the element. It is kept up to date by the unions
+    /// made through the entry.
+    pub fn root(&self) -> &T {
+        &self.root
+    }
+
+    /// Union the class of the element with the class of `other`, like
+    /// [`UnionFind::union_by`].
+    pub fn union_by<U: Union<T>>(
+        &mut self,
+        other: &T,
+        union: U,
+    ) -> Result<UnionStatus, UnionError<U::Err, E::MergeError>>
+    where
+        E: MergeExtra<T>,
+    {
+        let other_root = self
+            .uf
+            .find_shorten(other)
+            .ok_or(UnionError::Elem2NotFound)?;
+        let status = self
+            .uf
+            .union_helper(self.root.clone(), other_root.clone(), union)?;
+        self.uf.record_union(&self.elem, other, status);
+        self.root = self.uf.surviving_root(self.root.clone(), other_root);
+        Ok(status)
+    }
+
+    /// Gives back the union find, for operations the entry doesn't provide.
+    pub fn into_union_find(self) -> &'a mut UnionFind<T, V, E, M> {
+        self.uf
+    }
+}
+
+impl<T: Hash + Eq + Clone, R: Mapping<T, usize>, M: Mapping<T, T>>
+    OccupiedEntry<'_, T, usize, ByRank<T, R>, M>
+{
+    /// Union the class of the element with the class of `other` by rank, like
+    /// [`UnionFind::union_by_rank`].
+    pub fn union_by_rank(&mut self, other: &T) -> Result<UnionStatus, UnionByRankError> {
+        let other_root = self
+            .uf
+            .find_shorten(other)
+            .ok_or(UnionByRankError::Elem2NotFound)?;
+        let status = self
+            .uf
+            .union_by_rank_helper(self.root.clone(), other_root.clone())?;
+        self.uf.record_union(&self.elem, other, status);
+        self.root = self.uf.surviving_root(self.root.clone(), other_root);
+        Ok(status)
+    }
+}
+
+impl<T: Hash + Eq + Clone, V, S: BuildHasher, M: Mapping<T, T>>
+    OccupiedEntry<'_, T, V, Values<T, V, S>, M>
+{
+    /// The value of the class of the element.
+    pub fn value(&self) -> Option<&V> {
+        self.uf.extra.value(&self.root)
+    }
+
+    /// Like [`value`](OccupiedEntry::value), but the value can be modified.
+    pub fn value_mut(&mut self) -> Option<&mut V> {
+        self.uf.extra.value_mut(&self.root)
+    }
+}
+
+impl<'a, T: Hash + Eq + Clone, V, E, M: GrowableMapping<T, T>> VacantEntry<'a, T, V, E, M>
+where
+    E: GrowableExtra<T, V>,
+{
+    pub fn key(&self) -> &T {
+        &self.elem
+    }
+
+    /// Adds the element in a class on its own.
+    pub fn add(self) -> AddedEntry<'a, T, V, E, M>
+    where
+        V: Default,
+    {
+        self.add_with_extra(V::default())
+    }
+
+    /// Adds the element in a class on its own, with the given extra information.
+    pub fn add_with_extra(self, extra: V) -> AddedEntry<'a, T, V, E, M> {
+        self.uf.add_with_extra(self.elem.clone(), extra)?;
+        Ok(OccupiedEntry {
+            uf: self.uf,
+            root: self.elem.clone(),
+            elem: self.elem,
+        })
+    }
+}
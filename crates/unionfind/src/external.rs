@@ -0,0 +1,217 @@
+use crate::generic::UnionStatus;
+use crate::progress::Progress;
+use crate::HashUnionFindByRank;
+use std::convert::Infallible;
+use std::fmt::Display;
+use std::hash::Hash;
+use std::io::{BufRead, Lines, Write};
+use std::marker::PhantomData;
+use std::str::FromStr;
+use thiserror::Error;
+
+#[derive(Debug, Error)]
+pub enum EdgeListError {
+    #[error(transparent)]
+    Io(#[from] std::io::Error),
+
+    #[error("line {line}: expected two vertices")]
+    Malformed { line: usize },
+
+    #[error("line {line}: `{vertex}` is not a valid vertex")]
+    InvalidVertex { line: usize, vertex: String },
+
+    #[error("line {line}: `{weight}` is not a valid weight")]
+    InvalidWeight { line: usize, weight: String },
+
+    /// The operation was cancelled through a
+    /// [`CancellationToken`](crate::progress::CancellationToken) after reading `edges` edges.
+    #[error("cancelled after reading {edges} edges")]
+    Cancelled { edges: usize },
+}
+
+/// An edge read from an edge list.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Edge<V> {
+    pub u: V,
+    pub v: V,
+    /// The third column, if present and weights are read, see
+    /// [`with_weights`](Edges::with_weights).
+    pub weight: Option<f64>,
+}
+
+/// Reads the edges of an edge list one line at a time.
+///
+/// Every line contains two vertices, separated by a delimiter. By default, any whitespace
+/// or a comma is accepted as delimiter. Further columns are ignored, unless weights are
+/// read from the third column with [`with_weights`](Edges::with_weights). Empty lines and
+/// lines starting with `#` are skipped.
+#[derive(Debug)]
+pub struct Edges<R, V> {
+    lines: Lines<R>,
+    line: usize,
+    delimiter: Option<char>,
+    weights: bool,
+    phantom: PhantomData<V>,
+}
+
+impl<R: BufRead, V: FromStr> Edges<R, V> {
+    pub fn new(input: R) -> Self {
+        Self {
+            lines: input.lines(),
+            line: 0,
+            delimiter: None,
+            weights: false,
+            phantom: PhantomData,
+        }
+    }
+
+    /// Only accept `delimiter` between columns.
+    pub fn with_delimiter(mut self, delimiter: char) -> Self {
+        self.delimiter = Some(delimiter);
+        self
+    }
+
+    /// Parse the third column, if present, as the weight of the edge. Lines with a third
+    /// column that is not a number are then rejected.
+    pub fn with_weights(mut self) -> Self {
+        self.weights = true;
+        self
+    }
+
+    fn parse(&self, line: &str) -> Result<Edge<V>, EdgeListError> {
+        let mut columns: Box<dyn Iterator<Item = &str>> = match self.delimiter {
+            Some(delimiter) => Box::new(line.split(delimiter).map(str::trim)),
+            None => Box::new(
+                line.split(|c: char| c.is_whitespace() || c == ',')
+                    .filter(|column| !column.is_empty()),
+            ),
+        };
+
+        let (Some(u), Some(v)) = (columns.next(), columns.next()) else {
+            return Err(EdgeListError::Malformed { line: self.line });
+        };
+        let parse = |vertex: &str| {
+            vertex
+                .parse::<V>()
+                .map_err(|_| EdgeListError::InvalidVertex {
+                    line: self.line,
+                    vertex: vertex.to_string(),
+                })
+        };
+        let weight = columns
+            .next()
+            .filter(|_| self.weights)
+            .map(|weight| {
+                weight.parse().map_err(|_| EdgeListError::InvalidWeight {
+                    line: self.line,
+                    weight: weight.to_string(),
+                })
+            })
+            .transpose()?;
+
+        Ok(Edge {
+            u: parse(u)?,
+            v: parse(v)?,
+            weight,
+        })
+    }
+}
+
+impl<R: BufRead, V: FromStr> Iterator for Edges<R, V> {
+    type Item = Result<Edge<V>, EdgeListError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            let line = match self.lines.next()? {
+                Ok(line) => line,
+                Err(e) => return Some(Err(e.into())),
+            };
+            self.line += 1;
+
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            return Some(self.parse(line));
+        }
+    }
+}
+
+/// Statistics about a run of [`components_of_edge_list`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct EdgeListStats {
+    pub vertices: usize,
+    pub edges: usize,
+    pub components: usize,
+}
+
+/// Computes the connected components of a graph given as an edge list, without ever
+/// holding the edges in memory.
+///
+/// Edges are read one line at a time from `edges`, as described in [`Edges`].
+/// Only a union find over the vertices is kept in memory.
+///
+/// Afterwards, a line `vertex,component` is written to `output` for every vertex, where
+/// `component` is the representative vertex of its component. The output is written
+/// incrementally, wrap `output` in a [`BufWriter`](std::io::BufWriter) to write it in chunks.
+pub fn components_of_edge_list<V>(
+    edges: impl BufRead,
+    output: impl Write,
+) -> Result<EdgeListStats, EdgeListError>
+where
+    V: FromStr + Hash + Eq + Clone + Display,
+{
+    components_of_edge_list_with_progress::<V>(edges, output, &mut Progress::none())
+}
+
+/// Like [`components_of_edge_list`], but progress is reported in read edges, and cancellation
+/// is checked through `progress`. When the operation is cancelled, nothing is written to
+/// `output`, and [`EdgeListError::Cancelled`] is returned.
+pub fn components_of_edge_list_with_progress<V>(
+    edges: impl BufRead,
+    mut output: impl Write,
+    progress: &mut Progress<'_>,
+) -> Result<EdgeListStats, EdgeListError>
+where
+    V: FromStr + Hash + Eq + Clone + Display,
+{
+    let mut uf = HashUnionFindByRank::<V>::empty();
+    let mut stats = EdgeListStats {
+        vertices: 0,
+        edges: 0,
+        components: 0,
+    };
+
+    for edge in Edges::<_, V>::new(edges) {
+        progress
+            .check::<Infallible>(stats.edges)
+            .map_err(|_| EdgeListError::Cancelled { edges: stats.edges })?;
+        let Edge { u, v, .. } = edge?;
+
+        for vertex in [&u, &v] {
+            if uf.find_shorten(vertex).is_none() {
+                uf.add(vertex.clone()).expect("vertex was not present");
+                stats.vertices += 1;
+                stats.components += 1;
+            }
+        }
+
+        let status = uf.union_by_rank(&u, &v).expect("both vertices were added");
+        if status == UnionStatus::PerformedUnion {
+            stats.components -= 1;
+        }
+        stats.edges += 1;
+        progress.processed(stats.edges);
+    }
+
+    let vertices: Vec<V> = uf.keys().collect();
+    for vertex in vertices {
+        let component = uf
+            .find_shorten(&vertex)
+            .expect("vertex is in the union find");
+        writeln!(output, "{vertex},{component}")?;
+    }
+    output.flush()?;
+
+    Ok(stats)
+}
@@ -1,9 +1,12 @@
 use std::collections::HashMap;
-use crate::mapping::{GrowableMapping, Mapping, RankMapping};
+use std::hash::RandomState;
+use crate::mapping::{
+    deserialize_entries, serialize_entries, GrowableMapping, IterableMapping, Mapping, RankMapping,
+};
 use std::convert::Infallible;
 use std::error::Error;
 use std::fmt::Debug;
-use std::hash::Hash;
+use std::hash::{BuildHasher, Hash};
 use std::marker::PhantomData;
 use serde::{Deserialize, Serialize};
 use serde_with::serde_as;
@@ -37,6 +40,12 @@ pub trait GrowableExtra<K, V> {
     fn add(&mut self, k: K, v: V) -> Result<(), Self::AddError>
     where
         Self: Sized;
+
+    /// Reserves room for at least `additional` more elements. Does nothing by default.
+    fn reserve(&mut self, _additional: usize) {}
+
+    /// Frees memory that is not used by the current elements. Does nothing by default.
+    fn shrink_to_fit(&mut self) {}
 }
 
 /// () trivially implements GrowableExtra, which is the default when there is no extra info.
@@ -51,26 +60,161 @@ impl<K, V> GrowableExtra<K, V> for () {
     }
 }
 
-#[serde_as]
+/// Trait for extra information that has to be combined when two classes are unioned
+/// using [`union_by`](crate::generic::UnionFind::union_by).
+///
+/// Extra information that is not affected by unions implements it with
+/// `type MergeError = Infallible` and a `merge` that returns `Ok(())`.
+pub trait MergeExtra<T> {
+    type MergeError;
+
+    /// Called before the classes with representatives `a` and `b` are unioned,
+    /// after which `into` will be the representative of the unioned class.
+    ///
+    /// Returning an error aborts the union. In that case, the extra information
+    /// must be left unchanged.
+    fn merge(&mut self, a: &T, b: &T, into: &T) -> Result<(), Self::MergeError>;
+}
+
+/// () trivially implements MergeExtra, since there is nothing to merge.
+impl<T> MergeExtra<T> for () {
+    type MergeError = Infallible;
+
+    fn merge(&mut self, _a: &T, _b: &T, _into: &T) -> Result<(), Self::MergeError> {
+        Ok(())
+    }
+}
+
+/// Extra information that can be reset to the information of a union find in which every
+/// element is in a class on its own, see
+/// [`reset_to_singletons`](crate::generic::UnionFind::reset_to_singletons).
+pub trait ResetExtra {
+    /// Reset the information of every element, keeping allocated memory where possible.
+    fn reset(&mut self);
+}
+
+/// () trivially implements ResetExtra, since there is nothing to reset.
+impl ResetExtra for () {
+    fn reset(&mut self) {}
+}
+
+/// Extra information that can be recomputed from the parent mapping alone. This allows
+/// serializing a union find without its extra information, see
+/// [`serialize_without_extra`](crate::generic::UnionFind::serialize_without_extra).
+pub trait RecomputeExtra<T>: Sized {
+    /// Recompute the extra information for a parent mapping that forms a forest.
+    fn recompute<M: IterableMapping<T, T>>(parent: &M) -> Self;
+}
+
+impl<T> RecomputeExtra<T> for () {
+    fn recompute<M: IterableMapping<T, T>>(_parent: &M) -> Self {}
+}
+
+/// Extra information of which changes by merges and additions can be undone, so that a
+/// [`Transaction`](crate::transaction::Transaction) can be aborted.
+pub trait UndoExtra<T> {
+    /// The information stored for a single element.
+    type Saved;
+
+    /// Saves the information of a representative, before it is merged.
+    fn save(&self, elem: &T) -> Self::Saved;
+
+    /// Restores information saved with [`save`](UndoExtra::save). Information is restored
+    /// in the reverse order in which it was saved.
+    fn restore(&mut self, elem: &T, saved: Self::Saved);
+
+    /// Removes the information of an element that was added.
+    fn remove(&mut self, elem: &T);
+}
+
+/// () trivially implements UndoExtra, since there is nothing to undo.
+impl<T> UndoExtra<T> for () {
+    type Saved = ();
+
+    fn save(&self, _elem: &T) -> Self::Saved {}
+
+    fn restore(&mut self, _elem: &T, _saved: Self::Saved) {}
+
+    fn remove(&mut self, _elem: &T) {}
+}
+
+/// Extra information that is kept up to date when an element is removed from the union find,
+/// see [`remove`](crate::generic::UnionFind::remove).
+pub trait RemoveExtra<T> {
+    /// Removes `elem` from the class with representative `old_root`. When `elem` was the
+    /// representative, `new_root` is the representative of the rest of the class, or `None` if
+    /// `elem` was the only member. Otherwise, `new_root` is `old_root`.
+    fn remove(&mut self, elem: &T, old_root: &T, new_root: Option<&T>);
+}
+
+impl<T> RemoveExtra<T> for () {
+    fn remove(&mut self, _elem: &T, _old_root: &T, _new_root: Option<&T>) {}
+}
+
+/// Extra information that is kept up to date when another member of a class becomes its
+/// representative, see [`set_representative`](crate::generic::UnionFind::set_representative).
+pub trait RerootExtra<T> {
+    /// Moves the information of the class with representative `old_root` to `new_root`.
+    /// Afterwards, `old_root` points to `new_root`, so the tree can be one level higher.
+    fn reroot(&mut self, old_root: &T, new_root: &T);
+}
+
+impl<T> RerootExtra<T> for () {
+    fn reroot(&mut self, _old_root: &T, _new_root: &T) {}
+}
+
+/// Follows parents from `elem` until a representative is found.
+/// Returns the representative and the number of steps taken.
+fn root_of<'a, T: Eq, M: Mapping<T, T>>(parent: &'a M, elem: &'a T) -> (&'a T, usize) {
+    let mut current = elem;
+    let mut depth = 0;
+    loop {
+        let next = parent.get(current).expect("parent is in the mapping");
+        if next == current {
+            return (current, depth);
+        }
+        current = next;
+        depth += 1;
+    }
+}
+
+/// Extra information storing the rank of every element, used by
+/// [`union_by_rank`](crate::generic::UnionFind::union_by_rank). Ranks are stored in a
+/// `HashMap` by default, but any [`RankMapping`] can be used.
 #[derive(Debug, Clone, Deserialize, Serialize)]
-#[serde(bound(serialize = "T: Serialize", deserialize = "T: Deserialize<'de>"))]
-pub struct ByRank<T: Hash + Eq> {
-    #[serde_as(as = "Vec<(_, _)>")]
-    mapping: HashMap<T, usize>,
+#[serde(bound(
+    serialize = "T: Serialize, R: IterableMapping<T, usize>",
+    deserialize = "T: Deserialize<'de>, R: GrowableMapping<T, usize>"
+))]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+#[cfg_attr(feature = "schemars", schemars(bound = "T: schemars::JsonSchema"))]
+pub struct ByRank<T: Hash + Eq, R = HashMap<T, usize>> {
+    #[serde(serialize_with = "serialize_entries", deserialize_with = "deserialize_entries")]
+    #[cfg_attr(feature = "schemars", schemars(with = "Vec<(T, usize)>"))]
+    mapping: R,
     phantom: PhantomData<T>,
 }
 
-impl<T: Hash+Eq> ByRank<T>
+impl<T: Hash + Eq, R: Default> Default for ByRank<T, R> {
+    fn default() -> Self {
+        Self {
+            mapping: R::default(),
+            phantom: Default::default(),
+        }
+    }
+}
+
+impl<T: Hash+Eq, R: RankMapping<T>> ByRank<T, R>
 {
-    pub fn new(elems: impl IntoIterator<Item = T>) -> Result<Self, ()> {
+    pub fn new(elems: impl IntoIterator<Item = T>) -> Result<Self, <R as RankMapping<T>>::Err> {
         Ok(Self {
-            mapping: HashMap::zero_map(elems).unwrap(),
+            mapping: R::zero_map(elems)?,
             phantom: Default::default(),
         })
     }
 }
 
-impl<T: Hash+Eq> ByRank<T>
+impl<T: Hash+Eq, R: Mapping<T, usize>> ByRank<T, R>
 {
     pub fn rank(&self, elem: &T) -> Option<usize> {
         self.mapping.get(elem).cloned()
@@ -81,25 +225,553 @@ impl<T: Hash+Eq> ByRank<T>
     }
 }
 
-impl<T: Hash+Eq> Extra<T, usize> for ByRank<T>
+impl<T: Hash+Eq, R: RankMapping<T>> Extra<T, usize> for ByRank<T, R>
 {
-    type DefaultMappingErr = <HashMap<T, usize> as RankMapping<T>>::Err;
+    type DefaultMappingErr = <R as RankMapping<T>>::Err;
 
     fn default_mapping(
         elems: impl IntoIterator<Item = T>,
     ) -> Result<Self, Self::DefaultMappingErr> {
         Ok(Self {
-            mapping: HashMap::zero_map(elems)?,
+            mapping: R::zero_map(elems)?,
             phantom: Default::default(),
         })
     }
 }
 
-impl<T: Hash+ Eq> GrowableExtra<T, usize> for ByRank<T>
+/// Ranks are only maintained by [`union_by_rank`](crate::generic::UnionFind::union_by_rank),
+/// merging them never fails.
+impl<T: Hash + Eq, R> MergeExtra<T> for ByRank<T, R> {
+    type MergeError = Infallible;
+
+    fn merge(&mut self, _a: &T, _b: &T, _into: &T) -> Result<(), Self::MergeError> {
+        Ok(())
+    }
+}
+
+impl<T: Hash + Eq, R: IterableMapping<T, usize>> ResetExtra for ByRank<T, R> {
+    fn reset(&mut self) {
+        let elems: Vec<T> = self.mapping.entries().map(|(elem, _)| elem).collect();
+        for elem in elems {
+            self.mapping.set(elem, 0);
+        }
+    }
+}
+
+/// The rank of every representative is recomputed as the height of its tree. Since path
+/// shortening only ever lowers trees, this never exceeds the original rank, and classes
+/// still have at least `2^rank` elements.
+impl<T: Hash + Eq + Clone, S: BuildHasher + Default> RecomputeExtra<T>
+    for ByRank<T, HashMap<T, usize, S>>
 {
-    type AddError = <HashMap<T, usize> as GrowableMapping<T, usize>>::AddError;
+    fn recompute<M: IterableMapping<T, T>>(parent: &M) -> Self {
+        let mut mapping: HashMap<T, usize, S> =
+            parent.entries().map(|(elem, _)| (elem, 0)).collect();
+        for (elem, _) in parent.entries() {
+            let (root, depth) = root_of(parent, &elem);
+            let rank = mapping.get_mut(root).expect("every element has a rank");
+            *rank = (*rank).max(depth);
+        }
+
+        Self {
+            mapping,
+            phantom: Default::default(),
+        }
+    }
+}
+
+impl<T: Hash+ Eq, R: GrowableMapping<T, usize>> GrowableExtra<T, usize> for ByRank<T, R>
+{
+    type AddError = <R as GrowableMapping<T, usize>>::AddError;
 
     fn add(&mut self, elem: T, value: usize) -> Result<(), Self::AddError> {
         self.mapping.add(elem, value)
     }
+
+    fn reserve(&mut self, additional: usize) {
+        self.mapping.reserve(additional)
+    }
+
+    fn shrink_to_fit(&mut self) {
+        self.mapping.shrink_to_fit()
+    }
+}
+
+impl<T: Hash + Eq + Clone, S: BuildHasher> UndoExtra<T> for ByRank<T, HashMap<T, usize, S>> {
+    type Saved = Option<usize>;
+
+    fn save(&self, elem: &T) -> Self::Saved {
+        self.mapping.get(elem).copied()
+    }
+
+    fn restore(&mut self, elem: &T, saved: Self::Saved) {
+        if let Some(rank) = saved {
+            self.mapping.insert(elem.clone(), rank);
+        }
+    }
+
+    fn remove(&mut self, elem: &T) {
+        self.mapping.remove(elem);
+    }
+}
+
+/// The new representative inherits the rank, which still bounds the height of its tree.
+impl<T: Hash + Eq + Clone, S: BuildHasher> RemoveExtra<T> for ByRank<T, HashMap<T, usize, S>> {
+    fn remove(&mut self, elem: &T, old_root: &T, new_root: Option<&T>) {
+        let rank = self.mapping.remove(elem).expect("every element has a rank");
+        if let (true, Some(new_root)) = (elem == old_root, new_root) {
+            self.mapping.insert(new_root.clone(), rank);
+        }
+    }
+}
+
+/// The new representative gets a rank one higher than the old one, since its tree can be one
+/// level higher.
+impl<T: Hash + Eq + Clone, R: Mapping<T, usize>> RerootExtra<T> for ByRank<T, R> {
+    fn reroot(&mut self, old_root: &T, new_root: &T) {
+        let rank = self.rank(old_root).expect("every element has a rank");
+        self.set_rank(new_root.clone(), rank + 1);
+    }
+}
+
+/// Extra information tracking the size of every class. Only the sizes stored for
+/// representatives are meaningful.
+///
+/// Also keeps track of the number of pairs of distinct elements that are in the same
+/// class, which is the sum of `size * (size - 1) / 2` over all classes.
+#[serde_as]
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(bound(
+    serialize = "T: Serialize",
+    deserialize = "T: Deserialize<'de>, S: BuildHasher + Default"
+))]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+#[cfg_attr(feature = "schemars", schemars(bound = "T: schemars::JsonSchema"))]
+pub struct BySize<T: Hash + Eq, S = RandomState> {
+    #[serde_as(as = "Vec<(_, _)>")]
+    #[cfg_attr(feature = "schemars", schemars(with = "Vec<(T, usize)>"))]
+    mapping: HashMap<T, usize, S>,
+    connected_pairs: u64,
+}
+
+impl<T: Hash + Eq, S: Default> Default for BySize<T, S> {
+    fn default() -> Self {
+        Self {
+            mapping: HashMap::default(),
+            connected_pairs: 0,
+        }
+    }
+}
+
+impl<T: Hash + Eq, S: BuildHasher> BySize<T, S> {
+    pub fn size(&self, elem: &T) -> Option<usize> {
+        self.mapping.get(elem).cloned()
+    }
+
+    /// The number of pairs of distinct elements that are in the same class.
+    pub fn connected_pairs(&self) -> u64 {
+        self.connected_pairs
+    }
+}
+
+impl<T: Hash + Eq, S: BuildHasher + Default> Extra<T, ()> for BySize<T, S> {
+    type DefaultMappingErr = <HashMap<T, usize, S> as GrowableMapping<T, usize>>::AddError;
+
+    fn default_mapping(
+        elems: impl IntoIterator<Item = T>,
+    ) -> Result<Self, Self::DefaultMappingErr> {
+        let mut mapping = HashMap::empty();
+        for elem in elems {
+            mapping.add(elem, 1)?;
+        }
+
+        Ok(Self {
+            mapping,
+            connected_pairs: 0,
+        })
+    }
+}
+
+impl<T: Hash + Eq + Clone, S: BuildHasher> MergeExtra<T> for BySize<T, S> {
+    type MergeError = Infallible;
+
+    fn merge(&mut self, a: &T, b: &T, into: &T) -> Result<(), Self::MergeError> {
+        let size_a = self.mapping[a];
+        let size_b = self.mapping[b];
+        self.connected_pairs += size_a as u64 * size_b as u64;
+        self.mapping.insert(into.clone(), size_a + size_b);
+        Ok(())
+    }
+}
+
+impl<T: Hash + Eq, S: BuildHasher> ResetExtra for BySize<T, S> {
+    fn reset(&mut self) {
+        self.mapping.values_mut().for_each(|size| *size = 1);
+        self.connected_pairs = 0;
+    }
+}
+
+impl<T: Hash + Eq + Clone, S: BuildHasher + Default> RecomputeExtra<T> for BySize<T, S> {
+    fn recompute<M: IterableMapping<T, T>>(parent: &M) -> Self {
+        let mut mapping: HashMap<T, usize, S> =
+            parent.entries().map(|(elem, _)| (elem, 1)).collect();
+        for (elem, _) in parent.entries() {
+            let (root, depth) = root_of(parent, &elem);
+            if depth > 0 {
+                *mapping.get_mut(root).expect("every element has a size") += 1;
+            }
+        }
+
+        let connected_pairs = parent
+            .entries()
+            .filter(|(elem, parent)| elem == *parent)
+            .map(|(root, _)| {
+                let size = mapping[&root] as u64;
+                size * (size - 1) / 2
+            })
+            .sum();
+
+        Self {
+            mapping,
+            connected_pairs,
+        }
+    }
+}
+
+/// Since merges are undone in reverse order, restoring the number of connected pairs saved
+/// with every size restores the number from before the first merge.
+impl<T: Hash + Eq + Clone, S: BuildHasher> UndoExtra<T> for BySize<T, S> {
+    type Saved = (Option<usize>, u64);
+
+    fn save(&self, elem: &T) -> Self::Saved {
+        (self.mapping.get(elem).copied(), self.connected_pairs)
+    }
+
+    fn restore(&mut self, elem: &T, (size, connected_pairs): Self::Saved) {
+        if let Some(size) = size {
+            self.mapping.insert(elem.clone(), size);
+        }
+        self.connected_pairs = connected_pairs;
+    }
+
+    fn remove(&mut self, elem: &T) {
+        self.mapping.remove(elem);
+    }
+}
+
+impl<T: Hash + Eq + Clone, S: BuildHasher> RemoveExtra<T> for BySize<T, S> {
+    fn remove(&mut self, elem: &T, old_root: &T, new_root: Option<&T>) {
+        let size = self.mapping[old_root];
+        self.connected_pairs -= size as u64 - 1;
+        self.mapping.remove(elem);
+        if let Some(new_root) = new_root {
+            self.mapping.insert(new_root.clone(), size - 1);
+        }
+    }
+}
+
+impl<T: Hash + Eq + Clone, S: BuildHasher> RerootExtra<T> for BySize<T, S> {
+    fn reroot(&mut self, old_root: &T, new_root: &T) {
+        let size = self.mapping[old_root];
+        self.mapping.insert(new_root.clone(), size);
+    }
+}
+
+/// Every element starts out in a class of size one, so no value is needed when adding an element.
+impl<T: Hash + Eq, S: BuildHasher + Default> GrowableExtra<T, ()> for BySize<T, S> {
+    type AddError = <HashMap<T, usize, S> as GrowableMapping<T, usize>>::AddError;
+
+    fn add(&mut self, elem: T, _value: ()) -> Result<(), Self::AddError> {
+        self.mapping.add(elem, 1)
+    }
+
+    fn reserve(&mut self, additional: usize) {
+        self.mapping.reserve(additional)
+    }
+
+    fn shrink_to_fit(&mut self) {
+        self.mapping.shrink_to_fit()
+    }
+}
+
+/// Extra information linking the elements of every class in a cycle, so that the members
+/// of a class can be enumerated in time proportional to the size of the class, see
+/// [`class_members`](crate::generic::UnionFind::class_members).
+///
+/// Unions only have to swap two links, so keeping track of the members takes constant time.
+#[serde_as]
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(bound(
+    serialize = "T: Serialize",
+    deserialize = "T: Deserialize<'de>, S: BuildHasher + Default"
+))]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+#[cfg_attr(feature = "schemars", schemars(bound = "T: schemars::JsonSchema"))]
+pub struct Members<T: Hash + Eq, S = RandomState> {
+    /// The next element in the same class, for every element.
+    #[serde_as(as = "Vec<(_, _)>")]
+    #[cfg_attr(feature = "schemars", schemars(with = "Vec<(T, T)>"))]
+    next: HashMap<T, T, S>,
+}
+
+impl<T: Hash + Eq, S: Default> Default for Members<T, S> {
+    fn default() -> Self {
+        Self {
+            next: HashMap::default(),
+        }
+    }
+}
+
+impl<T: Hash + Eq, S: BuildHasher> Members<T, S> {
+    /// Every member of the class of `elem`, starting with `elem`.
+    pub fn members<'a>(&'a self, elem: &'a T) -> Option<impl Iterator<Item = &'a T> + 'a> {
+        let mut next = Some(self.next.get_key_value(elem)?.0);
+        Some(std::iter::from_fn(move || {
+            let current = next?;
+            let following = &self.next[current];
+            next = (following != elem).then_some(following);
+            Some(current)
+        }))
+    }
+}
+
+impl<T: Hash + Eq + Clone, S: BuildHasher + Default> Extra<T, ()> for Members<T, S> {
+    type DefaultMappingErr = <HashMap<T, T, S> as GrowableMapping<T, T>>::AddError;
+
+    fn default_mapping(
+        elems: impl IntoIterator<Item = T>,
+    ) -> Result<Self, Self::DefaultMappingErr> {
+        let mut next = HashMap::empty();
+        for elem in elems {
+            next.add(elem.clone(), elem)?;
+        }
+        Ok(Self { next })
+    }
+}
+
+impl<T: Hash + Eq + Clone, S: BuildHasher> MergeExtra<T> for Members<T, S> {
+    type MergeError = Infallible;
+
+    /// Swapping the links of two elements in different cycles joins the cycles.
+    fn merge(&mut self, a: &T, b: &T, _into: &T) -> Result<(), Self::MergeError> {
+        let next_a = self.next[a].clone();
+        let next_b = self.next.insert(b.clone(), next_a).expect("b is a member");
+        self.next.insert(a.clone(), next_b);
+        Ok(())
+    }
+}
+
+impl<T: Hash + Eq + Clone, S: BuildHasher + Default> GrowableExtra<T, ()> for Members<T, S> {
+    type AddError = <HashMap<T, T, S> as GrowableMapping<T, T>>::AddError;
+
+    fn add(&mut self, elem: T, _value: ()) -> Result<(), Self::AddError> {
+        self.next.add(elem.clone(), elem)
+    }
+
+    fn reserve(&mut self, additional: usize) {
+        self.next.reserve(additional)
+    }
+
+    fn shrink_to_fit(&mut self) {
+        self.next.shrink_to_fit()
+    }
+}
+
+impl<T: Hash + Eq + Clone, S: BuildHasher> ResetExtra for Members<T, S> {
+    fn reset(&mut self) {
+        for (elem, next) in self.next.iter_mut() {
+            next.clone_from(elem);
+        }
+    }
+}
+
+impl<T: Hash + Eq + Clone, S: BuildHasher + Default> RecomputeExtra<T> for Members<T, S> {
+    fn recompute<M: IterableMapping<T, T>>(parent: &M) -> Self {
+        // every element is inserted right after the representative of its class
+        let mut next: HashMap<T, T, S> =
+            parent.entries().map(|(elem, _)| (elem.clone(), elem)).collect();
+        for (elem, _) in parent.entries() {
+            let (root, depth) = root_of(parent, &elem);
+            if depth > 0 {
+                let after_root =
+                    next.insert(root.clone(), elem.clone()).expect("root is a member");
+                next.insert(elem, after_root);
+            }
+        }
+        Self { next }
+    }
+}
+
+impl<T: Hash + Eq + Clone, S: BuildHasher> UndoExtra<T> for Members<T, S> {
+    type Saved = Option<T>;
+
+    fn save(&self, elem: &T) -> Self::Saved {
+        self.next.get(elem).cloned()
+    }
+
+    fn restore(&mut self, elem: &T, saved: Self::Saved) {
+        if let Some(next) = saved {
+            self.next.insert(elem.clone(), next);
+        }
+    }
+
+    fn remove(&mut self, elem: &T) {
+        self.next.remove(elem);
+    }
+}
+
+/// Takes time proportional to the size of the class, to find the element linking to `elem`.
+impl<T: Hash + Eq + Clone, S: BuildHasher> RemoveExtra<T> for Members<T, S> {
+    fn remove(&mut self, elem: &T, _old_root: &T, _new_root: Option<&T>) {
+        let next = self.next.remove(elem).expect("elem is a member");
+        if next == *elem {
+            return;
+        }
+        let mut current = next.clone();
+        while self.next[&current] != *elem {
+            current = self.next[&current].clone();
+        }
+        self.next.insert(current, next);
+    }
+}
+
+/// The members are linked in a cycle, which doesn't depend on the representative.
+impl<T: Hash + Eq, S: BuildHasher> RerootExtra<T> for Members<T, S> {
+    fn reroot(&mut self, _old_root: &T, _new_root: &T) {}
+}
+
+/// Extra information storing a value for every class, which is combined by a merge function
+/// when classes are unioned with [`union_values`](crate::generic::UnionFind::union_values).
+/// This is the core of unification, for example in type inference, where the value of a class
+/// of type variables is the type they are known to be, if any.
+///
+/// Values are only stored for representatives. Unions are made by rank, so ranks are stored
+/// alongside the values.
+#[serde_as]
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(bound(
+    serialize = "T: Serialize, V: Serialize",
+    deserialize = "T: Deserialize<'de>, V: Deserialize<'de>, S: BuildHasher + Default"
+))]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+#[cfg_attr(
+    feature = "schemars",
+    schemars(bound = "T: schemars::JsonSchema, V: schemars::JsonSchema")
+)]
+pub struct Values<T: Hash + Eq, V, S = RandomState> {
+    #[serde_as(as = "Vec<(_, _)>")]
+    #[cfg_attr(feature = "schemars", schemars(with = "Vec<(T, V)>"))]
+    values: HashMap<T, V, S>,
+    #[serde_as(as = "Vec<(_, _)>")]
+    #[cfg_attr(feature = "schemars", schemars(with = "Vec<(T, usize)>"))]
+    rank: HashMap<T, usize, S>,
+}
+
+impl<T: Hash + Eq, V, S: Default> Default for Values<T, V, S> {
+    fn default() -> Self {
+        Self {
+            values: HashMap::default(),
+            rank: HashMap::default(),
+        }
+    }
+}
+
+impl<T: Hash + Eq, V, S: BuildHasher> Values<T, V, S> {
+    /// The value of the class with representative `root`.
+    pub fn value(&self, root: &T) -> Option<&V> {
+        self.values.get(root)
+    }
+
+    pub fn value_mut(&mut self, root: &T) -> Option<&mut V> {
+        self.values.get_mut(root)
+    }
+}
+
+impl<T: Hash + Eq + Clone, V, S: BuildHasher> Values<T, V, S> {
+    /// Merges the values of the classes with representatives `a` and `b`. Returns the
+    /// representative that stops being one, followed by the representative of the unioned
+    /// class, which is `into` if given, and otherwise chosen by rank. When `merge` fails,
+    /// nothing is changed.
+    pub(crate) fn merge<Err>(
+        &mut self,
+        a: T,
+        b: T,
+        into: Option<T>,
+        merge: impl FnOnce(&V, &V) -> Result<V, Err>,
+    ) -> Result<(T, T), Err> {
+        let merged = merge(&self.values[&a], &self.values[&b])?;
+
+        let (child, root) = match into {
+            Some(into) if into == a => (b, a),
+            Some(_) => (a, b),
+            None if self.rank[&a] > self.rank[&b] => (b, a),
+            None => (a, b),
+        };
+        let child_rank = self.rank.remove(&child).expect("child has a rank");
+        let root_rank = self.rank.get_mut(&root).expect("root has a rank");
+        *root_rank = (*root_rank).max(child_rank + 1);
+        self.values.remove(&child);
+        self.values.insert(root.clone(), merged);
+        Ok((child, root))
+    }
+}
+
+impl<T: Hash + Eq + Clone, V: Default, S: BuildHasher + Default> Extra<T, V> for Values<T, V, S> {
+    type DefaultMappingErr = Infallible;
+
+    fn default_mapping(
+        elems: impl IntoIterator<Item = T>,
+    ) -> Result<Self, Self::DefaultMappingErr> {
+        let values: HashMap<T, V, S> = elems.into_iter().map(|elem| (elem, V::default())).collect();
+        Ok(Self {
+            rank: values.keys().map(|elem| (elem.clone(), 0)).collect(),
+            values,
+        })
+    }
+}
+
+/// Every added element gets its own class, with the given value.
+impl<T: Hash + Eq + Clone, V, S: BuildHasher + Default> GrowableExtra<T, V> for Values<T, V, S> {
+    type AddError = <HashMap<T, V, S> as GrowableMapping<T, V>>::AddError;
+
+    fn add(&mut self, elem: T, value: V) -> Result<(), Self::AddError> {
+        self.values.add(elem.clone(), value)?;
+        self.rank.insert(elem, 0);
+        Ok(())
+    }
+
+    fn reserve(&mut self, additional: usize) {
+        self.values.reserve(additional);
+        self.rank.reserve(additional);
+    }
+
+    fn shrink_to_fit(&mut self) {
+        self.values.shrink_to_fit();
+        self.rank.shrink_to_fit();
+    }
+}
+
+/// The value and rank of the class move to the new representative.
+impl<T: Hash + Eq + Clone, V, S: BuildHasher> RemoveExtra<T> for Values<T, V, S> {
+    fn remove(&mut self, elem: &T, old_root: &T, new_root: Option<&T>) {
+        if elem != old_root {
+            return;
+        }
+        let value = self.values.remove(elem).expect("representative has a value");
+        let rank = self.rank.remove(elem).expect("representative has a rank");
+        if let Some(new_root) = new_root {
+            self.values.insert(new_root.clone(), value);
+            self.rank.insert(new_root.clone(), rank);
+        }
+    }
+}
+
+/// The value moves to the new representative, and its rank is increased like for [`ByRank`].
+impl<T: Hash + Eq + Clone, V, S: BuildHasher> RerootExtra<T> for Values<T, V, S> {
+    fn reroot(&mut self, old_root: &T, new_root: &T) {
+        let value = self.values.remove(old_root).expect("representative has a value");
+        let rank = self.rank.remove(old_root).expect("representative has a rank");
+        self.values.insert(new_root.clone(), value);
+        self.rank.insert(new_root.clone(), rank + 1);
+    }
 }
@@ -0,0 +1,113 @@
+use std::collections::{HashMap, VecDeque};
+use std::hash::Hash;
+
+/// Keeps track of the edges that actually merged two classes in a union find.
+/// These edges form a spanning forest of the classes, which means there is exactly one
+/// path of edges between any two elements of the same class.
+///
+/// Every edge carries a label, usually describing why the union was made.
+#[derive(Debug, Clone)]
+pub(crate) struct SpanningForest<T: Hash + Eq, L> {
+    /// For every element, its neighbours and the index of the edge connecting them.
+    adjacent: HashMap<T, Vec<(T, usize)>>,
+    /// The endpoints and label of every edge, in the order they were added.
+    edges: Vec<(T, T, L)>,
+}
+
+impl<T: Hash + Eq + Clone, L> SpanningForest<T, L> {
+    pub(crate) fn new() -> Self {
+        Self {
+            adjacent: HashMap::new(),
+            edges: Vec::new(),
+        }
+    }
+
+    /// Adds an edge between two elements. The elements must not already be connected
+    /// in the forest, otherwise it would stop being a forest.
+    pub(crate) fn add_edge(&mut self, a: T, b: T, label: L) {
+        let idx = self.edges.len();
+        self.adjacent
+            .entry(a.clone())
+            .or_default()
+            .push((b.clone(), idx));
+        self.adjacent
+            .entry(b.clone())
+            .or_default()
+            .push((a.clone(), idx));
+        self.edges.push((a, b, label));
+    }
+
+    /// Removes the edge that was added last, and returns its label.
+    pub(crate) fn pop_edge(&mut self) -> Option<L> {
+        let (a, b, label) = self.edges.pop()?;
+        // the last edge is always the last entry in the adjacency lists of both its endpoints
+        for endpoint in [a, b] {
+            if let Some(edges) = self.adjacent.get_mut(&endpoint) {
+                edges.pop();
+            }
+        }
+        Some(label)
+    }
+
+    pub(crate) fn last_label_mut(&mut self) -> Option<&mut L> {
+        self.edges.last_mut().map(|(_, _, label)| label)
+    }
+
+    /// Finds the labels on the path from `from` to `to`, in order.
+    /// Returns `None` if the two elements are not connected.
+    pub(crate) fn path(&self, from: &T, to: &T) -> Option<Vec<&L>> {
+        let path = self.path_edges(from, to)?;
+        Some(path.into_iter().map(|idx| &self.edges[idx].2).collect())
+    }
+
+    /// Finds the edges on the path from `from` to `to`, in order, with their endpoints in the
+    /// order they were added. Returns `None` if the two elements are not connected.
+    pub(crate) fn path_edges_with_endpoints(&self, from: &T, to: &T) -> Option<Vec<&(T, T, L)>> {
+        let path = self.path_edges(from, to)?;
+        Some(path.into_iter().map(|idx| &self.edges[idx]).collect())
+    }
+
+    /// Finds the elements on the path from `from` to `to`, starting with `from`
+    /// and ending with `to`. Returns `None` if the two elements are not connected.
+    pub(crate) fn path_elements(&self, from: &T, to: &T) -> Option<Vec<T>> {
+        let mut elements = vec![from.clone()];
+        for idx in self.path_edges(from, to)? {
+            let (a, b, _) = &self.edges[idx];
+            let last = elements.last().expect("path starts with from");
+            let next = if a == last { b } else { a };
+            elements.push(next.clone());
+        }
+        Some(elements)
+    }
+
+    /// Finds the indices of the edges on the path from `from` to `to`, in order.
+    fn path_edges(&self, from: &T, to: &T) -> Option<Vec<usize>> {
+        // maps every visited element to the element and edge we reached it through
+        let mut reached_through: HashMap<&T, Option<(&T, usize)>> = HashMap::new();
+        let mut queue = VecDeque::new();
+        reached_through.insert(from, None);
+        queue.push_back(from);
+
+        while let Some(current) = queue.pop_front() {
+            if current == to {
+                let mut path = Vec::new();
+                let mut current = current;
+                while let Some((previous, idx)) = reached_through[current] {
+                    path.push(idx);
+                    current = previous;
+                }
+                path.reverse();
+                return Some(path);
+            }
+
+            for (next, idx) in self.adjacent.get(current).into_iter().flatten() {
+                if !reached_through.contains_key(next) {
+                    reached_through.insert(next, Some((current, *idx)));
+                    queue.push_back(next);
+                }
+            }
+        }
+
+        None
+    }
+}
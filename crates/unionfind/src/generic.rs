@@ -1,103 +1,1212 @@
-use crate::extra::{ByRank, Extra, GrowableExtra};
+use crate::extra::{
+    ByRank, BySize, Extra, GrowableExtra, Members, MergeExtra, RecomputeExtra, RemoveExtra,
+    RerootExtra, ResetExtra, UndoExtra, Values,
+};
+use crate::forest::SpanningForest;
 use crate::mapping::{
-    GrowableIdentityMapping, GrowableMapping, Mapping, ParentMapping, RankMapping,
+    deserialize_entries, serialize_entries, GrowableIdentityMapping, GrowableMapping,
+    IterableMapping, Lookup, Mapping, ParentMapping, RankMapping, RemovableMapping,
 };
-use crate::union::Union;
+use crate::progress::{BulkError, Progress};
+use crate::transaction::Transaction;
+use crate::union::{infallible, ByMax, ByMin, Union};
+use crate::watch::{RepresentativeChange, Watchers};
 use std::cmp::Ordering;
-use std::collections::HashMap;
-use std::hash::Hash;
+use std::collections::{HashMap, HashSet};
+use std::convert::Infallible;
+use std::hash::{BuildHasher, Hash};
 use std::marker::PhantomData;
+use std::ops::{Deref, DerefMut, Index};
+use std::vec::Drain;
 use thiserror::Error;
-use serde::{Deserialize, Serialize};
-use serde_with::serde_as;
+use serde::de::Error as _;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
 
 /// A union find data structure. Note that this implementation clones elements a lot.
 /// Generally, you should use the data structure with small, preferably [`Copy`]able types,
 /// like integers. However, arbitrary [`Clone`]+[`PartialEq`] types are possible.
-#[serde_as]
+///
+/// The parent mapping is a [`HashMap`] by default, but any [`Mapping`] can be used, like
+/// a [`DenseMapping`](crate::mapping::DenseMapping) for integer elements. Most operations
+/// work for every mapping, while some, like iterating over the elements, need a `HashMap`.
+///
+/// `L` is the type of the labels that unions can carry, see
+/// [`union_by_labeled`](UnionFind::union_by_labeled). Labels are not serialized.
+///
+/// When serialized, the parent mapping is represented as a sequence of `(element, parent)` pairs.
+/// On deserialization, these pairs are inserted into the mapping as they are decoded, without first
+/// collecting them, so peak memory use stays close to the size of the union find itself.
 #[derive(Debug, Clone, Serialize, Deserialize)]
-#[serde(bound(serialize = "T: Serialize, E: Serialize", deserialize = "T: Deserialize<'de>, E: Deserialize<'de>"))]
-pub struct UnionFind<T: Hash+Eq, V, E = ()> {
+#[serde(bound(
+    serialize = "T: Serialize, E: Serialize, M: IterableMapping<T, T>",
+    deserialize = "T: Deserialize<'de> + Clone, E: Deserialize<'de>, M: GrowableMapping<T, T> + IterableMapping<T, T>"
+))]
+#[serde(try_from = "UnionFindData<T, V, E, M>")]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+#[cfg_attr(feature = "schemars", schemars(bound = "T: schemars::JsonSchema, E: schemars::JsonSchema"))]
+pub struct UnionFind<T: Hash+Eq, V, E = (), M = HashMap<T, T>, L = ()> {
     /// A mapping from some key to a parent key, for every key.
     /// When a key is in a class on its own, its parent is itself. Once
     /// unions start happening, multiple keys might get the same parent indicating
     /// they are unioned.
-    #[serde_as(as = "Vec<(_, _)>")]
-    pub parent: HashMap<T, T>,
+    ///
+    /// Modifying this mapping can easily break the invariants of the union find, so it will
+    /// become private in the next release. Use [`raw`](UnionFind::raw) and
+    /// [`parents`](UnionFind::parents) to read it, and [`raw_mut`](UnionFind::raw_mut) to
+    /// modify it.
+    #[deprecated(note = "use raw()/parents()")]
+    #[serde(serialize_with = "serialize_entries")]
+    #[cfg_attr(feature = "schemars", schemars(with = "Vec<(T, T)>"))]
+    pub parent: M,
     /// An optional array of extra information for each key.
     /// Under union by rank this is a `Mapping<T, usize>` to assign a rank to each element
     /// in the union find.
-    extra: E,
+    pub(crate) extra: E,
+    /// The maximum number of parent pointers rewritten by path shortening during
+    /// a single operation. `None` means path shortening is unbounded.
+    #[serde(default)]
+    compression_budget: Option<usize>,
+    /// Elements for which changes of their representative are reported.
+    #[serde(skip)]
+    pub(crate) watchers: Watchers<T>,
+    /// Representatives that must remain representatives, see [`pin`](UnionFind::pin).
+    #[serde(default)]
+    pinned: HashSet<T>,
+    /// The number of classes, see [`num_sets`](UnionFind::num_sets).
+    #[serde(skip)]
+    pub(crate) sets: usize,
+    /// The unions that merged two classes, with their label if they were labeled, kept when
+    /// enabled with [`track_paths`](UnionFind::track_paths).
+    #[serde(skip)]
+    pub(crate) union_tree: Option<SpanningForest<T, Option<L>>>,
     phantom: PhantomData<(T, V)>,
 }
 
+/// Errors returned when constructing a union find. `I` is the error of the elements, for
+/// constructors like [`try_new`](UnionFind::try_new) that take fallible elements.
 #[derive(Debug, Error, PartialEq)]
-pub enum NewUnionFindError<P, E> {
+pub enum NewUnionFindError<P, E, I = Infallible> {
     #[error("couldn't construct parent mapping")]
     Parent(#[source] P),
 
     #[error("couldn't construct extra mapping")]
     Extra(#[source] E),
+
+    #[error("couldn't produce an element")]
+    Elems(#[source] I),
 }
 
-type NewUnionFindErrorSimple<T, V, M, E> =
+pub(crate) type NewUnionFindErrorSimple<T, V, M, E> =
     NewUnionFindError<<M as ParentMapping<T>>::Err, <E as Extra<T, V>>::DefaultMappingErr>;
 
-impl<T: Hash+Eq, V, E> UnionFind<T, V, E>
+impl<T: Hash+Eq, V, E, M: ParentMapping<T>, L> UnionFind<T, V, E, M, L>
 where
     T: Clone,
     E: Extra<T, V>,
 {
     /// Constructs a new union find, allowing you to specify all type parameters.
+    ///
+    /// Fails if the parent mapping or the extra information can't hold the elements, for
+    /// example when a [`Vec`] mapping is given elements that are not `0..n` in order.
     pub fn new(
         elems: impl IntoIterator<Item = T> + Clone,
-    ) -> Result<Self, ()> {
+    ) -> Result<Self, NewUnionFindErrorSimple<T, V, M, E>> {
+        let parent = M::identity_map(elems.clone()).map_err(NewUnionFindError::Parent)?;
         Ok(Self {
-            parent: HashMap::identity_map(elems.clone()).unwrap(),
-            extra: E::default_mapping(elems).unwrap(),
+            sets: elems.clone().into_iter().count(),
+            parent,
+            extra: E::default_mapping(elems).map_err(NewUnionFindError::Extra)?,
+            compression_budget: None,
+            watchers: Default::default(),
+            pinned: Default::default(),
+            union_tree: None,
             phantom: Default::default(),
         })
     }
 }
 
-impl<T: Hash+Eq, V, E> UnionFind<T, V, E> {
+impl<T: Hash + Eq, V, E, M: GrowableMapping<T, T>, L> UnionFind<T, V, E, M, L>
+where
+    E: GrowableExtra<T, V> + Default,
+{
+    /// Constructs a union find without elements, to which elements can be added later,
+    /// for example with [`find_or_add`](UnionFind::find_or_add). The extra information
+    /// starts out as its [`Default`], which should hold no elements.
+    pub fn empty() -> Self {
+        Self {
+            sets: 0,
+            parent: M::empty(),
+            extra: E::default(),
+            compression_budget: None,
+            watchers: Default::default(),
+            pinned: Default::default(),
+            union_tree: None,
+            phantom: Default::default(),
+        }
+    }
+
+    /// Constructs a union find without elements, with room for at least `capacity` elements
+    /// in both the parent mapping and the extra information, see
+    /// [`reserve`](UnionFind::reserve).
+    pub fn with_capacity(capacity: usize) -> Self {
+        let mut uf = Self::empty();
+        uf.reserve(capacity);
+        uf
+    }
+}
+
+impl<T: Hash + Eq, V, E: GrowableExtra<T, V>, M: GrowableMapping<T, T>, L>
+    UnionFind<T, V, E, M, L>
+{
+    /// Reserves room for at least `additional` more elements in both the parent mapping and
+    /// the extra information, so that adding them doesn't reallocate. Mappings that can't
+    /// allocate ahead of time ignore this.
+    pub fn reserve(&mut self, additional: usize) {
+        self.parent.reserve(additional);
+        self.extra.reserve(additional);
+    }
+
+    /// Frees memory of the parent mapping and the extra information that is not used by the
+    /// current elements.
+    pub fn shrink_to_fit(&mut self) {
+        self.parent.shrink_to_fit();
+        self.extra.shrink_to_fit();
+    }
+}
+
+/// The same as [`empty`](UnionFind::empty).
+impl<T: Hash + Eq, V, E, M: GrowableMapping<T, T>, L> Default for UnionFind<T, V, E, M, L>
+where
+    E: GrowableExtra<T, V> + Default,
+{
+    fn default() -> Self {
+        Self::empty()
+    }
+}
+
+impl<T: Hash+Eq, V, E, L> UnionFind<T, V, E, HashMap<T, T>, L>
+where
+    T: Clone,
+    E: Extra<T, V>,
+{
+    /// Like [`new`](UnionFind::new), but for elements that are produced by a fallible
+    /// iterator, for example while decoding. Stops at, and returns, the first error as an
+    /// [`Elems`](NewUnionFindError::Elems) error.
+    pub fn try_new<Err>(
+        elems: impl IntoIterator<Item = Result<T, Err>>,
+    ) -> Result<Self, NewUnionFindError<Infallible, E::DefaultMappingErr, Err>> {
+        let parent = elems
+            .into_iter()
+            .map(|elem| elem.map(|elem| (elem.clone(), elem)))
+            .collect::<Result<HashMap<T, T>, Err>>()
+            .map_err(NewUnionFindError::Elems)?;
+        let extra =
+            E::default_mapping(parent.keys().cloned()).map_err(NewUnionFindError::Extra)?;
+        Ok(Self::from_raw_parts_unchecked(parent, extra))
+    }
+}
+
+impl<T: Hash+Eq, V, E, M: Mapping<T, T>, L> UnionFind<T, V, E, M, L> {
     /// Find an element in the union find. Performs no path shortening,
     /// but can be used through an immutable reference.
     ///
     /// Use [`find_shorten`](UnionFind::find_shorten) for a more efficient find.
-    pub fn find(&self, elem: &T) -> Option<T>
+    ///
+    /// Like [`HashMap::get`], the element can be given in any borrowed form of the element
+    /// type that the parent mapping supports, for example a `&str` for `String` elements.
+    pub fn find<Q: ?Sized>(&self, elem: &Q) -> Option<T>
+    where
+        T: Clone,
+        M: Lookup<T, T, Q>,
+    {
+        self.root_ref(elem).cloned()
+    }
+
+    /// The representative of an element, without cloning it or shortening paths.
+    fn root_ref<Q: ?Sized>(&self, elem: &Q) -> Option<&T>
+    where
+        M: Lookup<T, T, Q>,
+    {
+        let mut current = self.parent.lookup(elem)?;
+        loop {
+            let parent = self.parent.get(current)?;
+            if parent == current {
+                return Some(current);
+            }
+            current = parent;
+        }
+    }
+
+    /// Returns true if the element is in the union find.
+    pub fn contains<Q: ?Sized>(&self, elem: &Q) -> bool
+    where
+        M: Lookup<T, T, Q>,
+    {
+        self.parent.lookup(elem).is_some()
+    }
+
+    /// Returns whether two elements are in the same class, or `None` if either of
+    /// them is not in the union find. Performs no path shortening.
+    pub fn connected<Q: ?Sized>(&self, elem1: &Q, elem2: &Q) -> Option<bool>
+    where
+        M: Lookup<T, T, Q>,
+    {
+        Some(self.root_ref(elem1)? == self.root_ref(elem2)?)
+    }
+
+    /// Find an element in the union find. Performs path shortening,
+    /// which means you need mutable access to the union find.
+    ///
+    /// Use [`find`](UnionFind::find) for an immutable version.
+    ///
+    /// When a [compression budget](UnionFind::set_compression_budget) is set,
+    /// at most that many parent pointers are rewritten.
+    pub fn find_shorten<Q: ?Sized>(&mut self, elem: &Q) -> Option<T>
+    where
+        T: Clone,
+        M: Lookup<T, T, Q>,
+    {
+        let mut budget = self.compression_budget;
+        self.find_shorten_budgeted(elem, &mut budget)
+    }
+
+    /// Like [`find_shorten`](UnionFind::find_shorten), but rewrites at most `budget` parent
+    /// pointers, when a budget is given. The budget is shared between all finds of one operation.
+    fn find_shorten_budgeted<Q: ?Sized>(
+        &mut self,
+        elem: &Q,
+        budget: &mut Option<usize>,
+    ) -> Option<T>
+    where
+        T: Clone,
+        M: Lookup<T, T, Q>,
+    {
+        let root = self.root_ref(elem)?.clone();
+        // an owned element is only needed when its parent changes
+        if *budget == Some(0) || *self.parent.lookup(elem)? == root {
+            return Some(root);
+        }
+
+        // Only spend budget when the parent of an element would actually change. The budget is
+        // spent on the start of the path, since that is where later finds are likely to start.
+        let mut current = self.parent.lookup_key(elem)?;
+        while *budget != Some(0) {
+            let parent = self.parent.get(&current)?.clone();
+            if parent == root || current == root {
+                break;
+            }
+            if let Some(budget) = budget {
+                *budget -= 1;
+            }
+            // path shortening
+            self.parent.set(current, root.clone());
+            current = parent;
+        }
+        Some(root)
+    }
+
+    /// Find an element in the union find, making every other element on the path point to
+    /// its grandparent (path halving). Unlike [`find_shorten`](UnionFind::find_shorten),
+    /// this takes a single pass over the path, which is often faster on large union finds.
+    ///
+    /// When a [compression budget](UnionFind::set_compression_budget) is set,
+    /// at most that many parent pointers are rewritten.
+    pub fn find_halve(&mut self, elem: &T) -> Option<T>
+    where
+        T: Clone,
+    {
+        self.find_one_pass(elem, true)
+    }
+
+    /// Find an element in the union find, making every element on the path point to its
+    /// grandparent (path splitting). Like [`find_halve`](UnionFind::find_halve), this takes
+    /// a single pass over the path.
+    ///
+    /// When a [compression budget](UnionFind::set_compression_budget) is set,
+    /// at most that many parent pointers are rewritten.
+    pub fn find_split(&mut self, elem: &T) -> Option<T>
+    where
+        T: Clone,
+    {
+        self.find_one_pass(elem, false)
+    }
+
+    /// Walks from `elem` to its representative, pointing elements to their grandparent.
+    /// When halving, the walk continues at the grandparent, so only every other element
+    /// is rewritten. Otherwise, it continues at the old parent.
+    fn find_one_pass(&mut self, elem: &T, halve: bool) -> Option<T>
+    where
+        T: Clone,
+    {
+        let mut budget = self.compression_budget;
+        let mut current = elem.clone();
+        loop {
+            let parent = self.parent.get(&current)?.clone();
+            if parent == current {
+                return Some(current);
+            }
+            let grandparent = self.parent.get(&parent)?.clone();
+            if grandparent == parent {
+                return Some(parent);
+            }
+
+            if budget != Some(0) {
+                if let Some(budget) = &mut budget {
+                    *budget -= 1;
+                }
+                self.parent.set(current, grandparent.clone());
+            }
+            current = if halve { grandparent } else { parent };
+        }
+    }
+
+    /// Bounds the amount of path shortening done by a single operation, like
+    /// [`find_shorten`](UnionFind::find_shorten) or a union, to at most `budget` rewritten parent
+    /// pointers. The remaining shortening is deferred to later operations. This bounds the
+    /// worst case time of operations that shorten paths, at the cost of slower finds on average.
+    ///
+    /// `None` means path shortening is unbounded, which is the default.
+    pub fn set_compression_budget(&mut self, budget: Option<usize>) {
+        self.compression_budget = budget;
+    }
+
+    /// The current compression budget, see [`set_compression_budget`](UnionFind::set_compression_budget).
+    pub fn compression_budget(&self) -> Option<usize> {
+        self.compression_budget
+    }
+
+    /// The number of classes. This is maintained during additions and unions,
+    /// so takes constant time.
+    pub fn num_sets(&self) -> usize {
+        self.sets
+    }
+}
+
+impl<T: Hash + Eq, V, E, M: IterableMapping<T, T>, L> UnionFind<T, V, E, M, L> {
+    /// Gives mutable access to the mapping from every element to its parent. When the returned
+    /// guard is dropped, the [number of classes](UnionFind::num_sets) is counted again.
+    ///
+    /// This is an escape hatch. The mapping must keep forming a forest: every parent must be in
+    /// the mapping and following parents must always end at a representative. Otherwise, finds
+    /// may return wrong results or never terminate. Call [`validate`](UnionFind::validate)
+    /// afterwards when in doubt. Note that the extra information of the union find (like ranks)
+    /// is not updated.
+    pub fn raw_mut(&mut self) -> RawMut<'_, T, V, E, M, L> {
+        RawMut { uf: self }
+    }
+
+    /// Iterates over all elements in the class of `elem`, in arbitrary order, or returns `None`
+    /// if the element is not in the union find. This scans every element, so takes time linear
+    /// in the size of the union find. Use the [`Members`] extra to only visit the class itself,
+    /// see [`class_members`](UnionFind::class_members).
+    pub fn members_of<'a>(&'a self, elem: &T) -> Option<impl Iterator<Item = T> + 'a> {
+        let root = self.root_ref(elem)?;
+        Some(
+            self.keys()
+                .filter(move |member| self.root_ref(member) == Some(root)),
+        )
+    }
+
+    /// Iterates over all elements in the union find, in arbitrary order.
+    pub fn keys(&self) -> impl Iterator<Item = T> + '_ {
+        self.parent.entries().map(|(elem, _)| elem)
+    }
+
+    /// Iterates over all elements together with their current parent, in arbitrary order.
+    /// Note that the parent of an element is not necessarily its representative.
+    pub fn parents(&self) -> impl Iterator<Item = (T, &T)> + '_ {
+        self.parent.entries()
+    }
+
+    /// Iterates over all elements together with their representative, in arbitrary order.
+    /// Performs no path shortening.
+    pub fn iter(&self) -> Iter<'_, T, M> {
+        Iter {
+            parents: Box::new(self.parent.entries()),
+            parent: &self.parent,
+        }
+    }
+
+    /// Iterates over the representative of every class, in arbitrary order.
+    pub fn roots(&self) -> impl Iterator<Item = &T> + '_ {
+        self.parent
+            .entries()
+            .filter(|(elem, parent)| elem == *parent)
+            .map(|(_, root)| root)
+    }
+}
+
+impl<T: Hash + Eq, V, E, M: GrowableMapping<T, T>, L> UnionFind<T, V, E, M, L> {
+    /// The number of elements in the union find.
+    pub fn len(&self) -> usize {
+        self.parent.len()
+    }
+
+    /// Returns true if the union find contains no elements.
+    pub fn is_empty(&self) -> bool {
+        self.parent.is_empty()
+    }
+}
+
+/// Mutable access to the parent mapping of a union find, returned by
+/// [`raw_mut`](UnionFind::raw_mut). Counts the classes again when dropped.
+pub struct RawMut<'a, T: Hash + Eq, V, E, M: IterableMapping<T, T>, L> {
+    uf: &'a mut UnionFind<T, V, E, M, L>,
+}
+
+impl<T: Hash + Eq, V, E, M: IterableMapping<T, T>, L> Deref for RawMut<'_, T, V, E, M, L> {
+    type Target = M;
+
+    fn deref(&self) -> &M {
+        &self.uf.parent
+    }
+}
+
+impl<T: Hash + Eq, V, E, M: IterableMapping<T, T>, L> DerefMut for RawMut<'_, T, V, E, M, L> {
+    fn deref_mut(&mut self) -> &mut M {
+        &mut self.uf.parent
+    }
+}
+
+impl<T: Hash + Eq, V, E, M: IterableMapping<T, T>, L> Drop for RawMut<'_, T, V, E, M, L> {
+    fn drop(&mut self) {
+        self.uf.sets = self
+            .uf
+            .parent
+            .entries()
+            .filter(|(elem, parent)| elem == *parent)
+            .count();
+    }
+}
+
+/// Iterator over every element of a union find together with its representative,
+/// returned by [`iter`](UnionFind::iter).
+pub struct Iter<'a, T, M = HashMap<T, T>> {
+    parents: Box<dyn Iterator<Item = (T, &'a T)> + 'a>,
+    parent: &'a M,
+}
+
+impl<'a, T: Eq, M: Mapping<T, T>> Iterator for Iter<'a, T, M> {
+    type Item = (T, &'a T);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let (elem, mut root) = self.parents.next()?;
+        loop {
+            let parent = self.parent.get(root).expect("parent is in the union find");
+            if parent == root {
+                return Some((elem, root));
+            }
+            root = parent;
+        }
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.parents.size_hint()
+    }
+}
+
+impl<'a, T: Hash + Eq, V, E, M: IterableMapping<T, T>, L> IntoIterator
+    for &'a UnionFind<T, V, E, M, L>
+{
+    type Item = (T, &'a T);
+    type IntoIter = Iter<'a, T, M>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+/// Consumes the union find, yielding every element together with its representative.
+impl<T: Hash + Eq + Clone, V, E, M: IterableMapping<T, T>, L> IntoIterator
+    for UnionFind<T, V, E, M, L>
+{
+    type Item = (T, T);
+    type IntoIter = std::vec::IntoIter<(T, T)>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+            .map(|(elem, root)| (elem, root.clone()))
+            .collect::<Vec<_>>()
+            .into_iter()
+    }
+}
+
+/// Collects elements into a union find where every element is in a class on its own.
+///
+/// # Panics
+///
+/// Panics if the extra information can't be constructed for the elements. Use
+/// [`try_new`](UnionFind::try_new) to get the error instead.
+impl<T: Hash + Eq + Clone, V, E: Extra<T, V>, L> FromIterator<T>
+    for UnionFind<T, V, E, HashMap<T, T>, L>
+{
+    fn from_iter<I: IntoIterator<Item = T>>(elems: I) -> Self {
+        let parent: HashMap<T, T> = elems.into_iter().map(|elem| (elem.clone(), elem)).collect();
+        let extra =
+            E::default_mapping(parent.keys().cloned()).expect("couldn't construct extra mapping");
+        Self::from_raw_parts_unchecked(parent, extra)
+    }
+}
+
+/// Adds elements that are not in the union find yet, each in a class on its own.
+///
+/// # Panics
+///
+/// Panics if an element can't be added to the extra information. Use
+/// [`add`](UnionFind::add) to get the error instead.
+impl<T: Hash + Eq + Clone, V: Default, E: GrowableExtra<T, V>, L> Extend<T>
+    for UnionFind<T, V, E, HashMap<T, T>, L>
+{
+    fn extend<I: IntoIterator<Item = T>>(&mut self, elems: I) {
+        for elem in elems {
+            if !self.parent.contains_key(&elem) {
+                self.add(elem)
+                    .expect("couldn't add element to the extra information");
+            }
+        }
+    }
+}
+
+/// Unions every pair of elements by rank, adding elements that are not present yet.
+///
+/// # Panics
+///
+/// Panics if the representatives of the classes of a pair are both [pinned](UnionFind::pin).
+impl<T: Hash + Eq + Clone, L> Extend<(T, T)> for UnionFind<T, usize, ByRank<T>, HashMap<T, T>, L> {
+    fn extend<I: IntoIterator<Item = (T, T)>>(&mut self, pairs: I) {
+        for (a, b) in pairs {
+            self.union_adding(a, b);
+        }
+    }
+}
+
+/// `uf[&elem]` returns the representative of `elem`, like [`find`](UnionFind::find) but
+/// without cloning it.
+///
+/// # Panics
+///
+/// Panics if `elem` is not in the union find.
+impl<T: Hash + Eq, V, E, M: Mapping<T, T>, L, Q: ?Sized> Index<&Q> for UnionFind<T, V, E, M, L>
+where
+    M: Lookup<T, T, Q>,
+{
+    type Output = T;
+
+    fn index(&self, elem: &Q) -> &T {
+        self.root_ref(elem).expect("element is not in the union find")
+    }
+}
+
+/// Returned by [`validate`](UnionFind::validate) when the parent mapping of a union find
+/// does not form a forest.
+#[derive(Debug, Error, PartialEq, Eq)]
+pub enum InvalidStructure<T> {
+    #[error("an element has a parent that is not in the union find")]
+    DanglingParent { elem: T, parent: T },
+
+    #[error("an element is part of a cycle of parents")]
+    Cycle { elem: T },
+}
+
+/// Checks that a parent mapping forms a forest.
+fn validate_parents<T: Hash + Eq + Clone, M: IterableMapping<T, T>>(
+    parents: &M,
+) -> Result<(), InvalidStructure<T>> {
+    // elements that are known to lead to a representative
+    let mut valid = HashSet::new();
+    let mut path = HashSet::new();
+
+    for (elem, parent) in parents.entries() {
+        let mut current = elem;
+        let mut parent = parent;
+        while !valid.contains(&current) {
+            if !path.insert(current.clone()) {
+                return Err(InvalidStructure::Cycle { elem: current });
+            }
+
+            if *parent == current {
+                break;
+            }
+            let Some(grandparent) = parents.get(parent) else {
+                return Err(InvalidStructure::DanglingParent {
+                    elem: current,
+                    parent: parent.clone(),
+                });
+            };
+            current = parent.clone();
+            parent = grandparent;
+        }
+
+        valid.extend(path.drain());
+    }
+
+    Ok(())
+}
+
+/// The number of representatives in a parent mapping.
+fn count_roots<T: Eq, M: IterableMapping<T, T>>(parents: &M) -> usize {
+    parents.entries().filter(|(elem, parent)| elem == *parent).count()
+}
+
+/// The serialized fields of a [`UnionFind`]. Deserializing goes through this struct so that
+/// information which is not serialized, like the number of classes, can be recomputed.
+#[derive(Deserialize)]
+#[serde(bound(deserialize = "T: Deserialize<'de>, E: Deserialize<'de>, M: GrowableMapping<T, T>"))]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+#[cfg_attr(feature = "schemars", schemars(rename = "UnionFind", bound = "T: schemars::JsonSchema, E: schemars::JsonSchema"))]
+struct UnionFindData<T: Hash + Eq, V, E, M> {
+    #[serde(deserialize_with = "deserialize_entries")]
+    #[cfg_attr(feature = "schemars", schemars(with = "Vec<(T, T)>"))]
+    parent: M,
+    extra: E,
+    #[serde(default)]
+    compression_budget: Option<usize>,
+    #[serde(default)]
+    pinned: HashSet<T>,
+    phantom: PhantomData<(T, V)>,
+}
+
+/// Checks that the deserialized parent mapping forms a forest, since finds would not terminate
+/// on a cycle of parents.
+impl<T: Hash + Eq + Clone, V, E, M: IterableMapping<T, T>, L> TryFrom<UnionFindData<T, V, E, M>>
+    for UnionFind<T, V, E, M, L>
+{
+    type Error = InvalidStructure<T>;
+
+    fn try_from(data: UnionFindData<T, V, E, M>) -> Result<Self, Self::Error> {
+        validate_parents(&data.parent)?;
+        Ok(Self {
+            sets: count_roots(&data.parent),
+            parent: data.parent,
+            extra: data.extra,
+            compression_budget: data.compression_budget,
+            watchers: Default::default(),
+            pinned: data.pinned,
+            union_tree: None,
+            phantom: data.phantom,
+        })
+    }
+}
+
+impl<T: Hash + Eq, V, E, M, L> UnionFind<T, V, E, M, L> {
+    /// Gives read access to the mapping from every element to its parent.
+    /// Elements that are their own parent are representatives.
+    pub fn raw(&self) -> &M {
+        &self.parent
+    }
+
+    /// Checks that the parent mapping forms a forest, which is always the case unless
+    /// it was modified through [`raw_mut`](UnionFind::raw_mut).
+    pub fn validate(&self) -> Result<(), InvalidStructure<T>>
     where
         T: Clone,
+        M: IterableMapping<T, T>,
     {
-        let parent = self.parent.get(elem)?.clone();
-        if &parent == elem {
-            Some(parent)
+        validate_parents(&self.parent)
+    }
+
+    /// Splits the union find into its parent mapping and extra information.
+    pub fn into_raw_parts(self) -> (M, E) {
+        (self.parent, self.extra)
+    }
+
+    /// Builds a union find from a parent mapping and extra information,
+    /// after checking that the parent mapping forms a forest.
+    pub fn from_raw_parts(parent: M, extra: E) -> Result<Self, InvalidStructure<T>>
+    where
+        T: Clone,
+        M: IterableMapping<T, T>,
+    {
+        validate_parents(&parent)?;
+        Ok(Self::from_raw_parts_unchecked(parent, extra))
+    }
+
+    /// Like [`from_raw_parts`](UnionFind::from_raw_parts), for a parent mapping that is
+    /// known to form a forest.
+    pub(crate) fn from_raw_parts_unchecked(parent: M, extra: E) -> Self
+    where
+        M: IterableMapping<T, T>,
+    {
+        Self {
+            sets: count_roots(&parent),
+            parent,
+            extra,
+            compression_budget: None,
+            watchers: Default::default(),
+            pinned: Default::default(),
+            union_tree: None,
+            phantom: Default::default(),
+        }
+    }
+}
+
+impl<T: Hash + Eq + Clone, V, E: RecomputeExtra<T>, M, L> UnionFind<T, V, E, M, L>
+where
+    M: IterableMapping<T, T>,
+{
+    /// Serializes only the parent mapping, which is usually much smaller than the complete
+    /// union find. Can be used in a `#[serde(serialize_with = "...")]` attribute.
+    ///
+    /// Use [`deserialize_without_extra`](UnionFind::deserialize_without_extra) to load the
+    /// union find again. The extra information is then recomputed, and the compression budget
+    /// is reset.
+    pub fn serialize_without_extra<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        T: Serialize,
+    {
+        serialize_entries(&self.parent, serializer)
+    }
+
+    /// Deserializes a union find serialized with
+    /// [`serialize_without_extra`](UnionFind::serialize_without_extra), and recomputes its
+    /// extra information. Fails if the parent mapping does not form a forest.
+    pub fn deserialize_without_extra<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<Self, D::Error>
+    where
+        T: Deserialize<'de>,
+        M: GrowableMapping<T, T>,
+    {
+        let parent: M = deserialize_entries(deserializer)?;
+        validate_parents(&parent).map_err(D::Error::custom)?;
+        let extra = E::recompute(&parent);
+        Ok(Self::from_raw_parts_unchecked(parent, extra))
+    }
+
+    /// A new union find containing only the given keys, where two keys are equivalent if they
+    /// are equivalent in this union find. Keys that are not in this union find are skipped.
+    /// The new union find stores its parents in a `HashMap`, since the keys may not fit the
+    /// parent mapping of this one, like a `Vec` that needs consecutive keys.
+    ///
+    /// Every key points directly to the representative of its class. A representative that is
+    /// among the keys stays the representative, otherwise one of the keys of the class takes
+    /// its place. The extra information is recomputed, and pins and watched elements are not
+    /// carried over.
+    pub fn restrict(&self, keys: impl IntoIterator<Item = T>) -> UnionFind<T, V, E> {
+        let roots: HashMap<T, T> = keys
+            .into_iter()
+            .filter_map(|key| Some((key.clone(), self.find(&key)?)))
+            .collect();
+
+        let mut new_roots: HashMap<&T, &T> = HashMap::new();
+        for (key, root) in &roots {
+            let new_root = new_roots.entry(root).or_insert(key);
+            if roots.contains_key(root) {
+                *new_root = root;
+            }
+        }
+
+        let parent: HashMap<T, T> = roots
+            .iter()
+            .map(|(key, root)| (key.clone(), new_roots[root].clone()))
+            .collect();
+        let extra = E::recompute(&parent);
+        UnionFind::from_raw_parts_unchecked(parent, extra)
+    }
+
+    /// Like [`compress_all`](UnionFind::compress_all), but also makes the smallest element of
+    /// every class its representative, unless the representative is
+    /// [pinned](UnionFind::pin). Afterwards, the parent mapping only depends on the partition
+    /// and the pins, and not on the order of the unions, which makes snapshots deterministic.
+    ///
+    /// The extra information is recomputed. Watched elements report a change of
+    /// representative for every class whose representative changed.
+    pub fn canonicalize(&mut self)
+    where
+        T: Ord,
+    {
+        let mut smallest: HashMap<T, T> = HashMap::with_capacity(self.sets);
+        for (elem, root) in self.iter() {
+            let min = smallest
+                .entry(root.clone())
+                .or_insert_with(|| root.clone());
+            if elem < *min && !self.pinned.contains(root) {
+                *min = elem;
+            }
+        }
+
+        for (root, min) in &smallest {
+            if root != min {
+                self.watchers.root_changed(root, min);
+            }
+        }
+        let parent: Vec<(T, T)> = self
+            .iter()
+            .map(|(elem, root)| (elem, smallest[root].clone()))
+            .collect();
+        for (elem, root) in parent {
+            self.parent.set(elem, root);
+        }
+        self.extra = E::recompute(&self.parent);
+    }
+}
+
+impl<T: Hash + Eq + Clone, V, E, M: IterableMapping<T, T>, L> UnionFind<T, V, E, M, L> {
+    /// Groups all elements by class, keyed by the representative of the class.
+    /// Elements within a class are in arbitrary order. Performs no path shortening.
+    pub fn classes(&self) -> HashMap<T, Vec<T>> {
+        let mut classes: HashMap<T, Vec<T>> = HashMap::with_capacity(self.sets);
+        for (elem, root) in self.iter() {
+            classes.entry(root.clone()).or_default().push(elem);
+        }
+        classes
+    }
+
+    /// Consumes the union find, returning the elements of every class. Classes and
+    /// elements within a class are in arbitrary order.
+    pub fn into_classes(self) -> Vec<Vec<T>> {
+        let mut index: HashMap<T, usize> = HashMap::with_capacity(self.sets);
+        let mut classes: Vec<Vec<T>> = Vec::with_capacity(self.sets);
+        for (elem, root) in self {
+            let next = classes.len();
+            let class = *index.entry(root).or_insert(next);
+            if class == next {
+                classes.push(Vec::new());
+            }
+            classes[class].push(elem);
+        }
+        classes
+    }
+
+    /// Points every element directly to the representative of its class, so that every
+    /// later find takes a single step, also through an immutable reference.
+    pub fn compress_all(&mut self) {
+        let roots: Vec<(T, T)> = self
+            .iter()
+            .filter(|(elem, root)| self.parent.get(elem) != Some(*root))
+            .map(|(elem, root)| (elem, root.clone()))
+            .collect();
+        for (elem, root) in roots {
+            self.parent.set(elem, root);
+        }
+    }
+
+    /// Relabels every element with `f`, keeping the partition. `f` is called once for every
+    /// element. The extra information is recomputed for the new keys, and pins and watched
+    /// elements are not carried over. The new union find stores its parents in a `HashMap`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `f` maps two elements to the same key.
+    pub fn map_keys<U, E2>(self, f: impl Fn(T) -> U) -> UnionFind<U, V, E2>
+    where
+        U: Hash + Eq + Clone,
+        E2: RecomputeExtra<U>,
+    {
+        let keys: HashMap<T, U> = self
+            .keys()
+            .map(|elem| (elem.clone(), f(elem)))
+            .collect();
+        let parent: HashMap<U, U> = self
+            .parent
+            .entries()
+            .map(|(elem, parent)| (keys[&elem].clone(), keys[parent].clone()))
+            .collect();
+        assert_eq!(parent.len(), keys.len(), "two elements were mapped to the same key");
+        let extra = E2::recompute(&parent);
+        UnionFind::from_raw_parts_unchecked(parent, extra)
+    }
+}
+
+impl<T: Hash + Eq + Clone, V, E, M: Mapping<T, T>, L> UnionFind<T, V, E, M, L> {
+    /// Pins the representative of the class of `elem`, and returns it. A pinned representative
+    /// remains the representative of its class, however many classes are merged into it,
+    /// until it is released with [`unpin`](UnionFind::unpin). This is useful when
+    /// representatives are used as keys elsewhere.
+    ///
+    /// Unions of two classes with pinned representatives fail.
+    /// Returns `None` if the element is not in the union find.
+    pub fn pin(&mut self, elem: &T) -> Option<T> {
+        let root = self.find_shorten(elem)?;
+        self.pinned.insert(root.clone());
+        Some(root)
+    }
+
+    /// Releases a representative pinned with [`pin`](UnionFind::pin).
+    /// Returns false if it was not pinned.
+    pub fn unpin(&mut self, root: &T) -> bool {
+        self.pinned.remove(root)
+    }
+
+    pub fn is_pinned(&self, root: &T) -> bool {
+        self.pinned.contains(root)
+    }
+
+    /// Start remembering which unions merged two classes, so that
+    /// [`path_between`](UnionFind::path_between) can explain why two elements are equivalent.
+    /// This takes memory linear in the number of elements.
+    ///
+    /// Only unions from now on are remembered, so it is best to call this on a new union find.
+    pub fn track_paths(&mut self) {
+        if self.union_tree.is_none() {
+            self.union_tree = Some(SpanningForest::new());
+        }
+    }
+
+    /// Returns the elements on a path from `a` to `b`, starting with `a` and ending with `b`,
+    /// where every two consecutive elements were passed to a union that merged their classes.
+    ///
+    /// Returns `None` if the elements are not equivalent, if paths are not tracked, or if
+    /// the elements only became equivalent through unions made before
+    /// [`track_paths`](UnionFind::track_paths) was called.
+    pub fn path_between(&self, a: &T, b: &T) -> Option<Vec<T>> {
+        self.union_tree.as_ref()?.path_elements(a, b)
+    }
+
+    /// Returns the unions that made `a` and `b` equivalent, as the pairs of elements that were
+    /// passed to them, in the order of the path from `a` to `b`. No union can be left out,
+    /// and the result does not depend on path shortening.
+    ///
+    /// Like [`path_between`](UnionFind::path_between), this needs
+    /// [`track_paths`](UnionFind::track_paths), and returns `None` if the elements are not
+    /// equivalent through unions that were tracked.
+    pub fn explain(&self, a: &T, b: &T) -> Option<Vec<(T, T)>> {
+        let edges = self.union_tree.as_ref()?.path_edges_with_endpoints(a, b)?;
+        Some(
+            edges
+                .into_iter()
+                .map(|(elem1, elem2, _)| (elem1.clone(), elem2.clone()))
+                .collect(),
+        )
+    }
+
+    /// Returns the labels of the labeled unions on the path from `a` to `b`, in the order of
+    /// the path, see [`union_by_labeled`](UnionFind::union_by_labeled). Unions on the path that
+    /// were not labeled are left out.
+    ///
+    /// Like [`path_between`](UnionFind::path_between), this returns `None` if the elements
+    /// are not equivalent through unions that were tracked.
+    pub fn labels_between(&self, a: &T, b: &T) -> Option<Vec<&L>> {
+        let labels = self.union_tree.as_ref()?.path(a, b)?;
+        Some(labels.into_iter().flatten().collect())
+    }
+
+    /// Remembers a union of two elements, if paths are tracked and it merged two classes.
+    pub(crate) fn record_union<Q: ?Sized>(&mut self, elem1: &Q, elem2: &Q, status: UnionStatus)
+    where
+        M: Lookup<T, T, Q>,
+    {
+        if let (Some(tree), UnionStatus::PerformedUnion) = (&mut self.union_tree, status) {
+            let elem1 = self.parent.lookup_key(elem1).expect("elem1 is in the union find");
+            let elem2 = self.parent.lookup_key(elem2).expect("elem2 is in the union find");
+            tree.add_edge(elem1, elem2, None);
+        }
+    }
+
+    /// Labels the union that was recorded last, if it merged two classes.
+    fn label_last_union(&mut self, status: UnionStatus, label: L) {
+        if status == UnionStatus::PerformedUnion {
+            let tree = self.union_tree.as_mut().expect("paths are tracked");
+            *tree.last_label_mut().expect("the union was recorded") = Some(label);
+        }
+    }
+
+    /// Start watching an element. Whenever the representative of the element changes,
+    /// because its class is merged into another class, a [`RepresentativeChange`] is reported
+    /// through [`drain_representative_changes`](UnionFind::drain_representative_changes).
+    ///
+    /// Returns false if the element is not in the union find.
+    pub fn watch(&mut self, elem: &T) -> bool {
+        let Some(root) = self.find_shorten(elem) else {
+            return false;
+        };
+        self.watchers.watch(elem.clone(), root);
+        true
+    }
+
+    /// Stop watching an element. Returns false if the element was not watched.
+    pub fn unwatch(&mut self, elem: &T) -> bool {
+        let Some(root) = self.find_shorten(elem) else {
+            return false;
+        };
+        self.watchers.unwatch(elem, &root)
+    }
+
+    /// Returns all changes of representatives of watched elements since the last call.
+    pub fn drain_representative_changes(&mut self) -> Drain<'_, RepresentativeChange<T>> {
+        self.watchers.drain_changes()
+    }
+}
+
+#[derive(Error, Debug, PartialEq)]
+pub enum SetRepresentativeError {
+    #[error("the element was not found in the union find")]
+    NotFound,
+
+    #[error("the representative of the class is pinned")]
+    Pinned,
+}
+
+impl<T: Hash + Eq + Clone, V, E: RerootExtra<T>, M: Mapping<T, T>, L> UnionFind<T, V, E, M, L> {
+    /// Makes `elem` the representative of its class, and returns the previous representative.
+    /// The previous representative then points to `elem`, and the extra information of the
+    /// class moves to `elem` using [`RerootExtra`]. Watched elements in the class report the
+    /// change of representative.
+    ///
+    /// Fails if the representative is [pinned](UnionFind::pin) and is not `elem`.
+    pub fn set_representative(&mut self, elem: &T) -> Result<T, SetRepresentativeError> {
+        let root = self
+            .find_shorten(elem)
+            .ok_or(SetRepresentativeError::NotFound)?;
+        if root == *elem {
+            return Ok(root);
+        }
+        if self.pinned.contains(&root) {
+            return Err(SetRepresentativeError::Pinned);
+        }
+
+        self.parent.set(elem.clone(), elem.clone());
+        self.parent.set(root.clone(), elem.clone());
+        self.extra.reroot(&root, elem);
+        self.watchers.root_changed(&root, elem);
+        Ok(root)
+    }
+}
+
+impl<T: Hash + Eq + Clone, V, E: ResetExtra, M, L> UnionFind<T, V, E, M, L>
+where
+    M: GrowableMapping<T, T> + IterableMapping<T, T>,
+{
+    /// Dissolves all unions, so every element is in a class on its own again, as if the
+    /// union find was just created from the same elements. Unlike creating a new union find,
+    /// this keeps the memory that was already allocated.
+    ///
+    /// Watched elements that were not their own representative report a change of
+    /// representative to themselves. Pinned representatives stay pinned, and remembered
+    /// [paths](UnionFind::track_paths) are forgotten.
+    pub fn reset_to_singletons(&mut self) {
+        let elems: Vec<T> = self.keys().collect();
+        for elem in elems {
+            self.parent.set(elem.clone(), elem);
+        }
+        self.sets = self.parent.len();
+        self.extra.reset();
+        self.watchers.reset();
+        if self.union_tree.is_some() {
+            self.union_tree = Some(SpanningForest::new());
+        }
+    }
+}
+
+impl<T: Hash + Eq + Clone, V, E: RemoveExtra<T>, M, L> UnionFind<T, V, E, M, L>
+where
+    M: IterableMapping<T, T> + RemovableMapping<T, T>,
+{
+    /// Removes an element, keeping the other members of its class together. Elements whose
+    /// parent was `elem` get the parent of `elem` instead. When `elem` was the representative,
+    /// one of them becomes the new representative, and inherits the extra information of the
+    /// class. Returns false if the element is not in the union find.
+    ///
+    /// Finding the elements whose parent was `elem` takes time linear in the size of the union
+    /// find. Watched elements in the class report a change of representative when the
+    /// representative is removed, and a removed representative is no longer pinned.
+    /// Remembered [paths](UnionFind::track_paths) keep going through removed elements.
+    pub fn remove(&mut self, elem: &T) -> bool {
+        let Some(root) = self.find(elem) else {
+            return false;
+        };
+        let children: Vec<T> = self
+            .parent
+            .entries()
+            .filter(|(child, parent)| *parent == elem && child != elem)
+            .map(|(child, _)| child)
+            .collect();
+
+        self.watchers.unwatch(elem, &root);
+        let new_root = if *elem == root {
+            self.pinned.remove(elem);
+            let new_root = children.first().cloned();
+            match &new_root {
+                Some(new_root) => self.watchers.root_changed(elem, new_root),
+                None => self.sets -= 1,
+            }
+            new_root
+        } else {
+            Some(root.clone())
+        };
+        let new_parent = if *elem == root {
+            new_root.clone()
         } else {
-            let new_parent = self.find(&parent)?;
-            Some(new_parent)
+            self.parent.get(elem).cloned()
+        };
+        if let Some(new_parent) = new_parent {
+            for child in children {
+                self.parent.set(child, new_parent.clone());
+            }
         }
+
+        self.parent.remove(elem);
+        self.extra.remove(elem, &root, new_root.as_ref());
+        true
     }
 
-    /// Find an element in the union find. Performs path shortening,
-    /// which means you need mutable access to the union find.
+    /// Removes all elements for which `keep` returns false, keeping the remaining members of
+    /// every class together. Like [`remove`](UnionFind::remove), but takes time linear in the
+    /// size of the union find, however many elements are removed. `keep` is called once for
+    /// every element, in arbitrary order. The extra information is updated as if every element
+    /// was removed with `remove`, so for some extras, like [`Members`], this is not linear.
     ///
-    /// Use [`find`](UnionFind::find) for an immutable version.
-    pub fn find_shorten(&mut self, elem: &T) -> Option<T>
-    where
-        T: Clone,
-    {
-        let parent = self.parent.get(elem)?.clone();
-        if &parent == elem {
-            Some(parent)
-        } else {
-            let new_parent = self.find_shorten(&parent)?;
-            // path shortening
-            self.parent.set(elem.clone(), new_parent.clone());
-            Some(new_parent)
+    /// Remaining elements point directly to the representative of their class afterwards.
+    /// When a representative is removed, another remaining member of its class becomes the
+    /// representative.
+    pub fn retain(&mut self, mut keep: impl FnMut(&T) -> bool) {
+        let roots: Vec<(T, T)> = self
+            .iter()
+            .map(|(elem, root)| (elem, root.clone()))
+            .collect();
+        let removed: HashSet<T> = roots
+            .iter()
+            .filter(|(elem, _)| !keep(elem))
+            .map(|(elem, _)| elem.clone())
+            .collect();
+        if removed.is_empty() {
+            return;
+        }
+
+        // the representative of every class after the removal, if any member remains
+        let mut new_roots: HashMap<T, Option<T>> = HashMap::new();
+        for (elem, root) in &roots {
+            let new_root = new_roots.entry(root.clone()).or_default();
+            if !removed.contains(root) {
+                *new_root = Some(root.clone());
+            } else if new_root.is_none() && !removed.contains(elem) {
+                *new_root = Some(elem.clone());
+            }
+        }
+
+        for (elem, root) in &roots {
+            if removed.contains(elem) {
+                self.watchers.unwatch(elem, root);
+                self.parent.remove(elem);
+                if elem != root {
+                    self.extra.remove(elem, root, Some(root));
+                }
+            } else {
+                let new_root = new_roots[root].clone().expect("a member remains");
+                self.parent.set(elem.clone(), new_root);
+            }
+        }
+        for (root, new_root) in &new_roots {
+            if !removed.contains(root) {
+                continue;
+            }
+            self.pinned.remove(root);
+            self.extra.remove(root, root, new_root.as_ref());
+            match new_root {
+                Some(new_root) => self.watchers.root_changed(root, new_root),
+                None => self.sets -= 1,
+            }
         }
     }
 }
 
+impl<T: Hash + Eq + Clone, V, E: UndoExtra<T>, M: GrowableMapping<T, T>> UnionFind<T, V, E, M> {
+    /// Starts a [`Transaction`], of which all additions and unions can be undone at once.
+    pub fn begin(&mut self) -> Transaction<'_, T, V, E, M> {
+        Transaction::new(self)
+    }
+}
+
 #[derive(Error, Debug)]
 pub enum UnionOrAddError<Err, T, V, M: GrowableMapping<T, T>, E: GrowableExtra<T, V>> {
     #[error(transparent)]
@@ -107,7 +1216,13 @@ pub enum UnionOrAddError<Err, T, V, M: GrowableMapping<T, T>, E: GrowableExtra<T
     NotUnionable(Err),
 }
 
-impl<T: Hash+Eq, V, E> UnionFind<T, V, E>
+pub(crate) type UnionByOrAddError<U, T, V, E, M = HashMap<T, T>> =
+    UnionOrAddError<UnionByError<U, T, E>, T, V, M, E>;
+
+pub(crate) type UnionByRankOrAddError<T, R, M> =
+    UnionOrAddError<UnionByRankError, T, usize, M, ByRank<T, R>>;
+
+impl<T: Hash+Eq, V, E, M: GrowableMapping<T, T>, L> UnionFind<T, V, E, M, L>
 where
     E: GrowableExtra<T, V>,
     V: Default,
@@ -116,24 +1231,92 @@ where
     /// but can be used through an immutable reference.
     /// If the element was not present in the unionfind previously, add it.
     ///
-    /// Use [`find_shorten`](UnionFind::find_shorten_or_add) for a more efficient find.
-    pub fn find_or_add(&mut self, elem: &T) -> Result<T, ()>
+    /// Use [`find_shorten_or_add`](UnionFind::find_shorten_or_add) for a more efficient find.
+    pub fn find_or_add(&mut self, elem: &T) -> Result<T, AddErrorSimple<T, V, M, E>>
     where
         T: Clone,
     {
         match self.find(elem) {
             Some(i) => Ok(i),
             None => {
-                self.add(elem.clone()).unwrap();
+                self.add(elem.clone())?;
+                Ok(elem.clone())
+            }
+        }
+    }
+
+    /// Find an element in the union find, shortening the path to its representative
+    /// like [`find_shorten`](UnionFind::find_shorten).
+    /// If the element was not present in the unionfind previously, add it.
+    pub fn find_shorten_or_add(&mut self, elem: &T) -> Result<T, AddErrorSimple<T, V, M, E>>
+    where
+        T: Clone,
+    {
+        match self.find_shorten(elem) {
+            Some(i) => Ok(i),
+            None => {
+                self.add(elem.clone())?;
                 Ok(elem.clone())
             }
         }
     }
+
+    fn add_if_missing(&mut self, elem: &T) -> Result<(), AddErrorSimple<T, V, M, E>>
+    where
+        T: Clone,
+    {
+        if self.parent.get(elem).is_none() {
+            self.add(elem.clone())?;
+        }
+        Ok(())
+    }
+
+    /// Union two elements like [`union_by`](UnionFind::union_by), first adding the elements
+    /// that are not in the union find yet, each in a class on its own.
+    pub fn union_or_add<U: Union<T>>(
+        &mut self,
+        elem1: &T,
+        elem2: &T,
+        union: U,
+    ) -> Result<UnionStatus, UnionByOrAddError<U, T, V, E, M>>
+    where
+        T: Clone,
+        E: MergeExtra<T>,
+    {
+        self.add_if_missing(elem1)
+            .map_err(UnionOrAddError::AddError)?;
+        self.add_if_missing(elem2)
+            .map_err(UnionOrAddError::AddError)?;
+        self.union_by(elem1, elem2, union)
+            .map_err(UnionOrAddError::NotUnionable)
+    }
+}
+
+impl<T, R, M, L> UnionFind<T, usize, ByRank<T, R>, M, L>
+where
+    T: Hash + Eq + Clone,
+    R: GrowableMapping<T, usize>,
+    M: GrowableMapping<T, T>,
+{
+    /// Union two elements by rank, first adding the elements that are not in the union find
+    /// yet, each in a class on its own.
+    pub fn union_by_rank_or_add(
+        &mut self,
+        elem1: &T,
+        elem2: &T,
+    ) -> Result<UnionStatus, UnionByRankOrAddError<T, R, M>> {
+        self.add_if_missing(elem1)
+            .map_err(UnionOrAddError::AddError)?;
+        self.add_if_missing(elem2)
+            .map_err(UnionOrAddError::AddError)?;
+        self.union_by_rank(elem1, elem2)
+            .map_err(UnionOrAddError::NotUnionable)
+    }
 }
 
 
 #[derive(Error, Debug)]
-pub enum UnionError<Err> {
+pub enum UnionError<Err, ExtraErr = Infallible> {
     #[error("the first element given as an argument to union was not found in the union find")]
     Elem1NotFound,
 
@@ -142,8 +1325,33 @@ pub enum UnionError<Err> {
 
     #[error("could not union elements")]
     NotUnionable(Err),
+
+    #[error("could not merge the extra information of the two classes")]
+    ExtraNotMergeable(ExtraErr),
+
+    #[error("the representatives of both classes are pinned")]
+    BothPinned,
+}
+
+/// The representatives of both classes of a union are [pinned](UnionFind::pin), so neither
+/// can stop being a representative.
+pub(crate) struct BothPinned;
+
+impl<Err, ExtraErr> From<BothPinned> for UnionError<Err, ExtraErr> {
+    fn from(_: BothPinned) -> Self {
+        UnionError::BothPinned
+    }
+}
+
+impl From<BothPinned> for UnionByRankError {
+    fn from(_: BothPinned) -> Self {
+        UnionByRankError::BothPinned
+    }
 }
 
+pub(crate) type UnionByError<U, T, E> =
+    UnionError<<U as Union<T>>::Err, <E as MergeExtra<T>>::MergeError>;
+
 /// When a union is made, there is a possibility that the two classes
 /// were already unioned before. This enum is returned to disambiguate the two cases.
 #[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Clone, Copy)]
@@ -154,73 +1362,461 @@ pub enum UnionStatus {
     PerformedUnion,
 }
 
-impl<T: Hash+Eq, V, E> UnionFind<T, V, E>
+/// The result of [`union_by_get_root`](UnionFind::union_by_get_root) and
+/// [`union_by_rank_get_root`](UnionFind::union_by_rank_get_root).
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub struct RootedUnion<T> {
+    pub status: UnionStatus,
+    /// The representative of the class containing both elements after the union.
+    pub root: T,
+}
+
+impl<T: Hash+Eq, V, E, M: Mapping<T, T>, L> UnionFind<T, V, E, M, L>
 {
-    fn union_helper<U: Union<T>>(
+    pub(crate) fn union_helper<U: Union<T>>(
         &mut self,
         parent1: T,
         parent2: T,
         union: U,
-    ) -> Result<UnionStatus, U::Err>
+    ) -> Result<UnionStatus, UnionError<U::Err, E::MergeError>>
     where
         T: Clone,
+        E: MergeExtra<T>,
     {
         if parent1 == parent2 {
             return Ok(UnionStatus::AlreadyEquivalent);
         }
+        let pinned = self.pinned_root(&parent1, &parent2)?;
 
-        let res = union.union(parent1.clone(), parent2.clone())?;
+        let mut res = union
+            .union(parent1.clone(), parent2.clone())
+            .map_err(UnionError::NotUnionable)?;
+        if let Some(pinned) = pinned {
+            res = pinned;
+        }
+        self.extra
+            .merge(&parent1, &parent2, &res)
+            .map_err(UnionError::ExtraNotMergeable)?;
 
-        self.parent.set(parent1, res.clone());
-        self.parent.set(parent2, res);
+        if parent1 != res {
+            self.link(parent1, res.clone());
+        }
+        if parent2 != res {
+            self.link(parent2, res);
+        }
 
         Ok(UnionStatus::PerformedUnion)
     }
 
-    /// union two elements in the union find
-    pub fn union_by<U: Union<T>>(
+    /// Returns which of two distinct representatives is pinned, if any.
+    /// Fails if both are pinned, since then neither may stop being a representative.
+    fn pinned_root(&self, root1: &T, root2: &T) -> Result<Option<T>, BothPinned>
+    where
+        T: Clone,
+    {
+        match (self.pinned.contains(root1), self.pinned.contains(root2)) {
+            (true, true) => Err(BothPinned),
+            (true, false) => Ok(Some(root1.clone())),
+            (false, true) => Ok(Some(root2.clone())),
+            (false, false) => Ok(None),
+        }
+    }
+
+    /// The one of two representatives that is still a representative after they were unioned.
+    pub(crate) fn surviving_root(&self, root1: T, root2: T) -> T {
+        if self.parent.get(&root1) == Some(&root1) {
+            root1
+        } else {
+            root2
+        }
+    }
+
+    /// Makes `new_root` the parent of `root`, which stops being a representative.
+    fn link(&mut self, root: T, new_root: T)
+    where
+        T: Clone,
+    {
+        self.watchers.root_changed(&root, &new_root);
+        self.parent.set(root, new_root);
+        self.sets -= 1;
+    }
+
+    /// union two elements in the union find.
+    ///
+    /// The extra information of both classes is merged using [`MergeExtra`]. If either `union`
+    /// or merging the extra information fails, both classes are left untouched.
+    ///
+    /// If the representative of one of the classes is [pinned](UnionFind::pin), it becomes the
+    /// representative of the unioned class, regardless of the result of `union`.
+    ///
+    /// Like with [`find`](UnionFind::find), the elements can be given in any borrowed form of
+    /// the element type that the parent mapping supports.
+    pub fn union_by<U: Union<T>, Q: ?Sized>(
+        &mut self,
+        elem1: &Q,
+        elem2: &Q,
+        union: U,
+    ) -> Result<UnionStatus, UnionError<U::Err, E::MergeError>>
+    where
+        T: Clone,
+        E: MergeExtra<T>,
+        M: Lookup<T, T, Q>,
+    {
+        Ok(self.union_by_get_root(elem1, elem2, union)?.status)
+    }
+
+    /// Like [`union_by`](UnionFind::union_by), but also returns the representative of the
+    /// unioned class, which saves a find afterwards.
+    pub fn union_by_get_root<U: Union<T>, Q: ?Sized>(
+        &mut self,
+        elem1: &Q,
+        elem2: &Q,
+        union: U,
+    ) -> Result<RootedUnion<T>, UnionError<U::Err, E::MergeError>>
+    where
+        T: Clone,
+        E: MergeExtra<T>,
+        M: Lookup<T, T, Q>,
+    {
+        let mut budget = self.compression_budget;
+        let parent1 = self
+            .find_shorten_budgeted(elem1, &mut budget)
+            .ok_or(UnionError::Elem1NotFound)?;
+        let parent2 = self
+            .find_shorten_budgeted(elem2, &mut budget)
+            .ok_or(UnionError::Elem2NotFound)?;
+
+        let status = self.union_helper(parent1.clone(), parent2.clone(), union)?;
+        self.record_union(elem1, elem2, status);
+        Ok(RootedUnion {
+            status,
+            root: self.surviving_root(parent1, parent2),
+        })
+    }
+
+    /// Like [`union_by`](UnionFind::union_by), but the union carries a label, for example the
+    /// edge or rule that caused it. If the union merged two classes, the label is kept, and
+    /// [`labels_between`](UnionFind::labels_between) returns it to explain why two elements
+    /// are equivalent. Paths are [tracked](UnionFind::track_paths) from the first labeled
+    /// union on, if they weren't already.
+    pub fn union_by_labeled<U: Union<T>, Q: ?Sized>(
+        &mut self,
+        elem1: &Q,
+        elem2: &Q,
+        union: U,
+        label: L,
+    ) -> Result<UnionStatus, UnionError<U::Err, E::MergeError>>
+    where
+        T: Clone,
+        E: MergeExtra<T>,
+        M: Lookup<T, T, Q>,
+    {
+        self.track_paths();
+        let status = self.union_by(elem1, elem2, union)?;
+        self.label_last_union(status, label);
+        Ok(status)
+    }
+
+    /// Unions every pair of elements with [`union_by`](UnionFind::union_by), returning how many
+    /// unions merged two classes that were not equivalent before.
+    ///
+    /// When a union fails, the unions made up to that point are kept, and the index of the
+    /// failing pair is returned with the error.
+    pub fn union_all<U: Union<T> + Clone>(
+        &mut self,
+        pairs: impl IntoIterator<Item = (T, T)>,
+        union: U,
+    ) -> Result<usize, BulkError<UnionByError<U, T, E>>>
+    where
+        T: Clone,
+        E: MergeExtra<T>,
+    {
+        self.union_all_with_progress(pairs, union, &mut Progress::none())
+    }
+
+    /// Like [`union_all`](UnionFind::union_all), but progress is reported and cancellation is
+    /// checked through `progress`. When the operation is cancelled, the unions made up to that
+    /// point are kept.
+    pub fn union_all_with_progress<U: Union<T> + Clone>(
+        &mut self,
+        pairs: impl IntoIterator<Item = (T, T)>,
+        union: U,
+        progress: &mut Progress<'_>,
+    ) -> Result<usize, BulkError<UnionByError<U, T, E>>>
+    where
+        T: Clone,
+        E: MergeExtra<T>,
+    {
+        let mut merges = 0;
+        for (index, (elem1, elem2)) in pairs.into_iter().enumerate() {
+            progress.check(index)?;
+            let status = self
+                .union_by(&elem1, &elem2, union.clone())
+                .map_err(|error| BulkError::Item { index, error })?;
+            if status == UnionStatus::PerformedUnion {
+                merges += 1;
+            }
+            progress.processed(index + 1);
+        }
+        Ok(merges)
+    }
+}
+
+impl<T: Hash + Eq + Ord + Clone, V, E: MergeExtra<T>, M: Mapping<T, T>, L>
+    UnionFind<T, V, E, M, L>
+{
+    /// Union two elements with [`ByMin`], keeping the smallest representative.
+    pub fn union_by_min(
+        &mut self,
+        elem1: &T,
+        elem2: &T,
+    ) -> Result<UnionStatus, UnionError<Infallible, E::MergeError>> {
+        self.union_by(elem1, elem2, ByMin)
+    }
+
+    /// Union two elements with [`ByMax`], keeping the largest representative.
+    pub fn union_by_max(
         &mut self,
         elem1: &T,
         elem2: &T,
+    ) -> Result<UnionStatus, UnionError<Infallible, E::MergeError>> {
+        self.union_by(elem1, elem2, ByMax)
+    }
+}
+
+impl<T: Hash + Eq + Clone, V: Default, E, L> UnionFind<T, V, E, HashMap<T, T>, L>
+where
+    E: GrowableExtra<T, V> + MergeExtra<T>,
+{
+    /// Like [`absorb`](UnionFind::absorb), but the classes are unioned with `union`, and their
+    /// extra information is merged using [`MergeExtra`]. Elements of `other` that are not
+    /// present yet are added with the default value, after which the extra information of
+    /// their classes is built up again through the unions.
+    pub fn absorb_by<U: Union<T> + Clone>(
+        &mut self,
+        other: Self,
+        union: U,
+    ) -> Result<usize, UnionByOrAddError<U, T, V, E>> {
+        let mut merges = 0;
+        for (elem, root) in other {
+            if elem == root {
+                self.add_if_missing(&elem)
+                    .map_err(UnionOrAddError::AddError)?;
+                continue;
+            }
+            if self.union_or_add(&root, &elem, union.clone())? == UnionStatus::PerformedUnion {
+                merges += 1;
+            }
+        }
+        Ok(merges)
+    }
+
+    /// Like [`from_pairs`](UnionFind::from_pairs), but for any extra information that can be
+    /// merged, with the classes unioned by `union`. Elements are added with the default value
+    /// when they first occur. Stops at, and returns, the first element that can't be added or
+    /// union that fails.
+    pub fn from_pairs_by<U: Union<T> + Clone>(
+        pairs: impl IntoIterator<Item = (T, T)>,
         union: U,
-    ) -> Result<UnionStatus, UnionError<U::Err>>
+    ) -> Result<Self, UnionByOrAddError<U, T, V, E>>
     where
-        T: Clone,
+        E: Default,
     {
-        let parent1 = self.find_shorten(elem1).ok_or(UnionError::Elem1NotFound)?;
-        let parent2 = self.find_shorten(elem2).ok_or(UnionError::Elem2NotFound)?;
+        let mut uf = Self::empty();
+        for (a, b) in pairs {
+            uf.union_or_add(&a, &b, union.clone())?;
+        }
+        Ok(uf)
+    }
+}
+
+impl<T: Hash + Eq + Clone, L> UnionFind<T, usize, ByRank<T>, HashMap<T, T>, L> {
+    /// Builds a union find from pairs of elements that should be in the same class.
+    /// Elements are added when they first occur.
+    pub fn from_pairs(pairs: impl IntoIterator<Item = (T, T)>) -> Self {
+        let mut uf = Self::empty();
+        for (a, b) in pairs {
+            uf.union_adding(a, b);
+        }
+        uf
+    }
+
+    /// Like [`from_pairs`](UnionFind::from_pairs), but progress is reported and cancellation
+    /// is checked through `progress`. When the operation is cancelled, the partially built
+    /// union find is dropped.
+    pub fn from_pairs_with_progress(
+        pairs: impl IntoIterator<Item = (T, T)>,
+        progress: &mut Progress<'_>,
+    ) -> Result<Self, BulkError<Infallible>> {
+        let mut uf = Self::empty();
+        for (index, (a, b)) in pairs.into_iter().enumerate() {
+            progress.check(index)?;
+            uf.union_adding(a, b);
+            progress.processed(index + 1);
+        }
+        Ok(uf)
+    }
+
+    /// Like [`from_pairs`](UnionFind::from_pairs), but for pairs that are produced by a
+    /// fallible iterator, for example while parsing. Stops at, and returns, the first error.
+    pub fn try_from_pairs<Err>(
+        pairs: impl IntoIterator<Item = Result<(T, T), Err>>,
+    ) -> Result<Self, Err> {
+        let mut uf = Self::empty();
+        for pair in pairs {
+            let (a, b) = pair?;
+            uf.union_adding(a, b);
+        }
+        Ok(uf)
+    }
+
+    /// Adds all elements of `other` that are not present yet, and unions every element with
+    /// the representative of its class in `other` by rank, so that elements equivalent in
+    /// either union find are equivalent afterwards. Returns how many unions merged two classes
+    /// that were not equivalent before.
+    ///
+    /// Only one union per element of `other` is made, so absorbing the smaller of two union
+    /// finds into the larger is fastest. Representatives pinned in `other`, and elements it
+    /// watches, are not carried over. When a union fails because the representatives of both
+    /// classes are pinned, the unions made up to that point are kept.
+    pub fn absorb(&mut self, other: Self) -> Result<usize, UnionByRankError> {
+        let mut merges = 0;
+        for (elem, root) in other {
+            self.get_or_insert_with(elem.clone(), Default::default)
+                .expect("element was not present");
+            if elem == root {
+                continue;
+            }
+            self.get_or_insert_with(root.clone(), Default::default)
+                .expect("element was not present");
+            if self.union_by_rank(&elem, &root)? == UnionStatus::PerformedUnion {
+                merges += 1;
+            }
+        }
+        Ok(merges)
+    }
 
-        self.union_helper(parent1, parent2, union)
-            .map_err(UnionError::NotUnionable)
+    /// Unions two elements by rank, first adding them if they are not present.
+    pub(crate) fn union_adding(&mut self, a: T, b: T) {
+        self.get_or_insert_with(a.clone(), Default::default)
+            .expect("element was not present");
+        self.get_or_insert_with(b.clone(), Default::default)
+            .expect("element was not present");
+        self.union_by_rank(&a, &b)
+            .expect("both elements were added");
     }
 }
 
-#[derive(Error, Debug)]
+#[derive(Error, Debug, PartialEq)]
 pub enum UnionByRankError {
     #[error("the first element given as an argument to union was not found in the union find")]
     Elem1NotFound,
 
     #[error("the second element given as an argument to union was not found in the union find")]
     Elem2NotFound,
+
+    #[error("the representatives of both classes are pinned")]
+    BothPinned,
 }
 
-impl<T: Hash+Eq, V> UnionFind<T, V, ByRank<T>>
+impl<T: Hash+Eq, V, R, M, L> UnionFind<T, V, ByRank<T, R>, M, L>
 where
     T: Clone + PartialEq+ Hash +Eq,
+    R: Mapping<T, usize>,
+    M: Mapping<T, T>,
 {
-    /// union two elements in the union find by rank
-    pub fn union_by_rank(&mut self, elem1: &T, elem2: &T) -> Result<UnionStatus, UnionByRankError> {
+    /// union two elements in the union find by rank. Like with [`find`](UnionFind::find), the
+    /// elements can be given in any borrowed form of the element type.
+    pub fn union_by_rank<Q: ?Sized>(
+        &mut self,
+        elem1: &Q,
+        elem2: &Q,
+    ) -> Result<UnionStatus, UnionByRankError>
+    where
+        M: Lookup<T, T, Q>,
+    {
+        Ok(self.union_by_rank_get_root(elem1, elem2)?.status)
+    }
+
+    /// Like [`union_by_rank`](UnionFind::union_by_rank), but also returns the representative
+    /// of the unioned class, which saves a find afterwards.
+    pub fn union_by_rank_get_root<Q: ?Sized>(
+        &mut self,
+        elem1: &Q,
+        elem2: &Q,
+    ) -> Result<RootedUnion<T>, UnionByRankError>
+    where
+        M: Lookup<T, T, Q>,
+    {
+        let mut budget = self.compression_budget;
         let parent1 = self
-            .find_shorten(elem1)
+            .find_shorten_budgeted(elem1, &mut budget)
             .ok_or(UnionByRankError::Elem1NotFound)?;
         let parent2 = self
-            .find_shorten(elem2)
+            .find_shorten_budgeted(elem2, &mut budget)
             .ok_or(UnionByRankError::Elem2NotFound)?;
 
-        self.union_by_rank_helper(parent1, parent2)
+        let status = self.union_by_rank_helper(parent1.clone(), parent2.clone())?;
+        self.record_union(elem1, elem2, status);
+        Ok(RootedUnion {
+            status,
+            root: self.surviving_root(parent1, parent2),
+        })
+    }
+
+    /// Like [`union_by_rank`](UnionFind::union_by_rank), but the union carries a label, see
+    /// [`union_by_labeled`](UnionFind::union_by_labeled).
+    pub fn union_by_rank_labeled<Q: ?Sized>(
+        &mut self,
+        elem1: &Q,
+        elem2: &Q,
+        label: L,
+    ) -> Result<UnionStatus, UnionByRankError>
+    where
+        M: Lookup<T, T, Q>,
+    {
+        self.track_paths();
+        let status = self.union_by_rank(elem1, elem2)?;
+        self.label_last_union(status, label);
+        Ok(status)
+    }
+
+    /// Unions every pair of elements by rank, returning how many unions merged two classes
+    /// that were not equivalent before. When a union fails, the unions made up to that point
+    /// are kept, and the index of the failing pair is returned with the error.
+    pub fn union_all_by_rank(
+        &mut self,
+        pairs: impl IntoIterator<Item = (T, T)>,
+    ) -> Result<usize, BulkError<UnionByRankError>> {
+        self.union_all_by_rank_with_progress(pairs, &mut Progress::none())
+    }
+
+    /// Unions every pair of elements by rank, returning how many unions merged two classes
+    /// that were not equivalent before.
+    ///
+    /// Progress is reported and cancellation is checked through `progress`. When the operation
+    /// is cancelled or a union fails, the unions made up to that point are kept.
+    pub fn union_all_by_rank_with_progress(
+        &mut self,
+        pairs: impl IntoIterator<Item = (T, T)>,
+        progress: &mut Progress<'_>,
+    ) -> Result<usize, BulkError<UnionByRankError>> {
+        let mut merges = 0;
+        for (index, (elem1, elem2)) in pairs.into_iter().enumerate() {
+            progress.check(index)?;
+            let status = self
+                .union_by_rank(&elem1, &elem2)
+                .map_err(|error| BulkError::Item { index, error })?;
+            if status == UnionStatus::PerformedUnion {
+                merges += 1;
+            }
+            progress.processed(index + 1);
+        }
+
+        Ok(merges)
     }
 
-    fn union_by_rank_helper(
+    pub(crate) fn union_by_rank_helper(
         &mut self,
         parent1: T,
         parent2: T,
@@ -241,16 +1837,29 @@ where
             .rank(&parent2)
             .ok_or(UnionByRankError::Elem2NotFound)?;
 
+        if let Some(pinned) = self.pinned_root(&parent1, &parent2)? {
+            let (other, pinned_rank, other_rank) = if pinned == parent1 {
+                (parent2, rank1, rank2)
+            } else {
+                (parent1, rank2, rank1)
+            };
+            self.link(other, pinned.clone());
+            if pinned_rank <= other_rank {
+                self.extra.set_rank(pinned, other_rank + 1);
+            }
+            return Ok(UnionStatus::PerformedUnion);
+        }
+
         match rank1.cmp(&rank2) {
             Ordering::Less => {
-                self.parent.set(parent1, parent2);
+                self.link(parent1, parent2);
             }
             Ordering::Equal => {
-                self.parent.set(parent1, parent2.clone());
+                self.link(parent1, parent2.clone());
                 self.extra.set_rank(parent2, rank2 + 1);
             }
             Ordering::Greater => {
-                self.parent.set(parent2, parent1);
+                self.link(parent2, parent1);
             }
         }
 
@@ -258,6 +1867,124 @@ where
     }
 }
 
+/// The result of [`union_by_counting_pairs`](UnionFind::union_by_counting_pairs).
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub struct PairsUnion {
+    pub status: UnionStatus,
+    /// The number of pairs of elements that were not in the same class before the union,
+    /// but are afterwards.
+    pub newly_connected_pairs: u64,
+}
+
+impl<T: Hash + Eq + Clone, V, S: BuildHasher, M: Mapping<T, T>, L>
+    UnionFind<T, V, BySize<T, S>, M, L>
+{
+    /// The number of pairs of distinct elements that are in the same class.
+    /// This is maintained during unions, so takes constant time.
+    pub fn connected_pairs(&self) -> u64 {
+        self.extra.connected_pairs()
+    }
+
+    /// Like [`union_by`](UnionFind::union_by), but also reports how many pairs of elements
+    /// became connected by this union.
+    pub fn union_by_counting_pairs<U: Union<T>>(
+        &mut self,
+        elem1: &T,
+        elem2: &T,
+        union: U,
+    ) -> Result<PairsUnion, UnionError<U::Err>> {
+        let before = self.connected_pairs();
+        let status = self.union_by(elem1, elem2, union)?;
+        Ok(PairsUnion {
+            status,
+            newly_connected_pairs: self.connected_pairs() - before,
+        })
+    }
+
+    /// The number of elements in the class of `elem`, or `None` if the element is not
+    /// in the union find.
+    pub fn set_size(&self, elem: &T) -> Option<usize> {
+        self.extra.size(&self.find(elem)?)
+    }
+
+    /// Union two elements, making the representative of the larger class the representative
+    /// of the unioned class. Like union by rank, this keeps trees logarithmically shallow.
+    pub fn union_by_size(
+        &mut self,
+        elem1: &T,
+        elem2: &T,
+    ) -> Result<UnionStatus, UnionError<Infallible>> {
+        let root1 = self.find_shorten(elem1).ok_or(UnionError::Elem1NotFound)?;
+        let root2 = self.find_shorten(elem2).ok_or(UnionError::Elem2NotFound)?;
+        let larger = if self.extra.size(&root1) >= self.extra.size(&root2) {
+            root1.clone()
+        } else {
+            root2.clone()
+        };
+
+        // the elements themselves are unioned, so that tracked paths contain them
+        self.union_by(elem1, elem2, infallible(|a, b| if a == larger { a } else { b }))
+    }
+}
+
+impl<T: Hash + Eq + Clone, V, S: BuildHasher, M: Mapping<T, T>, L>
+    UnionFind<T, V, Values<T, V, S>, M, L>
+{
+    /// The value of the class of `elem`, or `None` if the element is not in the union find.
+    pub fn value(&self, elem: &T) -> Option<&V> {
+        self.extra.value(self.root_ref(elem)?)
+    }
+
+    /// Like [`value`](UnionFind::value), but the value can be modified.
+    pub fn value_mut(&mut self, elem: &T) -> Option<&mut V> {
+        let root = self.find_shorten(elem)?;
+        self.extra.value_mut(&root)
+    }
+
+    /// Union the classes of two elements, combining their values with `merge`. The value of the
+    /// class of `elem1` is passed first. If `merge` fails, the union is rejected and both
+    /// classes are left untouched.
+    ///
+    /// The representative of the unioned class is chosen by rank, unless one of the
+    /// representatives is [pinned](UnionFind::pin).
+    pub fn union_values<Err>(
+        &mut self,
+        elem1: &T,
+        elem2: &T,
+        merge: impl FnOnce(&V, &V) -> Result<V, Err>,
+    ) -> Result<UnionStatus, UnionError<Infallible, Err>> {
+        let mut budget = self.compression_budget;
+        let root1 = self
+            .find_shorten_budgeted(elem1, &mut budget)
+            .ok_or(UnionError::Elem1NotFound)?;
+        let root2 = self
+            .find_shorten_budgeted(elem2, &mut budget)
+            .ok_or(UnionError::Elem2NotFound)?;
+        if root1 == root2 {
+            return Ok(UnionStatus::AlreadyEquivalent);
+        }
+
+        let pinned = self.pinned_root(&root1, &root2)?;
+        let (child, root) = self
+            .extra
+            .merge(root1, root2, pinned, merge)
+            .map_err(UnionError::ExtraNotMergeable)?;
+        self.link(child, root);
+        self.record_union(elem1, elem2, UnionStatus::PerformedUnion);
+        Ok(UnionStatus::PerformedUnion)
+    }
+}
+
+impl<T: Hash + Eq + Clone, V, S: BuildHasher, M, L> UnionFind<T, V, Members<T, S>, M, L> {
+    /// Iterates over all elements in the class of `elem`, starting with `elem`, or returns
+    /// `None` if the element is not in the union find. Unlike
+    /// [`members_of`](UnionFind::members_of), this takes time proportional to the size of the
+    /// class.
+    pub fn class_members<'a>(&'a self, elem: &'a T) -> Option<impl Iterator<Item = &'a T> + 'a> {
+        self.extra.members(elem)
+    }
+}
+
 #[derive(Debug, Error, PartialEq)]
 pub enum AddError<E, P> {
     #[error("couldn't add element to parent mapping")]
@@ -267,34 +1994,52 @@ pub enum AddError<E, P> {
     Extra(#[source] E),
 }
 
-type AddErrorSimple<T, V, M, E> =
+pub(crate) type AddErrorSimple<T, V, M, E> =
     AddError<<E as GrowableExtra<T, V>>::AddError, <M as GrowableMapping<T, T>>::AddError>;
 
-impl<T: Clone + Hash+Eq, V, E> UnionFind<T, V, E>
+impl<T: Clone + Hash+Eq, V, E, M: GrowableMapping<T, T>, L> UnionFind<T, V, E, M, L>
 where
     E: GrowableExtra<T, V>,
     V: Default,
 {
-    pub fn add(&mut self, elem: T) -> Result<(), AddErrorSimple<T, V, HashMap<T,T>, E>> {
-        self.parent
-            .add_identity(elem.clone())
-            .map_err(AddError::Parent)?;
-        self.extra
-            .add(elem, Default::default())
-            .map_err(AddError::Extra)?;
-        Ok(())
+    pub fn add(&mut self, elem: T) -> Result<(), AddErrorSimple<T, V, M, E>> {
+        self.add_with_extra(elem, Default::default())
     }
 }
 
-impl<T: Hash+Eq + Clone, V, E> UnionFind<T, V, E>
+impl<T: Hash+Eq + Clone, V, E, M: GrowableMapping<T, T>, L> UnionFind<T, V, E, M, L>
 where
     E: GrowableExtra<T, V>,
 {
-    pub fn add_with_extra(&mut self, elem: T, extra: V) -> Result<(), AddErrorSimple<T, V, HashMap<T,T>, E>> {
+    /// Adds an element in a class on its own, with the given extra information. When adding
+    /// it to either the parent mapping or the extra information fails, the union find is left
+    /// unchanged.
+    pub fn add_with_extra(&mut self, elem: T, extra: V) -> Result<(), AddErrorSimple<T, V, M, E>> {
         self.parent
             .add_identity(elem.clone())
             .map_err(AddError::Parent)?;
-        self.extra.add(elem, extra).map_err(AddError::Extra)?;
+        if let Err(error) = self.extra.add(elem.clone(), extra) {
+            self.parent.undo_add(&elem);
+            return Err(AddError::Extra(error));
+        }
+        self.sets += 1;
         Ok(())
     }
+
+    /// Find the representative of an element, adding it to the union find if it
+    /// was not present yet. The extra information for the element is only computed
+    /// by calling `make_extra` when the element is actually added, which is useful
+    /// when computing it is expensive.
+    pub fn get_or_insert_with(
+        &mut self,
+        elem: T,
+        make_extra: impl FnOnce() -> V,
+    ) -> Result<T, AddErrorSimple<T, V, M, E>> {
+        if let Some(root) = self.find_shorten(&elem) {
+            return Ok(root);
+        }
+
+        self.add_with_extra(elem.clone(), make_extra())?;
+        Ok(elem)
+    }
 }
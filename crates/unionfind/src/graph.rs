@@ -0,0 +1,41 @@
+//! Connected components of [`petgraph`] graphs.
+//!
+//! Edges are treated as undirected, so for directed graphs these are the weakly connected
+//! components.
+
+use crate::HashUnionFindByRank;
+use petgraph::graph::{IndexType, NodeIndex};
+use petgraph::{EdgeType, Graph};
+use std::collections::HashMap;
+
+/// Creates a union find with every node of `graph`, where the classes are the connected
+/// components of the graph.
+pub fn from_graph<N, E, Ty: EdgeType, Ix: IndexType>(
+    graph: &Graph<N, E, Ty, Ix>,
+) -> HashUnionFindByRank<NodeIndex<Ix>> {
+    let mut uf = HashUnionFindByRank::new(graph.node_indices()).expect("node indices are distinct");
+    for edge in graph.raw_edges() {
+        uf.union_by_rank(&edge.source(), &edge.target())
+            .expect("endpoints of an edge are nodes of the graph");
+    }
+    uf
+}
+
+/// Returns the connected components of `graph`. Components are ordered by their lowest node
+/// index, and nodes within a component are in increasing order.
+pub fn components<N, E, Ty: EdgeType, Ix: IndexType>(
+    graph: &Graph<N, E, Ty, Ix>,
+) -> Vec<Vec<NodeIndex<Ix>>> {
+    let mut uf = from_graph(graph);
+    let mut component_of = HashMap::new();
+    let mut components: Vec<Vec<NodeIndex<Ix>>> = Vec::new();
+    for node in graph.node_indices() {
+        let root = uf.find_shorten(&node).expect("node is in the union find");
+        let component = *component_of.entry(root).or_insert_with(|| {
+            components.push(Vec::new());
+            components.len() - 1
+        });
+        components[component].push(node);
+    }
+    components
+}
@@ -0,0 +1,86 @@
+//! Connected-component labeling of 2D grids, such as binary images.
+
+use crate::dense::VecUnionFind;
+
+/// Which neighbours of a cell it is connected to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FourOrEight {
+    /// Only the cells left, right, above and below.
+    Four,
+    /// Also the diagonal cells.
+    Eight,
+}
+
+/// Labels the connected components of the foreground cells of a `width` by `height` grid.
+/// `is_foreground(x, y)` is called exactly once for every cell.
+///
+/// Returns a label for every cell, in row-major order, so the label of `(x, y)` is at
+/// `y * width + x`. Background cells get label `0`, and the components get the labels
+/// `1, 2, ...` in the order in which their first cell appears.
+///
+/// Uses the classic two-pass algorithm: the first pass unions every foreground cell with
+/// its foreground neighbours that were already visited, and the second pass assigns labels.
+///
+/// # Panics
+///
+/// Panics if there are more than `u32::MAX` components.
+pub fn label_grid(
+    width: usize,
+    height: usize,
+    is_foreground: impl Fn(usize, usize) -> bool,
+    connectivity: FourOrEight,
+) -> Vec<u32> {
+    let foreground: Vec<bool> = (0..height)
+        .flat_map(|y| (0..width).map(move |x| (x, y)))
+        .map(|(x, y)| is_foreground(x, y))
+        .collect();
+    let mut uf = VecUnionFind::with_len(foreground.len());
+
+    for y in 0..height {
+        for x in 0..width {
+            let cell = y * width + x;
+            if !foreground[cell] {
+                continue;
+            }
+            // the neighbours that come before the cell in row-major order
+            let diagonal = connectivity == FourOrEight::Eight;
+            let above = y.checked_sub(1);
+            let visited = [
+                x.checked_sub(1).map(|left| (left, y)),
+                above.map(|above| (x, above)),
+                above
+                    .zip(x.checked_sub(1))
+                    .filter(|_| diagonal)
+                    .map(|(above, left)| (left, above)),
+                above
+                    .filter(|_| diagonal && x + 1 < width)
+                    .map(|above| (x + 1, above)),
+            ];
+            for (nx, ny) in visited.into_iter().flatten() {
+                let neighbour = ny * width + nx;
+                if foreground[neighbour] {
+                    uf.union_by_rank(&cell, &neighbour)
+                        .expect("cells are in the union find");
+                }
+            }
+        }
+    }
+
+    let mut labels = vec![0; foreground.len()];
+    let mut root_labels = vec![0; foreground.len()];
+    let mut next = 0u32;
+    for (cell, label) in labels.iter_mut().enumerate() {
+        if !foreground[cell] {
+            continue;
+        }
+        let root = uf.find_shorten(&cell).expect("cell is in the union find");
+        if root_labels[root] == 0 {
+            next = next
+                .checked_add(1)
+                .expect("too many components for u32 labels");
+            root_labels[root] = next;
+        }
+        *label = root_labels[root];
+    }
+    labels
+}
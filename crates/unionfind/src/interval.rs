@@ -0,0 +1,80 @@
+//! Finding the next free slot after slots are occupied one by one.
+
+/// The slots `0..len`, which start free and can be occupied, answering which slot is the
+/// first free one at or after a given slot. Useful for scheduling jobs into the first free
+/// time slot, or for sweep-line algorithms that skip over processed positions.
+///
+/// Every slot points to a slot at or after it, and free slots point to themselves. Occupying
+/// a slot makes it point to the next slot, and finding the next free slot follows these
+/// pointers while halving the path. Both take near-constant amortized time.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct IntervalUnionFind {
+    /// Has one more entry than there are slots, which stays free, so every path ends.
+    next: Vec<usize>,
+    free: usize,
+}
+
+impl IntervalUnionFind {
+    /// Creates `len` slots that are all free.
+    pub fn new(len: usize) -> Self {
+        Self {
+            next: (0..=len).collect(),
+            free: len,
+        }
+    }
+
+    /// The number of slots.
+    pub fn len(&self) -> usize {
+        self.next.len() - 1
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// The number of free slots.
+    pub fn num_free(&self) -> usize {
+        self.free
+    }
+
+    /// Returns whether a slot is free. Slots outside `0..len` are never free.
+    pub fn is_free(&self, slot: usize) -> bool {
+        slot < self.len() && self.next[slot] == slot
+    }
+
+    /// Occupies a slot. Returns false if the slot was already occupied.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the slot is outside `0..len`.
+    pub fn occupy(&mut self, slot: usize) -> bool {
+        assert!(slot < self.len(), "slot {slot} is out of bounds");
+        if self.next[slot] != slot {
+            return false;
+        }
+        self.next[slot] = slot + 1;
+        self.free -= 1;
+        true
+    }
+
+    /// The first free slot at or after `slot`, or `None` if all of them are occupied.
+    pub fn next_free_at_or_after(&mut self, slot: usize) -> Option<usize> {
+        if slot >= self.len() {
+            return None;
+        }
+        let mut current = slot;
+        while self.next[current] != current {
+            let grandparent = self.next[self.next[current]];
+            self.next[current] = grandparent;
+            current = grandparent;
+        }
+        (current < self.len()).then_some(current)
+    }
+
+    /// Occupies the first free slot at or after `slot`, and returns it.
+    pub fn occupy_next_free(&mut self, slot: usize) -> Option<usize> {
+        let free = self.next_free_at_or_after(slot)?;
+        self.occupy(free);
+        Some(free)
+    }
+}
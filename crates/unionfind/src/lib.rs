@@ -19,18 +19,76 @@
 //! [`find_shorten`](UnionFind::find_shorten) instead of [`find`](UnionFind::find).
 //! By using [`find_shorten`](UnionFind::find_shorten), subsequent finds become faster than the first.
 //! However, an advantage to [`find`](UnionFind::find) is that it does not need mutable access to the datastructure
+//!
+//! The parent mapping of a [`UnionFind`] is the public field `parent`. Since modifying it
+//! directly can break the invariants of the union find, the field is deprecated, and will
+//! become private in the next release. Use [`raw`](UnionFind::raw) or
+//! [`parents`](UnionFind::parents) to read it, and [`raw_mut`](UnionFind::raw_mut) together
+//! with [`validate`](UnionFind::validate) if you really need to modify it.
 
-use crate::extra::ByRank;
+use crate::extra::{ByRank, BySize};
 use crate::generic::UnionFind;
 use std::collections::{BTreeMap, HashMap};
+use std::hash::RandomState;
 
+pub mod algorithms;
+pub mod bipartite;
+pub mod bridges;
+pub mod cell;
+pub mod concurrent;
+pub mod congruence;
+#[cfg(feature = "async")]
+pub mod cooperative;
+pub mod dense;
+pub mod difference;
+pub mod distributed;
+pub mod encoding;
+pub mod entry;
+pub mod external;
 pub mod extra;
+mod forest;
+// `UnionFind::parent` is only deprecated for users of the crate
+#[allow(deprecated)]
 pub mod generic;
+#[cfg(feature = "petgraph")]
+pub mod graph;
+pub mod grid;
+pub mod interval;
 pub mod mapping;
+pub mod offline;
+#[cfg(feature = "rayon")]
+pub mod parallel;
+pub mod percolation;
+pub mod persistent;
+pub mod progress;
+mod quotient;
+pub mod ranges;
+pub mod rollback;
+pub mod sketch;
+pub mod slice;
+pub mod small;
+pub mod smt;
+pub mod sync;
+pub mod timed;
+#[allow(deprecated)]
+pub mod transaction;
 pub mod union;
+#[cfg(feature = "unify")]
+pub mod unify;
+pub mod watch;
+pub mod weighted;
 
 #[cfg(test)]
 mod tests;
 
 
-pub type HashUnionFindByRank<T> = UnionFind<T, usize, ByRank<T>>;
+pub type HashUnionFindByRank<T> = HashUnionFindByRankWithHasher<T, RandomState>;
+/// Like [`HashUnionFindByRank`], but with a custom hasher `S` for both the parents and ranks,
+/// for example a faster hasher for integer elements.
+pub type HashUnionFindByRankWithHasher<T, S> =
+    UnionFind<T, usize, ByRank<T, HashMap<T, usize, S>>, HashMap<T, T, S>>;
+pub type HashUnionFindBySize<T> = UnionFind<T, (), BySize<T>>;
+/// Like [`HashUnionFindByRank`], but keeps elements and ranks in sorted order,
+/// so that iterating and serializing give the same output every run.
+pub type BTreeUnionFindByRank<T> =
+    UnionFind<T, usize, ByRank<T, BTreeMap<T, usize>>, BTreeMap<T, T>>;
@@ -1,7 +1,13 @@
+use fixedbitset::FixedBitSet;
+use serde::de::{Error as _, SeqAccess, Visitor};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::borrow::Borrow;
 use std::collections::{BTreeMap, HashMap};
 use std::error::Error;
-use std::fmt::Debug;
-use std::hash::Hash;
+use std::fmt::{self, Debug};
+use std::hash::{BuildHasher, Hash};
+use std::marker::PhantomData;
+use std::mem;
 use std::ops::{Deref, DerefMut};
 use thiserror::Error;
 
@@ -75,7 +81,7 @@ where
 /// The trait is even implemented for hashmaps. However,
 /// in some cases, it's efficient to use an array instead,
 /// since in a union find, keys are often integers, generated in-order.
-pub trait Mapping<K, V> {
+pub trait Mapping<K, V>: Lookup<K, V> {
     /// gets an element from the mapping. Returns none
     /// if the key is not found in the mapping.
     fn get(&self, key: &K) -> Option<&V>;
@@ -93,6 +99,19 @@ pub trait Mapping<K, V> {
     fn set(&mut self, key: K, value: V);
 }
 
+/// A mapping in which keys can be looked up through a borrowed form `Q` of the key type,
+/// like with [`HashMap::get`], so that for example `String` keys can be looked up with a
+/// `&str`. Every mapping can at least be looked up with the key type itself.
+pub trait Lookup<K, V, Q: ?Sized = K> {
+    /// The value of the key equal to `key`, if it is in the mapping.
+    fn lookup(&self, key: &Q) -> Option<&V>;
+
+    /// The key in the mapping equal to `key`, if any.
+    fn lookup_key(&self, key: &Q) -> Option<K>
+    where
+        K: Clone;
+}
+
 /// Represents a [`GrowableMapping`] to which items can be trivially added
 /// by cloning them and mapping them to themselves. This is implemented for all
 /// `GrowableMapping<T, T>` where `T: Clone`
@@ -133,6 +152,10 @@ pub trait GrowableMapping<K, V>: Mapping<K, V> {
     /// the correct errors to be usable in a union find.
     fn add(&mut self, key: K, value: V) -> Result<(), Self::AddError>;
 
+    /// Removes the key that was added last with [`add`](GrowableMapping::add), so that an
+    /// addition to a union find can be rolled back when adding to its extra information fails.
+    fn undo_add(&mut self, key: &K);
+
     /// Gets the number of items currently in the mapping.
     fn len(&self) -> usize;
 
@@ -140,9 +163,78 @@ pub trait GrowableMapping<K, V>: Mapping<K, V> {
     fn is_empty(&self) -> bool {
         self.len() == 0
     }
+
+    /// Reserves room for at least `additional` more keys, for mappings that can allocate
+    /// ahead of time. Does nothing by default.
+    fn reserve(&mut self, _additional: usize) {}
+
+    /// Frees memory that is not used by the keys in the mapping. Does nothing by default.
+    fn shrink_to_fit(&mut self) {}
+}
+
+/// A mapping whose entries can be iterated, for example to serialize a union find
+/// that uses it as its parent mapping.
+pub trait IterableMapping<K, V>: Mapping<K, V> {
+    /// All keys in the mapping with their value, in arbitrary order.
+    fn entries<'a>(&'a self) -> impl Iterator<Item = (K, &'a V)> + 'a
+    where
+        V: 'a;
+}
+
+/// A mapping from which keys can be removed, for example by
+/// [`UnionFind::remove`](crate::generic::UnionFind::remove).
+pub trait RemovableMapping<K, V>: Mapping<K, V> {
+    /// Removes a key from the mapping, and returns its value if it was present.
+    fn remove(&mut self, key: &K) -> Option<V>;
+}
+
+/// Serializes a mapping as a sequence of `(key, value)` pairs.
+pub(crate) fn serialize_entries<K, V, M, S>(mapping: &M, serializer: S) -> Result<S::Ok, S::Error>
+where
+    K: Serialize,
+    V: Serialize,
+    M: IterableMapping<K, V>,
+    S: Serializer,
+{
+    serializer.collect_seq(mapping.entries())
+}
+
+/// Deserializes a mapping from a sequence of `(key, value)` pairs, adding every pair to the
+/// mapping as soon as it is decoded. Later pairs overwrite earlier pairs for the same key.
+pub(crate) fn deserialize_entries<'de, K, V, M, D>(deserializer: D) -> Result<M, D::Error>
+where
+    K: Deserialize<'de>,
+    V: Deserialize<'de>,
+    M: GrowableMapping<K, V>,
+    D: Deserializer<'de>,
+{
+    struct EntriesVisitor<K, V, M>(PhantomData<(K, V, M)>);
+
+    impl<'de, K, V, M> Visitor<'de> for EntriesVisitor<K, V, M>
+    where
+        K: Deserialize<'de>,
+        V: Deserialize<'de>,
+        M: GrowableMapping<K, V>,
+    {
+        type Value = M;
+
+        fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+            formatter.write_str("a sequence of (key, value) pairs")
+        }
+
+        fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<M, A::Error> {
+            let mut mapping = M::empty();
+            while let Some((key, value)) = seq.next_element::<(K, V)>()? {
+                mapping.set_or_add(key, value).map_err(A::Error::custom)?;
+            }
+            Ok(mapping)
+        }
+    }
+
+    deserializer.deserialize_seq(EntriesVisitor(PhantomData))
 }
 
-impl<K: Hash + Eq, V> Mapping<K, V> for HashMap<K, V> {
+impl<K: Hash + Eq, V, S: BuildHasher> Mapping<K, V> for HashMap<K, V, S> {
     fn get(&self, key: &K) -> Option<&V> {
         HashMap::get(self, key)
     }
@@ -154,23 +246,70 @@ impl<K: Hash + Eq, V> Mapping<K, V> for HashMap<K, V> {
     }
 }
 
-impl<K: Hash + Eq, V> GrowableMapping<K, V> for HashMap<K, V> {
+impl<K, V, Q, S> Lookup<K, V, Q> for HashMap<K, V, S>
+where
+    K: Hash + Eq + Borrow<Q>,
+    Q: Hash + Eq + ?Sized,
+    S: BuildHasher,
+{
+    fn lookup(&self, key: &Q) -> Option<&V> {
+        HashMap::get(self, key)
+    }
+
+    fn lookup_key(&self, key: &Q) -> Option<K>
+    where
+        K: Clone,
+    {
+        self.get_key_value(key).map(|(key, _)| key.clone())
+    }
+}
+
+impl<K: Hash + Eq, V, S: BuildHasher + Default> GrowableMapping<K, V> for HashMap<K, V, S> {
     type AddError = AlreadyIn;
 
     fn empty() -> Self {
-        HashMap::new()
+        HashMap::default()
     }
 
     fn add(&mut self, key: K, value: V) -> Result<(), Self::AddError> {
-        if self.insert(key, value).is_some() {
+        // checked first, so that the value of a present key is not overwritten
+        if self.contains_key(&key) {
             return Err(AlreadyIn);
         }
+        self.insert(key, value);
         Ok(())
     }
 
+    fn undo_add(&mut self, key: &K) {
+        self.remove(key);
+    }
+
     fn len(&self) -> usize {
         HashMap::len(self)
     }
+
+    fn reserve(&mut self, additional: usize) {
+        HashMap::reserve(self, additional)
+    }
+
+    fn shrink_to_fit(&mut self) {
+        HashMap::shrink_to_fit(self)
+    }
+}
+
+impl<K: Hash + Eq + Clone, V, S: BuildHasher> IterableMapping<K, V> for HashMap<K, V, S> {
+    fn entries<'a>(&'a self) -> impl Iterator<Item = (K, &'a V)> + 'a
+    where
+        V: 'a,
+    {
+        self.iter().map(|(key, value)| (key.clone(), value))
+    }
+}
+
+impl<K: Hash + Eq, V, S: BuildHasher> RemovableMapping<K, V> for HashMap<K, V, S> {
+    fn remove(&mut self, key: &K) -> Option<V> {
+        HashMap::remove(self, key)
+    }
 }
 
 impl<K: Ord, V> Mapping<K, V> for BTreeMap<K, V> {
@@ -185,6 +324,19 @@ impl<K: Ord, V> Mapping<K, V> for BTreeMap<K, V> {
     }
 }
 
+impl<K: Ord + Borrow<Q>, V, Q: Ord + ?Sized> Lookup<K, V, Q> for BTreeMap<K, V> {
+    fn lookup(&self, key: &Q) -> Option<&V> {
+        BTreeMap::get(self, key)
+    }
+
+    fn lookup_key(&self, key: &Q) -> Option<K>
+    where
+        K: Clone,
+    {
+        self.get_key_value(key).map(|(key, _)| key.clone())
+    }
+}
+
 impl<K: Ord, V> GrowableMapping<K, V> for BTreeMap<K, V> {
     type AddError = AlreadyIn;
 
@@ -193,17 +345,38 @@ impl<K: Ord, V> GrowableMapping<K, V> for BTreeMap<K, V> {
     }
 
     fn add(&mut self, key: K, value: V) -> Result<(), Self::AddError> {
-        if self.insert(key, value).is_some() {
+        // checked first, so that the value of a present key is not overwritten
+        if self.contains_key(&key) {
             return Err(AlreadyIn);
         }
+        self.insert(key, value);
         Ok(())
     }
 
+    fn undo_add(&mut self, key: &K) {
+        self.remove(key);
+    }
+
     fn len(&self) -> usize {
         BTreeMap::len(self)
     }
 }
 
+impl<K: Ord + Clone, V> IterableMapping<K, V> for BTreeMap<K, V> {
+    fn entries<'a>(&'a self) -> impl Iterator<Item = (K, &'a V)> + 'a
+    where
+        V: 'a,
+    {
+        self.iter().map(|(key, value)| (key.clone(), value))
+    }
+}
+
+impl<K: Ord, V> RemovableMapping<K, V> for BTreeMap<K, V> {
+    fn remove(&mut self, key: &K) -> Option<V> {
+        BTreeMap::remove(self, key)
+    }
+}
+
 impl<V, const N: usize> Mapping<usize, V> for [V; N] {
     fn get(&self, key: &usize) -> Option<&V> {
         if *key < self.len() {
@@ -222,6 +395,16 @@ impl<V, const N: usize> Mapping<usize, V> for [V; N] {
     }
 }
 
+impl<V, const N: usize> Lookup<usize, V> for [V; N] {
+    fn lookup(&self, key: &usize) -> Option<&V> {
+        Mapping::get(self, key)
+    }
+
+    fn lookup_key(&self, key: &usize) -> Option<usize> {
+        self.contains_key(key).then_some(*key)
+    }
+}
+
 impl<V> Mapping<usize, V> for [V] {
     fn get(&self, key: &usize) -> Option<&V> {
         if *key < self.len() {
@@ -240,6 +423,16 @@ impl<V> Mapping<usize, V> for [V] {
     }
 }
 
+impl<V> Lookup<usize, V> for [V] {
+    fn lookup(&self, key: &usize) -> Option<&V> {
+        Mapping::get(self, key)
+    }
+
+    fn lookup_key(&self, key: &usize) -> Option<usize> {
+        self.contains_key(key).then_some(*key)
+    }
+}
+
 impl<V> Mapping<usize, V> for Vec<V> {
     fn get(&self, key: &usize) -> Option<&V> {
         if *key < self.len() {
@@ -258,6 +451,16 @@ impl<V> Mapping<usize, V> for Vec<V> {
     }
 }
 
+impl<V> Lookup<usize, V> for Vec<V> {
+    fn lookup(&self, key: &usize) -> Option<&V> {
+        Mapping::get(self, key)
+    }
+
+    fn lookup_key(&self, key: &usize) -> Option<usize> {
+        self.contains_key(key).then_some(*key)
+    }
+}
+
 impl<V> GrowableMapping<usize, V> for Vec<V> {
     type AddError = NotInOrder;
 
@@ -274,9 +477,316 @@ impl<V> GrowableMapping<usize, V> for Vec<V> {
         }
     }
 
+    fn undo_add(&mut self, key: &usize) {
+        if *key + 1 == self.len() {
+            self.pop();
+        }
+    }
+
     fn len(&self) -> usize {
         Vec::len(self)
     }
+
+    fn reserve(&mut self, additional: usize) {
+        Vec::reserve(self, additional)
+    }
+
+    fn shrink_to_fit(&mut self) {
+        Vec::shrink_to_fit(self)
+    }
+}
+
+impl<V> IterableMapping<usize, V> for Vec<V> {
+    fn entries<'a>(&'a self) -> impl Iterator<Item = (usize, &'a V)> + 'a
+    where
+        V: 'a,
+    {
+        self.iter().enumerate()
+    }
+}
+
+/// A mapping from `usize` keys to values, backed by a [`Vec`]. Unlike a plain `Vec`, keys
+/// don't need to be added in order. A bitset keeps track of which keys were added, so that
+/// keys which were never added are not mistaken for elements, and can be skipped efficiently
+/// when iterating.
+///
+/// Slots of keys that were never added hold `V::default()`, so memory use is proportional
+/// to the largest key rather than to the number of keys.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct DenseMapping<V> {
+    values: Vec<V>,
+    present: FixedBitSet,
+    len: usize,
+}
+
+impl<V> DenseMapping<V> {
+    pub fn new() -> Self {
+        Self {
+            values: Vec::new(),
+            present: FixedBitSet::new(),
+            len: 0,
+        }
+    }
+
+    /// Creates an empty mapping with room for keys up to `capacity` without reallocating.
+    pub fn with_capacity(capacity: usize) -> Self {
+        Self {
+            values: Vec::with_capacity(capacity),
+            present: FixedBitSet::with_capacity(capacity),
+            len: 0,
+        }
+    }
+
+    /// All keys in the mapping, in increasing order.
+    pub fn keys(&self) -> impl Iterator<Item = usize> + '_ {
+        self.present.ones()
+    }
+
+    /// All keys in the mapping with their value, in increasing order of keys.
+    pub fn iter(&self) -> impl Iterator<Item = (usize, &V)> + '_ {
+        self.present.ones().map(|key| (key, &self.values[key]))
+    }
+}
+
+impl<V> Mapping<usize, V> for DenseMapping<V> {
+    fn get(&self, key: &usize) -> Option<&V> {
+        if self.present.contains(*key) {
+            Some(&self.values[*key])
+        } else {
+            None
+        }
+    }
+
+    fn set(&mut self, key: usize, value: V) {
+        if self.present.contains(key) {
+            self.values[key] = value;
+        } else {
+            panic!("can't set value of element which is not yet in mapping");
+        }
+    }
+}
+
+impl<V> Lookup<usize, V> for DenseMapping<V> {
+    fn lookup(&self, key: &usize) -> Option<&V> {
+        Mapping::get(self, key)
+    }
+
+    fn lookup_key(&self, key: &usize) -> Option<usize> {
+        self.contains_key(key).then_some(*key)
+    }
+}
+
+impl<V: Default> GrowableMapping<usize, V> for DenseMapping<V> {
+    type AddError = AlreadyIn;
+
+    fn empty() -> Self {
+        Self::new()
+    }
+
+    fn add(&mut self, key: usize, value: V) -> Result<(), Self::AddError> {
+        if self.present.contains(key) {
+            return Err(AlreadyIn);
+        }
+
+        if key >= self.values.len() {
+            self.values.resize_with(key + 1, V::default);
+            self.present.grow(key + 1);
+        }
+        self.values[key] = value;
+        self.present.insert(key);
+        self.len += 1;
+        Ok(())
+    }
+
+    fn undo_add(&mut self, key: &usize) {
+        if self.present.contains(*key) {
+            self.present.set(*key, false);
+            self.len -= 1;
+        }
+    }
+
+    fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Keys are stored at their own index, so this only helps when keys are added in
+    /// increasing order.
+    fn reserve(&mut self, additional: usize) {
+        self.values.reserve(additional)
+    }
+
+    fn shrink_to_fit(&mut self) {
+        self.values.shrink_to_fit()
+    }
+}
+
+impl<V> IterableMapping<usize, V> for DenseMapping<V> {
+    fn entries<'a>(&'a self) -> impl Iterator<Item = (usize, &'a V)> + 'a
+    where
+        V: 'a,
+    {
+        self.iter()
+    }
+}
+
+impl<V: Default> RemovableMapping<usize, V> for DenseMapping<V> {
+    fn remove(&mut self, key: &usize) -> Option<V> {
+        if !self.present.contains(*key) {
+            return None;
+        }
+        self.present.set(*key, false);
+        self.len -= 1;
+        Some(mem::take(&mut self.values[*key]))
+    }
+}
+
+/// A page of a [`PagedMapping`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Page<V> {
+    values: Vec<V>,
+    present: FixedBitSet,
+}
+
+/// A mapping from `u64` keys to values, for keys scattered over an enormous range but
+/// clustered in runs. Keys are divided into pages of `PAGE_SIZE` consecutive keys, and a page
+/// is only allocated once a key in it is added. Within a page, values are stored like in a
+/// [`DenseMapping`], so lookups need no hashing.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PagedMapping<V, const PAGE_SIZE: usize = 1024> {
+    pages: BTreeMap<u64, Page<V>>,
+    len: usize,
+}
+
+impl<V, const PAGE_SIZE: usize> Default for PagedMapping<V, PAGE_SIZE> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<V, const PAGE_SIZE: usize> PagedMapping<V, PAGE_SIZE> {
+    pub fn new() -> Self {
+        assert!(PAGE_SIZE > 0, "pages must have room for at least one key");
+        Self {
+            pages: BTreeMap::new(),
+            len: 0,
+        }
+    }
+
+    /// The number of allocated pages.
+    pub fn pages(&self) -> usize {
+        self.pages.len()
+    }
+
+    fn split(key: u64) -> (u64, usize) {
+        (key / PAGE_SIZE as u64, (key % PAGE_SIZE as u64) as usize)
+    }
+
+    /// All keys in the mapping, in increasing order.
+    pub fn keys(&self) -> impl Iterator<Item = u64> + '_ {
+        self.iter().map(|(key, _)| key)
+    }
+
+    /// All keys in the mapping with their value, in increasing order of keys.
+    pub fn iter(&self) -> impl Iterator<Item = (u64, &V)> + '_ {
+        self.pages.iter().flat_map(|(page_number, page)| {
+            page.present.ones().map(move |offset| {
+                (
+                    page_number * PAGE_SIZE as u64 + offset as u64,
+                    &page.values[offset],
+                )
+            })
+        })
+    }
+}
+
+impl<V, const PAGE_SIZE: usize> Mapping<u64, V> for PagedMapping<V, PAGE_SIZE> {
+    fn get(&self, key: &u64) -> Option<&V> {
+        let (page_number, offset) = Self::split(*key);
+        let page = self.pages.get(&page_number)?;
+        if page.present.contains(offset) {
+            Some(&page.values[offset])
+        } else {
+            None
+        }
+    }
+
+    fn set(&mut self, key: u64, value: V) {
+        let (page_number, offset) = Self::split(key);
+        match self.pages.get_mut(&page_number) {
+            Some(page) if page.present.contains(offset) => page.values[offset] = value,
+            _ => panic!("can't set value of element which is not yet in mapping"),
+        }
+    }
+}
+
+impl<V, const PAGE_SIZE: usize> Lookup<u64, V> for PagedMapping<V, PAGE_SIZE> {
+    fn lookup(&self, key: &u64) -> Option<&V> {
+        Mapping::get(self, key)
+    }
+
+    fn lookup_key(&self, key: &u64) -> Option<u64> {
+        self.contains_key(key).then_some(*key)
+    }
+}
+
+impl<V: Default, const PAGE_SIZE: usize> GrowableMapping<u64, V> for PagedMapping<V, PAGE_SIZE> {
+    type AddError = AlreadyIn;
+
+    fn empty() -> Self {
+        Self::new()
+    }
+
+    fn add(&mut self, key: u64, value: V) -> Result<(), Self::AddError> {
+        let (page_number, offset) = Self::split(key);
+        let page = self.pages.entry(page_number).or_insert_with(|| Page {
+            values: (0..PAGE_SIZE).map(|_| V::default()).collect(),
+            present: FixedBitSet::with_capacity(PAGE_SIZE),
+        });
+        if page.present.contains(offset) {
+            return Err(AlreadyIn);
+        }
+
+        page.values[offset] = value;
+        page.present.insert(offset);
+        self.len += 1;
+        Ok(())
+    }
+
+    fn undo_add(&mut self, key: &u64) {
+        let (page_number, offset) = Self::split(*key);
+        if let Some(page) = self.pages.get_mut(&page_number) {
+            if page.present.contains(offset) {
+                page.present.set(offset, false);
+                self.len -= 1;
+            }
+        }
+    }
+
+    fn len(&self) -> usize {
+        self.len
+    }
+}
+
+impl<V, const PAGE_SIZE: usize> IterableMapping<u64, V> for PagedMapping<V, PAGE_SIZE> {
+    fn entries<'a>(&'a self) -> impl Iterator<Item = (u64, &'a V)> + 'a
+    where
+        V: 'a,
+    {
+        self.iter()
+    }
+}
+
+impl<V: Default, const PAGE_SIZE: usize> RemovableMapping<u64, V> for PagedMapping<V, PAGE_SIZE> {
+    fn remove(&mut self, key: &u64) -> Option<V> {
+        let (page_number, offset) = Self::split(*key);
+        let page = self.pages.get_mut(&page_number)?;
+        if !page.present.contains(offset) {
+            return None;
+        }
+        page.present.set(offset, false);
+        self.len -= 1;
+        Some(mem::take(&mut page.values[offset]))
+    }
 }
 
 /// A wrapper for types that normally implement [`GrowableMapping`], but which
@@ -309,3 +819,19 @@ where
         self.0.set(key, value);
     }
 }
+
+impl<K, V, Q: ?Sized, M> Lookup<K, V, Q> for FixedSize<M>
+where
+    M: Lookup<K, V, Q>,
+{
+    fn lookup(&self, key: &Q) -> Option<&V> {
+        self.0.lookup(key)
+    }
+
+    fn lookup_key(&self, key: &Q) -> Option<K>
+    where
+        K: Clone,
+    {
+        self.0.lookup_key(key)
+    }
+}
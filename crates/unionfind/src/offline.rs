@@ -0,0 +1,160 @@
+use crate::rollback::RollbackUnionFind;
+use crate::HashUnionFindByRank;
+use std::collections::{HashMap, HashSet};
+use std::hash::Hash;
+use std::ops::Range;
+
+/// An event in a timeline processed by [`answer_connectivity_queries`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Event<T> {
+    /// Inserts an edge between two vertices.
+    Edge(T, T),
+    /// Asks whether two vertices are connected by the edges inserted before this query.
+    Connected(T, T),
+}
+
+/// Processes a timeline of edge insertions interleaved with connectivity queries in one pass.
+/// Vertices are added when they first occur.
+///
+/// Returns the answers to the queries in the order the queries occur in the timeline,
+/// so the answer to the `i`th query is at index `i`.
+pub fn answer_connectivity_queries<T: Hash + Eq + Clone>(
+    events: impl IntoIterator<Item = Event<T>>,
+) -> Vec<bool> {
+    let mut uf = HashUnionFindByRank::<T>::empty();
+    let mut answers = Vec::new();
+
+    for event in events {
+        match event {
+            Event::Edge(u, v) => uf.union_adding(u, v),
+            Event::Connected(u, v) => {
+                let root_u = uf
+                    .get_or_insert_with(u, Default::default)
+                    .expect("vertex was not present");
+                let root_v = uf
+                    .get_or_insert_with(v, Default::default)
+                    .expect("vertex was not present");
+                answers.push(root_u == root_v);
+            }
+        }
+    }
+
+    answers
+}
+
+/// An event in a timeline processed by [`answer_dynamic_connectivity_queries`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DynamicEvent<T> {
+    /// Inserts an undirected edge between two vertices.
+    Insert(T, T),
+    /// Deletes an edge that was inserted before. When the edge was inserted several times,
+    /// one of the insertions is undone. Deleting an edge that is not present does nothing.
+    Delete(T, T),
+    /// Asks whether two vertices are connected by the edges present at this point.
+    Connected(T, T),
+}
+
+/// Like [`answer_connectivity_queries`], but edges can also be deleted. Answers all queries
+/// offline in `O((n + q) log q log n)` time, for `n` events and `q` queries.
+///
+/// Every edge is present during a range of queries. These ranges are split over a segment
+/// tree over the queries, so that every edge is stored in `O(log q)` nodes. The tree is then
+/// traversed depth first with a [`RollbackUnionFind`]: the edges of a node are unioned when
+/// entering it and rolled back when leaving it, so at every leaf exactly the edges present
+/// during its query are unioned.
+pub fn answer_dynamic_connectivity_queries<T: Hash + Eq + Clone>(
+    events: impl IntoIterator<Item = DynamicEvent<T>>,
+) -> Vec<bool> {
+    let mut vertices = HashSet::new();
+    let mut queries = Vec::new();
+    // the queries before which every present edge was inserted, per edge
+    let mut present: HashMap<(T, T), Vec<usize>> = HashMap::new();
+    let mut edges = Vec::new();
+
+    for event in events {
+        match event {
+            DynamicEvent::Insert(u, v) => {
+                vertices.extend([u.clone(), v.clone()]);
+                present.entry((u, v)).or_default().push(queries.len());
+            }
+            DynamicEvent::Delete(u, v) => {
+                // the edge may have been inserted in either orientation, or in both
+                let forward = (u.clone(), v.clone());
+                let key = match present.get(&forward) {
+                    Some(inserted) if !inserted.is_empty() => forward,
+                    _ => (v, u),
+                };
+                if let Some(inserted) = present.get_mut(&key).and_then(Vec::pop) {
+                    edges.push((key.0, key.1, inserted..queries.len()));
+                }
+            }
+            DynamicEvent::Connected(u, v) => {
+                vertices.extend([u.clone(), v.clone()]);
+                queries.push((u, v));
+            }
+        }
+    }
+    for ((u, v), inserted) in present {
+        for start in inserted {
+            edges.push((u.clone(), v.clone(), start..queries.len()));
+        }
+    }
+
+    let mut tree = vec![Vec::new(); 4 * queries.len()];
+    for (u, v, alive) in edges {
+        insert_edge(&mut tree, 1, 0..queries.len(), &alive, (u, v));
+    }
+
+    let mut uf = RollbackUnionFind::new(vertices);
+    let mut answers = vec![false; queries.len()];
+    if !queries.is_empty() {
+        answer_segment(&tree, 1, 0..queries.len(), &queries, &mut uf, &mut answers);
+    }
+    answers
+}
+
+/// Stores an edge in the nodes of the segment tree that exactly cover the queries `alive`.
+/// `node` covers the queries `range`.
+fn insert_edge<T: Clone>(
+    tree: &mut [Vec<(T, T)>],
+    node: usize,
+    range: Range<usize>,
+    alive: &Range<usize>,
+    edge: (T, T),
+) {
+    if alive.end <= range.start || range.end <= alive.start {
+        return;
+    }
+    if alive.start <= range.start && range.end <= alive.end {
+        tree[node].push(edge);
+        return;
+    }
+    let mid = (range.start + range.end) / 2;
+    insert_edge(tree, 2 * node, range.start..mid, alive, edge.clone());
+    insert_edge(tree, 2 * node + 1, mid..range.end, alive, edge);
+}
+
+fn answer_segment<T: Hash + Eq + Clone>(
+    tree: &[Vec<(T, T)>],
+    node: usize,
+    range: Range<usize>,
+    queries: &[(T, T)],
+    uf: &mut RollbackUnionFind<T>,
+    answers: &mut [bool],
+) {
+    let snapshot = uf.snapshot();
+    for (u, v) in &tree[node] {
+        uf.union(u, v).expect("vertices were added");
+    }
+
+    if range.len() == 1 {
+        let (u, v) = &queries[range.start];
+        answers[range.start] = uf.connected(u, v).expect("vertices were added");
+    } else {
+        let mid = (range.start + range.end) / 2;
+        answer_segment(tree, 2 * node, range.start..mid, queries, uf, answers);
+        answer_segment(tree, 2 * node + 1, mid..range.end, queries, uf, answers);
+    }
+
+    uf.rollback(snapshot);
+}
@@ -0,0 +1,166 @@
+//! Integration with [rayon](https://docs.rs/rayon), available with the `rayon` feature.
+
+use crate::extra::{ByRank, Extra, GrowableExtra, RecomputeExtra};
+use crate::generic::{UnionByRankError, UnionFind};
+use crate::mapping::Mapping;
+use rayon::iter::{
+    FromParallelIterator, IntoParallelIterator, IntoParallelRefIterator, ParallelExtend,
+    ParallelIterator,
+};
+use std::collections::{HashMap, HashSet};
+use std::hash::Hash;
+
+/// Collects elements into a union find where every element is in a class on its own.
+///
+/// # Panics
+///
+/// Panics if the extra information can't be constructed for the elements. Use
+/// [`try_new`](UnionFind::try_new) to get the error instead.
+impl<T, V, E> FromParallelIterator<T> for UnionFind<T, V, E>
+where
+    T: Hash + Eq + Clone + Send,
+    E: Extra<T, V>,
+{
+    fn from_par_iter<I>(elems: I) -> Self
+    where
+        I: IntoParallelIterator<Item = T>,
+    {
+        let parent: HashMap<T, T> = elems
+            .into_par_iter()
+            .map(|elem| (elem.clone(), elem))
+            .collect();
+        let extra =
+            E::default_mapping(parent.keys().cloned()).expect("couldn't construct extra mapping");
+        Self::from_raw_parts_unchecked(parent, extra)
+    }
+}
+
+/// Adds elements that are not in the union find yet, each in a class on its own.
+///
+/// # Panics
+///
+/// Panics if an element can't be added to the extra information. Use
+/// [`add`](UnionFind::add) to get the error instead.
+impl<T, V, E> ParallelExtend<T> for UnionFind<T, V, E>
+where
+    T: Hash + Eq + Clone + Send,
+    E: GrowableExtra<T, V>,
+    V: Default,
+{
+    fn par_extend<I>(&mut self, elems: I)
+    where
+        I: IntoParallelIterator<Item = T>,
+    {
+        let elems: HashSet<T> = elems.into_par_iter().collect();
+        for elem in elems {
+            if !self.raw().contains_key(&elem) {
+                self.add(elem)
+                    .expect("couldn't add element to the extra information");
+            }
+        }
+    }
+}
+
+impl<T: Hash + Eq + Clone + Send + Sync> UnionFind<T, usize, ByRank<T>> {
+    /// A parallel version of [`from_pairs`](UnionFind::from_pairs).
+    ///
+    /// Every rayon task builds a union find from part of the pairs, after which
+    /// these union finds are merged.
+    pub fn par_from_pairs(pairs: impl IntoParallelIterator<Item = (T, T)>) -> Self {
+        pairs
+            .into_par_iter()
+            .fold(
+                || Self::empty(),
+                |mut uf, (a, b)| {
+                    uf.union_adding(a, b);
+                    uf
+                },
+            )
+            .reduce(
+                || Self::empty(),
+                |a, b| {
+                    let (mut larger, smaller) = if a.len() >= b.len() { (a, b) } else { (b, a) };
+                    larger
+                        .absorb(smaller)
+                        .expect("no representatives are pinned");
+                    larger
+                },
+            )
+    }
+
+    /// Unions every pair of elements by rank, adding elements that are not present yet.
+    /// Returns how many unions merged two classes that were not equivalent before.
+    ///
+    /// The pairs are first combined into a union find in parallel, like
+    /// [`par_from_pairs`](UnionFind::par_from_pairs), which is then
+    /// [absorbed](UnionFind::absorb). Only one union per element of that union find is made
+    /// on this one, so this is much faster than making every union here when there are many
+    /// more pairs than elements.
+    ///
+    /// When a union fails because the representatives of both classes are pinned, the unions
+    /// made up to that point are kept.
+    pub fn union_all_par(
+        &mut self,
+        pairs: impl IntoParallelIterator<Item = (T, T)>,
+    ) -> Result<usize, UnionByRankError> {
+        self.absorb(Self::par_from_pairs(pairs))
+    }
+}
+
+impl<T, V, E, M> UnionFind<T, V, E, M>
+where
+    T: Hash + Eq + Clone + Send + Sync,
+    M: Mapping<T, T>,
+    Self: Sync,
+{
+    /// Finds the representatives of many elements in parallel, in the same order as the
+    /// elements. Performs no path shortening, so the union find is not modified.
+    ///
+    /// Finds are fastest after [`compress_all`](UnionFind::compress_all), which makes every
+    /// find take a single step.
+    pub fn par_find_many(&self, elems: &[T]) -> Vec<Option<T>> {
+        elems.par_iter().map(|elem| self.find(elem)).collect()
+    }
+}
+
+impl<T, V, E> UnionFind<T, V, E>
+where
+    T: Hash + Ord + Clone + Send + Sync,
+    E: RecomputeExtra<T>,
+{
+    /// Like [`par_from_pairs`](UnionFind::par_from_pairs), but the result does not depend on
+    /// how rayon splits up the work. Which element becomes the representative of a class in
+    /// `par_from_pairs` depends on the order in which the unions happen, which can differ
+    /// between runs and thread counts.
+    ///
+    /// Here, the smallest element of every class becomes its representative and every other
+    /// element points directly to it. The extra information is then recomputed from these
+    /// parents, so the result is the same for every run and thread count.
+    pub fn par_from_pairs_deterministic(pairs: impl IntoParallelIterator<Item = (T, T)>) -> Self {
+        let uf = UnionFind::<T, usize, ByRank<T>>::par_from_pairs(pairs);
+
+        let mut smallest: HashMap<T, T> = HashMap::new();
+        for elem in uf.raw().keys() {
+            let root = uf.find(elem).expect("element is present");
+            smallest
+                .entry(root)
+                .and_modify(|min| {
+                    if elem < min {
+                        *min = elem.clone();
+                    }
+                })
+                .or_insert_with(|| elem.clone());
+        }
+
+        let parent: HashMap<T, T> = uf
+            .raw()
+            .keys()
+            .map(|elem| {
+                let root = uf.find(elem).expect("element is present");
+                (elem.clone(), smallest[&root].clone())
+            })
+            .collect();
+        let extra = E::recompute(&parent);
+        Self::from_raw_parts_unchecked(parent, extra)
+    }
+}
@@ -0,0 +1,225 @@
+//! Site and bond percolation on a square lattice, the textbook application of union finds.
+//!
+//! Cells are identified by `(x, y)`, where `y == 0` is the top row. The lattice percolates
+//! when there is a path of open cells, or open bonds, from the top row to the bottom row.
+//! Both are detected with virtual top and bottom nodes, connected to every cell of the top and
+//! bottom row, so that checking whether the lattice percolates is a single find.
+//!
+//! To label all clusters of a lattice at once, like Hoshen–Kopelman, use
+//! [`label_grid`](crate::grid::label_grid).
+
+use crate::dense::VecUnionFind;
+
+/// The cells of a lattice and the virtual top and bottom nodes.
+#[derive(Debug, Clone)]
+struct Lattice {
+    width: usize,
+    height: usize,
+    uf: VecUnionFind,
+    /// Like `uf`, but without the bottom node. Otherwise every cell connected to the bottom
+    /// row would be full as soon as the lattice percolates, even if it's not connected to the
+    /// top row itself.
+    without_bottom: VecUnionFind,
+}
+
+impl Lattice {
+    fn new(width: usize, height: usize) -> Self {
+        let cells = width * height;
+        Self {
+            width,
+            height,
+            uf: VecUnionFind::with_len(cells + 2),
+            without_bottom: VecUnionFind::with_len(cells + 1),
+        }
+    }
+
+    fn top(&self) -> usize {
+        self.width * self.height
+    }
+
+    fn bottom(&self) -> usize {
+        self.top() + 1
+    }
+
+    /// # Panics
+    ///
+    /// Panics if the cell is outside the lattice.
+    fn cell(&self, (x, y): (usize, usize)) -> usize {
+        assert!(
+            x < self.width && y < self.height,
+            "cell ({x}, {y}) is outside the lattice"
+        );
+        y * self.width + x
+    }
+
+    fn union(&mut self, cell1: usize, cell2: usize) {
+        self.uf
+            .union_by_rank(&cell1, &cell2)
+            .expect("cells are in the lattice");
+        self.without_bottom
+            .union_by_rank(&cell1, &cell2)
+            .expect("cells are in the lattice");
+    }
+
+    /// Connects a cell to the virtual nodes, if it is in the top or bottom row.
+    fn attach(&mut self, (x, y): (usize, usize)) {
+        let cell = self.cell((x, y));
+        if y == 0 {
+            self.union(cell, self.top());
+        }
+        if y == self.height - 1 {
+            let bottom = self.bottom();
+            self.uf
+                .union_by_rank(&cell, &bottom)
+                .expect("cells are in the lattice");
+        }
+    }
+
+    fn is_full(&mut self, cell: (usize, usize)) -> bool {
+        let cell = self.cell(cell);
+        let top = self.top();
+        self.without_bottom.connected(&cell, &top) == Some(true)
+    }
+
+    fn percolates(&mut self) -> bool {
+        let (top, bottom) = (self.top(), self.bottom());
+        self.uf.connected(&top, &bottom) == Some(true)
+    }
+
+    fn neighbours(&self, (x, y): (usize, usize)) -> impl Iterator<Item = (usize, usize)> {
+        let (width, height) = (self.width, self.height);
+        [
+            x.checked_sub(1).map(|left| (left, y)),
+            (x + 1 < width).then_some((x + 1, y)),
+            y.checked_sub(1).map(|above| (x, above)),
+            (y + 1 < height).then_some((x, y + 1)),
+        ]
+        .into_iter()
+        .flatten()
+    }
+}
+
+/// Site percolation: cells start blocked and are opened one at a time. Open cells are
+/// connected to their open neighbours left, right, above and below.
+#[derive(Debug, Clone)]
+pub struct SitePercolation {
+    lattice: Lattice,
+    open: Vec<bool>,
+    num_open: usize,
+}
+
+impl SitePercolation {
+    /// Creates a `width` by `height` lattice where all cells are blocked.
+    pub fn new(width: usize, height: usize) -> Self {
+        Self {
+            lattice: Lattice::new(width, height),
+            open: vec![false; width * height],
+            num_open: 0,
+        }
+    }
+
+    /// Opens a cell, connecting it to its open neighbours. Returns false if the cell was
+    /// already open.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the cell is outside the lattice.
+    pub fn open(&mut self, x: usize, y: usize) -> bool {
+        let cell = self.lattice.cell((x, y));
+        if self.open[cell] {
+            return false;
+        }
+        self.open[cell] = true;
+        self.num_open += 1;
+
+        self.lattice.attach((x, y));
+        let neighbours: Vec<usize> = self
+            .lattice
+            .neighbours((x, y))
+            .map(|neighbour| self.lattice.cell(neighbour))
+            .filter(|&neighbour| self.open[neighbour])
+            .collect();
+        for neighbour in neighbours {
+            self.lattice.union(cell, neighbour);
+        }
+        true
+    }
+
+    /// # Panics
+    ///
+    /// Panics if the cell is outside the lattice.
+    pub fn is_open(&self, x: usize, y: usize) -> bool {
+        self.open[self.lattice.cell((x, y))]
+    }
+
+    /// Returns whether an open cell is connected to the top row through open cells.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the cell is outside the lattice.
+    pub fn is_full(&mut self, x: usize, y: usize) -> bool {
+        self.is_open(x, y) && self.lattice.is_full((x, y))
+    }
+
+    /// The number of open cells.
+    pub fn num_open(&self) -> usize {
+        self.num_open
+    }
+
+    /// Returns whether the top row is connected to the bottom row through open cells.
+    pub fn percolates(&mut self) -> bool {
+        self.lattice.percolates()
+    }
+}
+
+/// Bond percolation: all cells are present, and the bonds between neighbouring cells
+/// start closed and are opened one at a time.
+#[derive(Debug, Clone)]
+pub struct BondPercolation {
+    lattice: Lattice,
+}
+
+impl BondPercolation {
+    /// Creates a `width` by `height` lattice where all bonds are closed.
+    pub fn new(width: usize, height: usize) -> Self {
+        let mut lattice = Lattice::new(width, height);
+        if height > 0 {
+            for y in [0, height - 1] {
+                for x in 0..width {
+                    lattice.attach((x, y));
+                }
+            }
+        }
+        Self { lattice }
+    }
+
+    /// Opens the bond between two neighbouring cells.
+    ///
+    /// # Panics
+    ///
+    /// Panics if a cell is outside the lattice, or if the cells are not neighbours.
+    pub fn open(&mut self, cell1: (usize, usize), cell2: (usize, usize)) {
+        assert!(
+            self.lattice
+                .neighbours(cell1)
+                .any(|neighbour| neighbour == cell2),
+            "cells {cell1:?} and {cell2:?} are not neighbours"
+        );
+        let (cell1, cell2) = (self.lattice.cell(cell1), self.lattice.cell(cell2));
+        self.lattice.union(cell1, cell2);
+    }
+
+    /// Returns whether a cell is connected to the top row through open bonds.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the cell is outside the lattice.
+    pub fn is_full(&mut self, x: usize, y: usize) -> bool {
+        self.lattice.is_full((x, y))
+    }
+
+    /// Returns whether the top row is connected to the bottom row through open bonds.
+    pub fn percolates(&mut self) -> bool {
+        self.lattice.percolates()
+    }
+}
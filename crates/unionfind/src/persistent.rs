@@ -0,0 +1,271 @@
+//! A persistent union find, of which every version stays available after a union.
+//!
+//! Versions share most of their structure, so a union only allocates memory logarithmic in the
+//! number of elements, and cloning a version takes constant time. This makes it cheap to try
+//! out many different unions starting from the same union find.
+
+use crate::generic::{UnionError, UnionStatus};
+use std::cmp::Ordering;
+use std::convert::Infallible;
+use std::hash::{BuildHasher, Hash, RandomState};
+use std::rc::Rc;
+
+/// The number of bits of the hash consumed by every level of the trie.
+const BITS: u32 = 4;
+const BRANCHING: usize = 1 << BITS;
+
+#[derive(Debug)]
+enum Node<K, V> {
+    /// All entries whose keys have the same hash.
+    Leaf { hash: u64, entries: Vec<(K, V)> },
+    Branch([Option<Rc<Node<K, V>>>; BRANCHING]),
+}
+
+/// The child of a branch at `depth` in which an entry with `hash` is stored.
+fn slot(hash: u64, depth: u32) -> usize {
+    ((hash >> (depth * BITS)) as usize) & (BRANCHING - 1)
+}
+
+impl<K: Eq + Clone, V: Clone> Node<K, V> {
+    fn get(&self, hash: u64, key: &K, depth: u32) -> Option<&V> {
+        match self {
+            Node::Leaf {
+                hash: leaf_hash,
+                entries,
+            } => {
+                if *leaf_hash != hash {
+                    return None;
+                }
+                entries.iter().find(|(k, _)| k == key).map(|(_, v)| v)
+            }
+            Node::Branch(children) => children[slot(hash, depth)]
+                .as_ref()?
+                .get(hash, key, depth + 1),
+        }
+    }
+
+    /// Returns a copy of this node with the entry inserted, sharing all unchanged children,
+    /// and whether the key is new.
+    fn insert(&self, hash: u64, key: K, value: V, depth: u32) -> (Node<K, V>, bool) {
+        match self {
+            Node::Leaf {
+                hash: leaf_hash,
+                entries,
+            } if *leaf_hash == hash => {
+                let mut entries = entries.clone();
+                let added = match entries.iter_mut().find(|(k, _)| *k == key) {
+                    Some(entry) => {
+                        entry.1 = value;
+                        false
+                    }
+                    None => {
+                        entries.push((key, value));
+                        true
+                    }
+                };
+                (Node::Leaf { hash, entries }, added)
+            }
+            Node::Leaf {
+                hash: leaf_hash,
+                entries,
+            } => {
+                // Two different hashes always differ in some slot, so splitting terminates.
+                let mut children: [Option<Rc<Node<K, V>>>; BRANCHING] = Default::default();
+                children[slot(*leaf_hash, depth)] = Some(Rc::new(Node::Leaf {
+                    hash: *leaf_hash,
+                    entries: entries.clone(),
+                }));
+                Node::Branch(children).insert(hash, key, value, depth)
+            }
+            Node::Branch(children) => {
+                let mut children = children.clone();
+                let child = &mut children[slot(hash, depth)];
+                let (new_child, added) = match child {
+                    Some(node) => node.insert(hash, key, value, depth + 1),
+                    None => (
+                        Node::Leaf {
+                            hash,
+                            entries: vec![(key, value)],
+                        },
+                        true,
+                    ),
+                };
+                *child = Some(Rc::new(new_child));
+                (Node::Branch(children), added)
+            }
+        }
+    }
+}
+
+/// A persistent hash map, implemented as a trie over the hashes of the keys. Inserting
+/// copies the path to the entry and shares everything else with the previous version.
+#[derive(Debug)]
+struct PersistentMap<K, V> {
+    root: Option<Rc<Node<K, V>>>,
+    len: usize,
+    hasher: RandomState,
+}
+
+impl<K, V> Clone for PersistentMap<K, V> {
+    fn clone(&self) -> Self {
+        Self {
+            root: self.root.clone(),
+            len: self.len,
+            hasher: self.hasher.clone(),
+        }
+    }
+}
+
+impl<K: Hash + Eq + Clone, V: Clone> PersistentMap<K, V> {
+    fn new() -> Self {
+        Self {
+            root: None,
+            len: 0,
+            hasher: RandomState::new(),
+        }
+    }
+
+    fn get(&self, key: &K) -> Option<&V> {
+        let hash = self.hasher.hash_one(key);
+        self.root.as_ref()?.get(hash, key, 0)
+    }
+
+    /// Returns a new version of the map with the entry inserted.
+    fn insert(&self, key: K, value: V) -> Self {
+        let hash = self.hasher.hash_one(&key);
+        let (root, added) = match &self.root {
+            Some(root) => root.insert(hash, key, value, 0),
+            None => (
+                Node::Leaf {
+                    hash,
+                    entries: vec![(key, value)],
+                },
+                true,
+            ),
+        };
+        Self {
+            root: Some(Rc::new(root)),
+            len: self.len + added as usize,
+            hasher: self.hasher.clone(),
+        }
+    }
+}
+
+/// A persistent union find. Operations that modify the union find return a new version,
+/// and leave the version they were called on unchanged.
+///
+/// Unions are made by rank and no path shortening is performed, so finds take logarithmic
+/// time. Cloning a version is cheap, since all versions share their structure.
+#[derive(Debug)]
+pub struct PersistentUnionFind<T> {
+    /// For every element its parent, and for representatives also the rank.
+    nodes: PersistentMap<T, (T, usize)>,
+    sets: usize,
+}
+
+impl<T> Clone for PersistentUnionFind<T> {
+    fn clone(&self) -> Self {
+        Self {
+            nodes: self.nodes.clone(),
+            sets: self.sets,
+        }
+    }
+}
+
+impl<T: Hash + Eq + Clone> Default for PersistentUnionFind<T> {
+    fn default() -> Self {
+        Self::new([])
+    }
+}
+
+impl<T: Hash + Eq + Clone> PersistentUnionFind<T> {
+    /// Creates a union find in which every element is in a class on its own.
+    pub fn new(elems: impl IntoIterator<Item = T>) -> Self {
+        let mut uf = Self {
+            nodes: PersistentMap::new(),
+            sets: 0,
+        };
+        for elem in elems {
+            uf = uf.add(elem);
+        }
+        uf
+    }
+
+    pub fn len(&self) -> usize {
+        self.nodes.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.nodes.len == 0
+    }
+
+    /// The number of classes.
+    pub fn num_sets(&self) -> usize {
+        self.sets
+    }
+
+    /// Returns a version with `elem` added in a class on its own. If it was already present,
+    /// the version is the same as this one.
+    pub fn add(&self, elem: T) -> Self {
+        if self.nodes.get(&elem).is_some() {
+            return self.clone();
+        }
+        Self {
+            nodes: self.nodes.insert(elem.clone(), (elem, 0)),
+            sets: self.sets + 1,
+        }
+    }
+
+    /// Find the representative of an element, together with its rank.
+    fn root(&self, elem: &T) -> Option<(T, usize)> {
+        let mut current = elem.clone();
+        loop {
+            let (parent, rank) = self.nodes.get(&current)?;
+            if *parent == current {
+                return Some((current, *rank));
+            }
+            current = parent.clone();
+        }
+    }
+
+    /// Find the representative of an element.
+    pub fn find(&self, elem: &T) -> Option<T> {
+        self.root(elem).map(|(root, _)| root)
+    }
+
+    /// Returns whether two elements are in the same class, or `None` if either of them
+    /// is not in the union find.
+    pub fn connected(&self, elem1: &T, elem2: &T) -> Option<bool> {
+        Some(self.find(elem1)? == self.find(elem2)?)
+    }
+
+    /// Returns a version in which the classes of two elements are unioned by rank.
+    pub fn union(
+        &self,
+        elem1: &T,
+        elem2: &T,
+    ) -> Result<(Self, UnionStatus), UnionError<Infallible>> {
+        let (root1, rank1) = self.root(elem1).ok_or(UnionError::Elem1NotFound)?;
+        let (root2, rank2) = self.root(elem2).ok_or(UnionError::Elem2NotFound)?;
+        if root1 == root2 {
+            return Ok((self.clone(), UnionStatus::AlreadyEquivalent));
+        }
+
+        let (child, root, rank) = match rank1.cmp(&rank2) {
+            Ordering::Less => (root1, root2, rank2),
+            Ordering::Equal => (root2, root1, rank1 + 1),
+            Ordering::Greater => (root2, root1, rank1),
+        };
+        let mut nodes = self.nodes.insert(child, (root.clone(), 0));
+        if rank != rank1.max(rank2) {
+            nodes = nodes.insert(root.clone(), (root, rank));
+        }
+        Ok((
+            Self {
+                nodes,
+                sets: self.sets - 1,
+            },
+            UnionStatus::PerformedUnion,
+        ))
+    }
+}
@@ -0,0 +1,123 @@
+use std::fmt::{Debug, Formatter};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use thiserror::Error;
+
+/// A token that can be used to cancel a bulk operation, possibly from another thread.
+/// Clones of a token share their state: cancelling one cancels all of them.
+#[derive(Debug, Clone, Default)]
+pub struct CancellationToken(Arc<AtomicBool>);
+
+impl CancellationToken {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Request cancellation of every operation using this token.
+    pub fn cancel(&self) {
+        self.0.store(true, Ordering::Relaxed);
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.0.load(Ordering::Relaxed)
+    }
+}
+
+/// Errors returned by bulk operations that accept a [`Progress`].
+#[derive(Debug, Error, PartialEq)]
+pub enum BulkError<E> {
+    /// The operation was cancelled through a [`CancellationToken`]. The first `processed`
+    /// items were processed, and their effects are kept.
+    #[error("the operation was cancelled after processing {processed} items")]
+    Cancelled { processed: usize },
+
+    /// Processing an item failed. All items before it were processed, and their effects are kept.
+    #[error("could not process item {index}")]
+    Item {
+        index: usize,
+        #[source]
+        error: E,
+    },
+}
+
+/// Progress reporting and cancellation for bulk operations.
+///
+/// It is accepted by the `_with_progress` variants of
+/// [`union_all`](crate::generic::UnionFind::union_all_with_progress),
+/// [`union_all_by_rank`](crate::generic::UnionFind::union_all_by_rank_with_progress),
+/// [`from_pairs`](crate::generic::UnionFind::from_pairs_with_progress) and
+/// [`components_of_edge_list`](crate::external::components_of_edge_list_with_progress).
+/// [`canonicalize`](crate::generic::UnionFind::canonicalize) and serialization with serde
+/// don't accept one: the former can't stop halfway, and the latter is driven by the serializer.
+///
+/// ```
+/// # use unionfind::progress::{CancellationToken, Progress};
+/// let token = CancellationToken::new();
+/// let mut progress = Progress::none()
+///     .report_every(1000, |processed| println!("processed {processed} items"))
+///     .cancel_with(token.clone());
+/// ```
+pub struct Progress<'a> {
+    interval: usize,
+    callback: Option<Box<dyn FnMut(usize) + 'a>>,
+    cancellation: Option<CancellationToken>,
+}
+
+impl Debug for Progress<'_> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Progress")
+            .field("interval", &self.interval)
+            .field("callback", &self.callback.is_some())
+            .field("cancellation", &self.cancellation)
+            .finish()
+    }
+}
+
+impl Default for Progress<'_> {
+    fn default() -> Self {
+        Self::none()
+    }
+}
+
+impl<'a> Progress<'a> {
+    /// No progress reporting, and no cancellation.
+    pub fn none() -> Self {
+        Self {
+            interval: usize::MAX,
+            callback: None,
+            cancellation: None,
+        }
+    }
+
+    /// Call `callback` with the number of processed items every time another
+    /// `interval` items have been processed.
+    pub fn report_every(mut self, interval: usize, callback: impl FnMut(usize) + 'a) -> Self {
+        self.interval = interval.max(1);
+        self.callback = Some(Box::new(callback));
+        self
+    }
+
+    /// Stop the operation as soon as `token` is cancelled.
+    pub fn cancel_with(mut self, token: CancellationToken) -> Self {
+        self.cancellation = Some(token);
+        self
+    }
+
+    /// Must be called before processing item `index`.
+    /// Returns an error when the operation should stop.
+    pub(crate) fn check<E>(&self, index: usize) -> Result<(), BulkError<E>> {
+        match &self.cancellation {
+            Some(token) if token.is_cancelled() => Err(BulkError::Cancelled { processed: index }),
+            _ => Ok(()),
+        }
+    }
+
+    /// Must be called after `processed` items have been processed.
+    pub(crate) fn processed(&mut self, processed: usize) {
+        if processed.is_multiple_of(self.interval) {
+            if let Some(callback) = &mut self.callback {
+                callback(processed);
+            }
+        }
+    }
+}
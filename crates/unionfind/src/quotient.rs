@@ -0,0 +1,78 @@
+use crate::generic::UnionFind;
+use std::collections::HashSet;
+use std::hash::Hash;
+
+impl<T: Hash + Eq + Clone, V, E> UnionFind<T, V, E> {
+    /// The representative of `elem`, or `elem` itself if it is not in the union find.
+    fn representative_or_self(&self, elem: T) -> T {
+        self.find(&elem).unwrap_or(elem)
+    }
+
+    /// Contracts every class to a single vertex, its representative. The endpoints of every
+    /// edge are mapped to their representatives, and edges that end up as self-loops are dropped.
+    /// Endpoints that are not in the union find are kept as they are.
+    ///
+    /// Edges are treated as directed. When `deduplicate` is true, only the first of several
+    /// parallel edges is kept.
+    pub fn quotient_edges(
+        &self,
+        edges: impl IntoIterator<Item = (T, T)>,
+        deduplicate: bool,
+    ) -> Vec<(T, T)> {
+        let mut seen = HashSet::new();
+        let mut quotient = Vec::new();
+
+        for (u, v) in edges {
+            let u = self.representative_or_self(u);
+            let v = self.representative_or_self(v);
+            if u == v {
+                continue;
+            }
+            if deduplicate && !seen.insert((u.clone(), v.clone())) {
+                continue;
+            }
+            quotient.push((u, v));
+        }
+
+        quotient
+    }
+
+    /// Like [`quotient_edges`](UnionFind::quotient_edges), but builds a directed graph with a
+    /// node for every class, including classes without edges. Parallel edges are merged into a
+    /// single edge, weighted by the number of edges that were merged.
+    #[cfg(feature = "petgraph")]
+    pub fn quotient_graph(
+        &self,
+        edges: impl IntoIterator<Item = (T, T)>,
+    ) -> petgraph::Graph<T, usize> {
+        use std::collections::HashMap;
+
+        let mut graph = petgraph::Graph::new();
+        let mut nodes = HashMap::new();
+        let mut node = |graph: &mut petgraph::Graph<T, usize>, root: T| {
+            *nodes
+                .entry(root.clone())
+                .or_insert_with(|| graph.add_node(root))
+        };
+
+        for (elem, parent) in self.raw() {
+            if elem == parent {
+                node(&mut graph, elem.clone());
+            }
+        }
+
+        let mut merged = HashMap::new();
+        for (u, v) in self.quotient_edges(edges, false) {
+            let u = node(&mut graph, u);
+            let v = node(&mut graph, v);
+            match merged.get(&(u, v)) {
+                Some(&edge) => graph[edge] += 1,
+                None => {
+                    merged.insert((u, v), graph.add_edge(u, v, 1));
+                }
+            }
+        }
+
+        graph
+    }
+}
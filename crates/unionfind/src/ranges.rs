@@ -0,0 +1,271 @@
+use crate::HashUnionFindByRank;
+use std::collections::HashMap;
+use std::fmt::{Display, Formatter};
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
+use std::ops::RangeInclusive;
+use std::str::FromStr;
+use thiserror::Error;
+
+/// Values that have a next value, so that two ranges can be checked for adjacency.
+/// The ranges `1..=3` and `4..=6` are adjacent, and merged by [`merge_ranges`].
+pub trait Discrete: Ord + Clone {
+    /// The next value, or `None` for the largest value.
+    fn successor(&self) -> Option<Self>;
+}
+
+macro_rules! impl_discrete {
+    ($($ty:ty),*) => {
+        $(
+            impl Discrete for $ty {
+                fn successor(&self) -> Option<Self> {
+                    self.checked_add(1)
+                }
+            }
+        )*
+    };
+}
+
+impl_discrete!(u8, u16, u32, u64, u128, usize, i8, i16, i32, i64, i128, isize);
+
+impl Discrete for Ipv4Addr {
+    fn successor(&self) -> Option<Self> {
+        u32::from(*self).checked_add(1).map(Self::from)
+    }
+}
+
+impl Discrete for Ipv6Addr {
+    fn successor(&self) -> Option<Self> {
+        u128::from(*self).checked_add(1).map(Self::from)
+    }
+}
+
+/// IPv4 addresses are ordered before IPv6 addresses. The last IPv4 address has
+/// no successor, so IPv4 and IPv6 ranges are never merged.
+impl Discrete for IpAddr {
+    fn successor(&self) -> Option<Self> {
+        match self {
+            IpAddr::V4(addr) => addr.successor().map(IpAddr::V4),
+            IpAddr::V6(addr) => addr.successor().map(IpAddr::V6),
+        }
+    }
+}
+
+/// A class of ranges that overlap or are adjacent, possibly through other ranges in the class.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MergedRange<K> {
+    /// The smallest range covering every range in the class. Since ranges in a class
+    /// are connected, this range contains no values that are not in one of the ranges.
+    pub range: RangeInclusive<K>,
+    /// The indices of the ranges in the class, in increasing order.
+    pub members: Vec<usize>,
+}
+
+/// Whether a range starting at `start` overlaps or is adjacent to a range ending at `end`.
+fn touches<K: Discrete>(end: &K, start: &K) -> bool {
+    match end.successor() {
+        Some(next) => *start <= next,
+        // the largest value, which is not adjacent to anything after it, like the first
+        // IPv6 address after the last IPv4 address
+        None => start <= end,
+    }
+}
+
+/// Unions every pair of ranges that overlap or are adjacent, transitively. Empty ranges
+/// are in a class on their own, and their covering range is the empty range itself.
+///
+/// Returns the classes ordered by the start of their covering range. Unlike merging
+/// ranges by sorting alone, every class still records which of the ranges it consists of.
+pub fn merge_ranges<K: Discrete>(ranges: &[RangeInclusive<K>]) -> Vec<MergedRange<K>> {
+    let mut order: Vec<usize> = (0..ranges.len()).collect();
+    order.sort_by(|&a, &b| ranges[a].start().cmp(ranges[b].start()));
+
+    let mut uf = HashUnionFindByRank::new(0..ranges.len()).expect("indices are unique");
+    // the index of a range in the class being built, and the end of the class so far
+    let mut current: Option<(usize, K)> = None;
+    for &index in &order {
+        let range = &ranges[index];
+        if range.is_empty() {
+            continue;
+        }
+        match &mut current {
+            Some((class, end)) if touches(end, range.start()) => {
+                uf.union_by_rank(class, &index)
+                    .expect("indices are in the union find");
+                if range.end() > end {
+                    *end = range.end().clone();
+                }
+            }
+            _ => current = Some((index, range.end().clone())),
+        }
+    }
+
+    let mut classes: HashMap<usize, MergedRange<K>> = HashMap::new();
+    for &index in &order {
+        let range = &ranges[index];
+        let root = uf.find_shorten(&index).expect("index is in the union find");
+        let class = classes.entry(root).or_insert_with(|| MergedRange {
+            range: range.clone(),
+            members: Vec::new(),
+        });
+        class.members.push(index);
+        if range.end() > class.range.end() {
+            class.range = class.range.start().clone()..=range.end().clone();
+        }
+    }
+
+    let mut classes: Vec<_> = classes.into_values().collect();
+    for class in &mut classes {
+        class.members.sort_unstable();
+    }
+    classes.sort_by(|a, b| {
+        a.range
+            .start()
+            .cmp(b.range.start())
+            .then(a.members[0].cmp(&b.members[0]))
+    });
+    classes
+}
+
+/// A block of IP addresses in CIDR notation, like `10.0.0.0/8` or `2001:db8::/32`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct IpNet {
+    addr: IpAddr,
+    prefix: u8,
+}
+
+#[derive(Debug, Error, PartialEq, Eq)]
+pub enum IpNetError {
+    #[error("invalid address `{0}`")]
+    InvalidAddress(String),
+
+    #[error("invalid prefix length `{0}`")]
+    InvalidPrefix(String),
+
+    #[error("the address has bits set after the prefix")]
+    HostBitsSet,
+}
+
+/// The number of bits in an address, and the address as a number.
+fn address_bits(addr: IpAddr) -> (u8, u128) {
+    match addr {
+        IpAddr::V4(addr) => (32, u32::from(addr) as u128),
+        IpAddr::V6(addr) => (128, u128::from(addr)),
+    }
+}
+
+fn address_from_bits(v4: bool, bits: u128) -> IpAddr {
+    if v4 {
+        IpAddr::V4(Ipv4Addr::from(bits as u32))
+    } else {
+        IpAddr::V6(Ipv6Addr::from(bits))
+    }
+}
+
+/// A mask of the lowest `n` bits.
+fn low_bits(n: u8) -> u128 {
+    if n >= 128 {
+        u128::MAX
+    } else {
+        (1 << n) - 1
+    }
+}
+
+impl IpNet {
+    /// Returns an error if `addr` has bits set after the first `prefix` bits,
+    /// or if `prefix` is longer than the address.
+    pub fn new(addr: IpAddr, prefix: u8) -> Result<Self, IpNetError> {
+        let (width, bits) = address_bits(addr);
+        if prefix > width {
+            return Err(IpNetError::InvalidPrefix(prefix.to_string()));
+        }
+        if bits & low_bits(width - prefix) != 0 {
+            return Err(IpNetError::HostBitsSet);
+        }
+        Ok(Self { addr, prefix })
+    }
+
+    pub fn addr(&self) -> IpAddr {
+        self.addr
+    }
+
+    pub fn prefix(&self) -> u8 {
+        self.prefix
+    }
+
+    /// The addresses in this block, from the first to the last.
+    pub fn range(&self) -> RangeInclusive<IpAddr> {
+        let (width, bits) = address_bits(self.addr);
+        let last = bits | low_bits(width - self.prefix);
+        self.addr..=address_from_bits(width == 32, last)
+    }
+
+    /// The smallest list of blocks that together contain exactly the addresses in `range`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the start and end of the range are not of the same IP version.
+    pub fn covering(range: &RangeInclusive<IpAddr>) -> Vec<Self> {
+        let (width, mut start) = address_bits(*range.start());
+        let (end_width, end) = address_bits(*range.end());
+        assert_eq!(width, end_width, "range mixes IPv4 and IPv6 addresses");
+
+        let mut blocks = Vec::new();
+        while start <= end {
+            // the largest block starting at `start` that is aligned and does not pass `end`
+            let mut size = (start.trailing_zeros() as u8).min(width);
+            while start | low_bits(size) > end {
+                size -= 1;
+            }
+            blocks.push(Self {
+                addr: address_from_bits(width == 32, start),
+                prefix: width - size,
+            });
+
+            match (start | low_bits(size)).checked_add(1) {
+                Some(next) => start = next,
+                None => break,
+            }
+        }
+        blocks
+    }
+}
+
+impl Display for IpNet {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}/{}", self.addr, self.prefix)
+    }
+}
+
+/// Parses a block in CIDR notation. An address without a prefix length is a block
+/// containing only that address.
+impl FromStr for IpNet {
+    type Err = IpNetError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (addr, prefix) = match s.split_once('/') {
+            Some((addr, prefix)) => (addr, Some(prefix)),
+            None => (s, None),
+        };
+        let addr: IpAddr = addr
+            .parse()
+            .map_err(|_| IpNetError::InvalidAddress(addr.to_string()))?;
+        let prefix = match prefix {
+            Some(prefix) => prefix
+                .parse()
+                .map_err(|_| IpNetError::InvalidPrefix(prefix.to_string()))?,
+            None => address_bits(addr).0,
+        };
+        Self::new(addr, prefix)
+    }
+}
+
+/// Merges blocks that overlap or are adjacent, transitively, like [`merge_ranges`].
+/// For every class, returns the indices of the blocks in it together with the smallest
+/// list of blocks covering the same addresses.
+pub fn merge_ip_nets(nets: &[IpNet]) -> Vec<(Vec<usize>, Vec<IpNet>)> {
+    let ranges: Vec<_> = nets.iter().map(IpNet::range).collect();
+    merge_ranges(&ranges)
+        .into_iter()
+        .map(|class| (class.members, IpNet::covering(&class.range)))
+        .collect()
+}
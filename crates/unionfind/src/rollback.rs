@@ -0,0 +1,166 @@
+use crate::generic::{UnionError, UnionStatus};
+use std::cmp::Ordering;
+use std::collections::HashMap;
+use std::convert::Infallible;
+use std::hash::Hash;
+
+/// A point in the history of a [`RollbackUnionFind`], to which it can be rolled back.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Snapshot {
+    trail_len: usize,
+}
+
+/// An operation that can be undone.
+#[derive(Debug, Clone)]
+enum Undo<T> {
+    Add(T),
+    Union {
+        child: T,
+        root: T,
+        rank_increased: bool,
+    },
+}
+
+/// A union find that can undo operations, as needed for backtracking search.
+///
+/// Every operation is recorded on a trail. [`rollback`](RollbackUnionFind::rollback) undoes
+/// all operations since a [`snapshot`](RollbackUnionFind::snapshot), in time proportional to
+/// the number of operations undone. To make undoing cheap, no path shortening is performed.
+/// Unions are made by rank, so finds still take logarithmic time.
+#[derive(Debug, Clone)]
+pub struct RollbackUnionFind<T: Hash + Eq> {
+    parent: HashMap<T, T>,
+    rank: HashMap<T, usize>,
+    sets: usize,
+    trail: Vec<Undo<T>>,
+}
+
+impl<T: Hash + Eq + Clone> Default for RollbackUnionFind<T> {
+    fn default() -> Self {
+        Self::new([])
+    }
+}
+
+impl<T: Hash + Eq + Clone> RollbackUnionFind<T> {
+    /// Creates a union find in which every element is in a class on its own. Adding these
+    /// elements is not recorded, so they are never removed by a rollback.
+    pub fn new(elems: impl IntoIterator<Item = T>) -> Self {
+        let parent: HashMap<T, T> = elems.into_iter().map(|elem| (elem.clone(), elem)).collect();
+        Self {
+            rank: parent.keys().map(|elem| (elem.clone(), 0)).collect(),
+            sets: parent.len(),
+            parent,
+            trail: Vec::new(),
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.parent.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.parent.is_empty()
+    }
+
+    /// The number of classes.
+    pub fn num_sets(&self) -> usize {
+        self.sets
+    }
+
+    /// Adds an element in a class on its own. Returns false if it was already present.
+    pub fn add(&mut self, elem: T) -> bool {
+        if self.parent.contains_key(&elem) {
+            return false;
+        }
+        self.parent.insert(elem.clone(), elem.clone());
+        self.rank.insert(elem.clone(), 0);
+        self.sets += 1;
+        self.trail.push(Undo::Add(elem));
+        true
+    }
+
+    /// Find the representative of an element.
+    pub fn find(&self, elem: &T) -> Option<T> {
+        let mut current = self.parent.get_key_value(elem)?.0;
+        loop {
+            let parent = &self.parent[current];
+            if parent == current {
+                return Some(current.clone());
+            }
+            current = parent;
+        }
+    }
+
+    /// Returns whether two elements are in the same class, or `None` if either of them
+    /// is not in the union find.
+    pub fn connected(&self, elem1: &T, elem2: &T) -> Option<bool> {
+        Some(self.find(elem1)? == self.find(elem2)?)
+    }
+
+    /// Union the classes of two elements by rank.
+    pub fn union(&mut self, elem1: &T, elem2: &T) -> Result<UnionStatus, UnionError<Infallible>> {
+        let root1 = self.find(elem1).ok_or(UnionError::Elem1NotFound)?;
+        let root2 = self.find(elem2).ok_or(UnionError::Elem2NotFound)?;
+        if root1 == root2 {
+            return Ok(UnionStatus::AlreadyEquivalent);
+        }
+
+        let rank1 = self.rank[&root1];
+        let rank2 = self.rank[&root2];
+        let (child, root) = match rank1.cmp(&rank2) {
+            Ordering::Less => (root1, root2),
+            Ordering::Equal | Ordering::Greater => (root2, root1),
+        };
+        let rank_increased = rank1 == rank2;
+        if rank_increased {
+            *self.rank.get_mut(&root).expect("every element has a rank") += 1;
+        }
+        self.parent.insert(child.clone(), root.clone());
+        self.sets -= 1;
+        self.trail.push(Undo::Union {
+            child,
+            root,
+            rank_increased,
+        });
+        Ok(UnionStatus::PerformedUnion)
+    }
+
+    /// Returns the current point in the history, to roll back to later.
+    pub fn snapshot(&self) -> Snapshot {
+        Snapshot {
+            trail_len: self.trail.len(),
+        }
+    }
+
+    /// Undoes all additions and unions since `snapshot` was taken, most recent first.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the union find was already rolled back to a point before `snapshot`.
+    pub fn rollback(&mut self, snapshot: Snapshot) {
+        assert!(
+            snapshot.trail_len <= self.trail.len(),
+            "the union find was already rolled back past this snapshot"
+        );
+        for undo in self.trail.drain(snapshot.trail_len..).rev() {
+            match undo {
+                Undo::Add(elem) => {
+                    self.parent.remove(&elem);
+                    self.rank.remove(&elem);
+                    self.sets -= 1;
+                }
+                Undo::Union {
+                    child,
+                    root,
+                    rank_increased,
+                } => {
+                    if rank_increased {
+                        *self.rank.get_mut(&root).expect("every element has a rank") -= 1;
+                    }
+                    self.parent.insert(child.clone(), child);
+                    self.sets += 1;
+                }
+            }
+        }
+    }
+}
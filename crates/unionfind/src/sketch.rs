@@ -0,0 +1,236 @@
+//! Linear sketches for graph connectivity, based on ℓ₀-sampling.
+//!
+//! A [`ConnectivitySketch`] summarizes a stream of edges in memory proportional to the number
+//! of vertices times a polylogarithmic factor, however many edges there are. Afterwards, the
+//! connected components can be computed from the sketch alone. The sketch is linear, so
+//! edges can also be removed, and sketches of parts of the stream can be merged.
+//!
+//! The components are correct with high probability. When sampling fails, components that
+//! are connected in the graph may be reported as separate components. Vertices that are
+//! not connected are only reported as connected when 64-bit fingerprints collide.
+//!
+//! The polylogarithmic factor is large: every vertex takes 2 KiB per round, so about 36 KiB
+//! with the 18 rounds suited to 100 000 vertices. A plain union find over the vertices takes
+//! a few dozen bytes per vertex, so it is the better choice when edges are only ever added.
+//! A sketch is useful when edges are also removed, or when the stream is split over many
+//! machines whose sketches are merged, and then only pays off over storing the edges
+//! themselves when there are thousands of edges per vertex.
+
+use crate::HashUnionFindByRank;
+use std::collections::HashMap;
+use thiserror::Error;
+
+/// The number of sampling levels in every round. Level `i` contains about `2^-i` of all edges.
+const LEVELS: usize = 64;
+
+/// Recovers an edge from a set of edges if the set contains exactly one edge.
+/// Edges are added with sign `1` to one endpoint and `-1` to the other, so edges
+/// inside a set of vertices cancel out when the cells of those vertices are summed.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+struct Cell {
+    count: i64,
+    id_sum: u128,
+    fingerprint: u64,
+}
+
+impl Cell {
+    fn update(&mut self, id: u128, fingerprint: u64, positive: bool) {
+        if positive {
+            self.count += 1;
+            self.id_sum = self.id_sum.wrapping_add(id);
+            self.fingerprint = self.fingerprint.wrapping_add(fingerprint);
+        } else {
+            self.count -= 1;
+            self.id_sum = self.id_sum.wrapping_sub(id);
+            self.fingerprint = self.fingerprint.wrapping_sub(fingerprint);
+        }
+    }
+
+    fn merge(&mut self, other: &Cell) {
+        self.count += other.count;
+        self.id_sum = self.id_sum.wrapping_add(other.id_sum);
+        self.fingerprint = self.fingerprint.wrapping_add(other.fingerprint);
+    }
+
+    /// Returns the edge in this cell if it contains exactly one edge.
+    fn recover(&self, seed: u64) -> Option<u128> {
+        let (id, fingerprint) = match self.count {
+            1 => (self.id_sum, self.fingerprint),
+            -1 => (self.id_sum.wrapping_neg(), self.fingerprint.wrapping_neg()),
+            _ => return None,
+        };
+        (fingerprint_of(id, seed) == fingerprint).then_some(id)
+    }
+}
+
+/// A strong 64-bit mixing function (splitmix64).
+fn mix(mut x: u64) -> u64 {
+    x = x.wrapping_add(0x9e37_79b9_7f4a_7c15);
+    x = (x ^ (x >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    x = (x ^ (x >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+    x ^ (x >> 31)
+}
+
+fn hash_id(id: u128, seed: u64) -> u64 {
+    mix(mix(seed ^ id as u64) ^ (id >> 64) as u64)
+}
+
+fn fingerprint_of(id: u128, seed: u64) -> u64 {
+    hash_id(id, mix(seed ^ 0x5ce7c4))
+}
+
+fn edge_id(u: u64, v: u64) -> u128 {
+    ((u as u128) << 64) | v as u128
+}
+
+fn endpoints(id: u128) -> (u64, u64) {
+    ((id >> 64) as u64, id as u64)
+}
+
+/// Returned when merging sketches that were not created with the same parameters.
+#[derive(Debug, Error, PartialEq, Eq)]
+#[error("sketches with different rounds or seeds can't be merged")]
+pub struct SketchMismatch;
+
+/// A mergeable sketch of a graph from which its connected components can be computed.
+/// See the [module documentation](self).
+#[derive(Debug, Clone)]
+pub struct ConnectivitySketch {
+    rounds: usize,
+    seed: u64,
+    /// For every vertex, `LEVELS` cells for every round.
+    vertices: HashMap<u64, Vec<Cell>>,
+}
+
+impl ConnectivitySketch {
+    /// Creates an empty sketch. Every round can at least halve the number of components that
+    /// are still to be merged, so with `n` vertices, `rounds` should be a bit more than
+    /// `log2(n)`. Every vertex takes `2048 * rounds` bytes, see the
+    /// [module documentation](self).
+    ///
+    /// Sketches can only be merged if they were created with the same `rounds` and `seed`.
+    pub fn new(rounds: usize, seed: u64) -> Self {
+        Self {
+            rounds: rounds.max(1),
+            seed,
+            vertices: HashMap::new(),
+        }
+    }
+
+    /// The number of vertices in the sketch.
+    pub fn vertex_count(&self) -> usize {
+        self.vertices.len()
+    }
+
+    /// Adds a vertex without edges. Vertices are also added by adding edges.
+    pub fn add_vertex(&mut self, vertex: u64) {
+        let cells = self.rounds * LEVELS;
+        self.vertices
+            .entry(vertex)
+            .or_insert_with(|| vec![Cell::default(); cells]);
+    }
+
+    fn round_seed(&self, round: usize) -> u64 {
+        mix(self.seed ^ mix(round as u64))
+    }
+
+    fn update(&mut self, u: u64, v: u64, insert: bool) {
+        if u == v {
+            return;
+        }
+        let (u, v) = (u.min(v), u.max(v));
+        let id = edge_id(u, v);
+        self.add_vertex(u);
+        self.add_vertex(v);
+
+        for round in 0..self.rounds {
+            let seed = self.round_seed(round);
+            let level = (hash_id(id, seed).trailing_zeros() as usize).min(LEVELS - 1);
+            let fingerprint = fingerprint_of(id, seed);
+            let cells = round * LEVELS..round * LEVELS + level + 1;
+            for (vertex, positive) in [(u, insert), (v, !insert)] {
+                let sketch = self.vertices.get_mut(&vertex).expect("vertex was added");
+                for cell in &mut sketch[cells.clone()] {
+                    cell.update(id, fingerprint, positive);
+                }
+            }
+        }
+    }
+
+    /// Adds an edge. Adding the same edge twice is not supported.
+    pub fn add_edge(&mut self, u: u64, v: u64) {
+        self.update(u, v, true);
+    }
+
+    /// Removes an edge that was added before.
+    pub fn remove_edge(&mut self, u: u64, v: u64) {
+        self.update(u, v, false);
+    }
+
+    /// Adds all edges summarized by `other` to this sketch.
+    pub fn merge(&mut self, other: &ConnectivitySketch) -> Result<(), SketchMismatch> {
+        if self.rounds != other.rounds || self.seed != other.seed {
+            return Err(SketchMismatch);
+        }
+        for (&vertex, cells) in &other.vertices {
+            self.add_vertex(vertex);
+            let own = self.vertices.get_mut(&vertex).expect("vertex was added");
+            for (own, other) in own.iter_mut().zip(cells) {
+                own.merge(other);
+            }
+        }
+        Ok(())
+    }
+
+    /// Computes the connected components, using one round of the sketch for every step of
+    /// Borůvka's algorithm. Returns a union find over all vertices.
+    pub fn components(&self) -> HashUnionFindByRank<u64> {
+        let mut uf =
+            HashUnionFindByRank::new(self.vertices.keys().copied()).expect("vertices are unique");
+
+        for round in 0..self.rounds {
+            let seed = self.round_seed(round);
+            let cells = round * LEVELS..(round + 1) * LEVELS;
+
+            // sum the cells of every component, which cancels edges within components
+            let mut summed: HashMap<u64, Vec<Cell>> = HashMap::new();
+            for (&vertex, sketch) in &self.vertices {
+                let root = uf
+                    .find_shorten(&vertex)
+                    .expect("vertex is in the union find");
+                let sum = summed
+                    .entry(root)
+                    .or_insert_with(|| vec![Cell::default(); LEVELS]);
+                for (sum, cell) in sum.iter_mut().zip(&sketch[cells.clone()]) {
+                    sum.merge(cell);
+                }
+            }
+            if summed.len() <= 1 {
+                break;
+            }
+
+            // level 0 contains every edge, so it is empty for components without outgoing edges
+            if summed.values().all(|sum| sum[0] == Cell::default()) {
+                break;
+            }
+
+            for sum in summed.values() {
+                if let Some(id) = sum.iter().find_map(|cell| cell.recover(seed)) {
+                    let (u, v) = endpoints(id);
+                    // the endpoints can only be missing when a fingerprint collided
+                    let _ = uf.union_by_rank(&u, &v);
+                }
+            }
+        }
+        uf
+    }
+
+    /// The number of connected components, see [`components`](ConnectivitySketch::components).
+    pub fn component_count(&self) -> usize {
+        let uf = self.components();
+        uf.raw()
+            .iter()
+            .filter(|(elem, parent)| elem == parent)
+            .count()
+    }
+}
@@ -0,0 +1,167 @@
+use crate::generic::{UnionError, UnionStatus};
+use std::convert::Infallible;
+use std::ops::{Deref, DerefMut, Range};
+
+/// A union find over the positions of a slice, for partitioning the elements of a slice
+/// without cloning them or requiring them to implement [`Hash`](std::hash::Hash).
+///
+/// Positions are stored densely, so finds and unions need no hashing. The slice can be
+/// borrowed (`&[T]` or `&mut [T]`) or owned (like a [`Vec<T>`]). With mutable access to
+/// the slice, [`reorder`](SliceUnionFind::reorder) moves the elements of every class next
+/// to each other.
+#[derive(Debug, Clone)]
+pub struct SliceUnionFind<S> {
+    items: S,
+    parent: Vec<usize>,
+    /// The number of positions in the class, only meaningful for representatives.
+    size: Vec<usize>,
+}
+
+impl<T, S: Deref<Target = [T]>> SliceUnionFind<S> {
+    /// Creates a union find in which every position of `items` is in a class on its own.
+    pub fn new(items: S) -> Self {
+        let len = items.len();
+        Self {
+            items,
+            parent: (0..len).collect(),
+            size: vec![1; len],
+        }
+    }
+
+    pub fn items(&self) -> &[T] {
+        &self.items
+    }
+
+    pub fn into_items(self) -> S {
+        self.items
+    }
+
+    pub fn len(&self) -> usize {
+        self.parent.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.parent.is_empty()
+    }
+
+    /// Find the representative position of a position. Performs no path shortening,
+    /// but can be used through an immutable reference.
+    pub fn find(&self, mut position: usize) -> Option<usize> {
+        if position >= self.len() {
+            return None;
+        }
+        while self.parent[position] != position {
+            position = self.parent[position];
+        }
+        Some(position)
+    }
+
+    /// Find the representative position of a position, shortening the path to it.
+    pub fn find_shorten(&mut self, position: usize) -> Option<usize> {
+        let root = self.find(position)?;
+        let mut current = position;
+        while current != root {
+            let next = self.parent[current];
+            self.parent[current] = root;
+            current = next;
+        }
+        Some(root)
+    }
+
+    /// Union the classes of two positions. The representative of the larger class becomes
+    /// the representative of the unioned class.
+    pub fn union(
+        &mut self,
+        position1: usize,
+        position2: usize,
+    ) -> Result<UnionStatus, UnionError<Infallible>> {
+        let root1 = self
+            .find_shorten(position1)
+            .ok_or(UnionError::Elem1NotFound)?;
+        let root2 = self
+            .find_shorten(position2)
+            .ok_or(UnionError::Elem2NotFound)?;
+
+        if root1 == root2 {
+            return Ok(UnionStatus::AlreadyEquivalent);
+        }
+        let (root, child) = if self.size[root1] >= self.size[root2] {
+            (root1, root2)
+        } else {
+            (root2, root1)
+        };
+        self.parent[child] = root;
+        self.size[root] += self.size[child];
+        Ok(UnionStatus::PerformedUnion)
+    }
+
+    /// The positions in every class, in increasing order. Classes are ordered by their
+    /// first position.
+    fn group_positions(&self) -> Vec<Vec<usize>> {
+        let mut group_of_root = vec![usize::MAX; self.len()];
+        let mut groups: Vec<Vec<usize>> = Vec::new();
+        for position in 0..self.len() {
+            let root = self.find(position).expect("position is in range");
+            if group_of_root[root] == usize::MAX {
+                group_of_root[root] = groups.len();
+                groups.push(Vec::with_capacity(self.size[root]));
+            }
+            groups[group_of_root[root]].push(position);
+        }
+        groups
+    }
+
+    /// The elements of every class, in the order they occur in the slice.
+    /// Classes are ordered by the position of their first element.
+    pub fn groups<'a>(&'a self) -> impl Iterator<Item = impl Iterator<Item = &'a T> + 'a> + 'a
+    where
+        T: 'a,
+    {
+        self.group_positions()
+            .into_iter()
+            .map(move |group| group.into_iter().map(move |position| &self.items[position]))
+    }
+}
+
+impl<T, S: DerefMut<Target = [T]>> SliceUnionFind<S> {
+    /// Reorders the slice in place so the elements of every class are next to each other.
+    /// Classes are ordered by the position of their first element, and elements within a
+    /// class keep their relative order.
+    ///
+    /// Returns the range of positions of every class. Afterwards, the union find refers to
+    /// the new positions, and the first position of every class is its representative.
+    pub fn reorder(&mut self) -> Vec<Range<usize>> {
+        let groups = self.group_positions();
+        // the position every element is moved from, indexed by the position it is moved to
+        let mut order = Vec::with_capacity(self.len());
+        let mut ranges = Vec::with_capacity(groups.len());
+        for group in groups {
+            let start = order.len();
+            order.extend(group);
+            ranges.push(start..order.len());
+        }
+
+        // apply the permutation by following its cycles
+        let mut placed = vec![false; self.len()];
+        for start in 0..self.len() {
+            let mut current = start;
+            while !placed[current] {
+                placed[current] = true;
+                let source = order[current];
+                if source == start {
+                    break;
+                }
+                self.items.swap(current, source);
+                current = source;
+            }
+        }
+
+        for range in &ranges {
+            for position in range.clone() {
+                self.parent[position] = range.start;
+            }
+            self.size[range.start] = range.len();
+        }
+        ranges
+    }
+}
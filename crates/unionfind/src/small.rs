@@ -0,0 +1,110 @@
+use crate::generic::{UnionError, UnionStatus};
+use std::convert::Infallible;
+
+/// A union find for elements that only implement [`Eq`], not [`Hash`](std::hash::Hash)
+/// or [`Ord`]. Elements are stored in a [`Vec`] together with the index of their parent,
+/// and looked up with a linear scan.
+///
+/// Lookups take time linear in the number of elements, so this is only suitable for
+/// small numbers of elements. Once an element is found, finds and unions are as fast as
+/// in other union finds.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SmallUnionFind<T: Eq> {
+    /// Every element with the index of its parent.
+    entries: Vec<(T, usize)>,
+}
+
+impl<T: Eq> Default for SmallUnionFind<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T: Eq> SmallUnionFind<T> {
+    pub fn new() -> Self {
+        Self {
+            entries: Vec::new(),
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    fn index_of(&self, elem: &T) -> Option<usize> {
+        self.entries.iter().position(|(i, _)| i == elem)
+    }
+
+    pub fn contains(&self, elem: &T) -> bool {
+        self.index_of(elem).is_some()
+    }
+
+    /// Adds an element as a class on its own.
+    /// Returns false if the element was already present.
+    pub fn add(&mut self, elem: T) -> bool {
+        if self.contains(&elem) {
+            return false;
+        }
+        self.entries.push((elem, self.entries.len()));
+        true
+    }
+
+    fn root_index(&self, mut index: usize) -> usize {
+        while self.entries[index].1 != index {
+            index = self.entries[index].1;
+        }
+        index
+    }
+
+    fn root_index_shorten(&mut self, index: usize) -> usize {
+        let root = self.root_index(index);
+        let mut current = index;
+        while current != root {
+            let next = self.entries[current].1;
+            self.entries[current].1 = root;
+            current = next;
+        }
+        root
+    }
+
+    /// Find the representative of an element. Performs no path shortening,
+    /// but can be used through an immutable reference.
+    pub fn find(&self, elem: &T) -> Option<&T> {
+        let root = self.root_index(self.index_of(elem)?);
+        Some(&self.entries[root].0)
+    }
+
+    /// Find the representative of an element, shortening the path to it.
+    pub fn find_shorten(&mut self, elem: &T) -> Option<&T> {
+        let index = self.index_of(elem)?;
+        let root = self.root_index_shorten(index);
+        Some(&self.entries[root].0)
+    }
+
+    /// Union the classes of two elements. The representative of the class of `elem1`
+    /// becomes the representative of the unioned class.
+    pub fn union(&mut self, elem1: &T, elem2: &T) -> Result<UnionStatus, UnionError<Infallible>> {
+        let index1 = self.index_of(elem1).ok_or(UnionError::Elem1NotFound)?;
+        let index2 = self.index_of(elem2).ok_or(UnionError::Elem2NotFound)?;
+        let root1 = self.root_index_shorten(index1);
+        let root2 = self.root_index_shorten(index2);
+
+        if root1 == root2 {
+            return Ok(UnionStatus::AlreadyEquivalent);
+        }
+        self.entries[root2].1 = root1;
+        Ok(UnionStatus::PerformedUnion)
+    }
+
+    /// Every element together with its representative.
+    pub fn iter(&self) -> impl Iterator<Item = (&T, &T)> + '_ {
+        self.entries
+            .iter()
+            .enumerate()
+            .map(|(index, (elem, _))| (elem, &self.entries[self.root_index(index)].0))
+    }
+}
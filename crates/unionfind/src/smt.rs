@@ -0,0 +1,337 @@
+use crate::forest::SpanningForest;
+use std::cmp::Ordering;
+use std::collections::{HashMap, HashSet};
+use std::hash::Hash;
+use std::vec::Drain;
+use thiserror::Error;
+
+/// Returned when an asserted equality or disequality contradicts earlier assertions.
+#[derive(Debug, Error, PartialEq, Eq)]
+#[error("asserted equalities and disequalities are in conflict")]
+pub struct Conflict<L> {
+    /// The literals of all assertions involved in the conflict. Together they are
+    /// contradictory, which makes their negation suitable as a learned clause.
+    pub literals: Vec<L>,
+}
+
+/// Notification that the class of an interesting term was merged with another class.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Merge<T> {
+    /// The representative of the class that was merged into `into`.
+    pub from: T,
+    /// The representative of the merged class.
+    pub into: T,
+}
+
+/// Everything that needs to be undone when backtracking.
+#[derive(Debug, Clone)]
+enum Undo<T> {
+    Union {
+        from: T,
+        into: T,
+        rank_increased: bool,
+        /// The number of disequalities of `into` before the union.
+        distinct_len: usize,
+    },
+    Distinct {
+        root1: T,
+        root2: T,
+    },
+    Interesting {
+        term: T,
+        root: T,
+    },
+}
+
+/// A union find tailored to be the core of a theory solver in an SMT solver, for example
+/// for the theory of equality with uninterpreted functions.
+///
+/// * Every asserted equality and disequality is tagged with a literal of type `L`.
+/// * When an assertion conflicts with earlier assertions, the literals of all assertions
+///   involved are returned, which can be used for clause learning.
+/// * Terms can be marked as interesting, after which merges of their class are reported.
+/// * All assertions can be undone by backtracking to an earlier decision level.
+///
+/// To make backtracking cheap, no path shortening is performed. Unions are made by rank,
+/// so finds still take logarithmic time.
+#[derive(Debug, Clone)]
+pub struct TheoryUnionFind<T: Hash + Eq, L> {
+    parent: HashMap<T, T>,
+    rank: HashMap<T, usize>,
+    /// The equalities that merged two classes, labeled with their literal.
+    forest: SpanningForest<T, L>,
+
+    /// Asserted disequalities with their literal.
+    disequalities: Vec<(T, T, L)>,
+    /// For every representative, the indices of the disequalities involving its class.
+    distinct: HashMap<T, Vec<usize>>,
+
+    interesting: HashSet<T>,
+    /// For every representative, the number of interesting terms in its class.
+    interesting_count: HashMap<T, usize>,
+    merges: Vec<Merge<T>>,
+
+    trail: Vec<Undo<T>>,
+    /// The length of the trail at the start of every decision level.
+    levels: Vec<usize>,
+}
+
+impl<T: Hash + Eq + Clone, L> Default for TheoryUnionFind<T, L> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T: Hash + Eq + Clone, L> TheoryUnionFind<T, L> {
+    pub fn new() -> Self {
+        Self {
+            parent: HashMap::new(),
+            rank: HashMap::new(),
+            forest: SpanningForest::new(),
+            disequalities: Vec::new(),
+            distinct: HashMap::new(),
+            interesting: HashSet::new(),
+            interesting_count: HashMap::new(),
+            merges: Vec::new(),
+            trail: Vec::new(),
+            levels: Vec::new(),
+        }
+    }
+
+    /// Adds a term as a class on its own. Adding terms is not undone by backtracking.
+    /// Returns false if the term was already present.
+    pub fn add_term(&mut self, term: T) -> bool {
+        if self.parent.contains_key(&term) {
+            return false;
+        }
+
+        self.parent.insert(term.clone(), term.clone());
+        self.rank.insert(term, 0);
+        true
+    }
+
+    /// Find the representative of a term.
+    pub fn find(&self, term: &T) -> Option<T> {
+        let mut current = term;
+        loop {
+            let parent = self.parent.get(current)?;
+            if parent == current {
+                return Some(parent.clone());
+            }
+            current = parent;
+        }
+    }
+
+    /// Returns whether two terms are known to be equal.
+    pub fn are_equal(&self, term1: &T, term2: &T) -> bool {
+        match (self.find(term1), self.find(term2)) {
+            (Some(root1), Some(root2)) => root1 == root2,
+            _ => false,
+        }
+    }
+
+    /// Marks a term as interesting. Whenever the class of an interesting term is merged with
+    /// another class, a [`Merge`] is reported through [`drain_merges`](TheoryUnionFind::drain_merges).
+    pub fn mark_interesting(&mut self, term: T) {
+        self.add_term(term.clone());
+        if !self.interesting.insert(term.clone()) {
+            return;
+        }
+
+        let root = self.find(&term).expect("term was added");
+        *self.interesting_count.entry(root.clone()).or_default() += 1;
+        self.trail.push(Undo::Interesting { term, root });
+    }
+
+    /// Returns all merges of classes with interesting terms since the last call.
+    pub fn drain_merges(&mut self) -> Drain<'_, Merge<T>> {
+        self.merges.drain(..)
+    }
+
+    /// The current decision level. Starts at zero.
+    pub fn level(&self) -> usize {
+        self.levels.len()
+    }
+
+    /// Starts a new decision level.
+    pub fn push_level(&mut self) {
+        self.levels.push(self.trail.len());
+    }
+
+    /// Undoes all assertions made after decision level `level` was started,
+    /// and makes `level` the current level.
+    pub fn backtrack_to(&mut self, level: usize) {
+        while self.levels.len() > level {
+            let mark = self.levels.pop().expect("levels is not empty");
+            while self.trail.len() > mark {
+                let undo = self.trail.pop().expect("trail is longer than the mark");
+                self.undo(undo);
+            }
+        }
+    }
+
+    fn undo(&mut self, undo: Undo<T>) {
+        match undo {
+            Undo::Union {
+                from,
+                into,
+                rank_increased,
+                distinct_len,
+            } => {
+                self.parent.insert(from.clone(), from.clone());
+                if rank_increased {
+                    *self.rank.get_mut(&into).expect("root has a rank") -= 1;
+                }
+                if let Some(distinct) = self.distinct.get_mut(&into) {
+                    distinct.truncate(distinct_len);
+                }
+                if let Some(&count) = self.interesting_count.get(&from) {
+                    *self
+                        .interesting_count
+                        .get_mut(&into)
+                        .expect("counts were added on union") -= count;
+                }
+                self.forest.pop_edge();
+            }
+            Undo::Distinct { root1, root2 } => {
+                self.disequalities.pop();
+                for root in [root1, root2] {
+                    if let Some(distinct) = self.distinct.get_mut(&root) {
+                        distinct.pop();
+                    }
+                }
+            }
+            Undo::Interesting { term, root } => {
+                self.interesting.remove(&term);
+                *self
+                    .interesting_count
+                    .get_mut(&root)
+                    .expect("count was added when marking") -= 1;
+            }
+        }
+    }
+}
+
+impl<T: Hash + Eq + Clone, L: Clone> TheoryUnionFind<T, L> {
+    /// Returns the literals of the equalities which together imply that two terms are equal,
+    /// or `None` if they are not equal.
+    pub fn explain(&self, term1: &T, term2: &T) -> Option<Vec<L>> {
+        let path = self.forest.path(term1, term2)?;
+        Some(path.into_iter().cloned().collect())
+    }
+
+    /// Asserts that two terms are equal. Terms that weren't added before are added.
+    ///
+    /// If the equality conflicts with an asserted disequality, nothing is changed
+    /// and the conflict is returned.
+    pub fn assert_equal(&mut self, term1: T, term2: T, literal: L) -> Result<(), Conflict<L>> {
+        self.add_term(term1.clone());
+        self.add_term(term2.clone());
+        let root1 = self.find(&term1).expect("term was added");
+        let root2 = self.find(&term2).expect("term was added");
+        if root1 == root2 {
+            return Ok(());
+        }
+
+        self.check_distinct(&term1, &root1, &term2, &root2, &literal)?;
+
+        let rank1 = self.rank[&root1];
+        let rank2 = self.rank[&root2];
+        let (from, into) = match rank1.cmp(&rank2) {
+            Ordering::Less | Ordering::Equal => (root1, root2),
+            Ordering::Greater => (root2, root1),
+        };
+        let rank_increased = rank1 == rank2;
+        if rank_increased {
+            *self.rank.get_mut(&into).expect("root has a rank") += 1;
+        }
+
+        self.parent.insert(from.clone(), into.clone());
+
+        let moved = self.distinct.get(&from).cloned().unwrap_or_default();
+        let distinct = self.distinct.entry(into.clone()).or_default();
+        let distinct_len = distinct.len();
+        distinct.extend(moved);
+
+        let interesting_from = self.interesting_count.get(&from).copied().unwrap_or(0);
+        let interesting_into = self.interesting_count.entry(into.clone()).or_default();
+        if interesting_from > 0 || *interesting_into > 0 {
+            self.merges.push(Merge {
+                from: from.clone(),
+                into: into.clone(),
+            });
+        }
+        *interesting_into += interesting_from;
+
+        self.forest.add_edge(term1, term2, literal);
+        self.trail.push(Undo::Union {
+            from,
+            into,
+            rank_increased,
+            distinct_len,
+        });
+
+        Ok(())
+    }
+
+    /// Checks whether merging the classes of `term1` and `term2` violates a disequality.
+    fn check_distinct(
+        &self,
+        term1: &T,
+        root1: &T,
+        term2: &T,
+        root2: &T,
+        literal: &L,
+    ) -> Result<(), Conflict<L>> {
+        let Some(distinct) = self.distinct.get(root1) else {
+            return Ok(());
+        };
+
+        for &idx in distinct {
+            let (a, b, distinct_literal) = &self.disequalities[idx];
+            let root_a = self.find(a).expect("term was added");
+            let root_b = self.find(b).expect("term was added");
+
+            // orient the disequality so that `a` is in the class of `term1`
+            let (a, b) = if &root_a == root1 && &root_b == root2 {
+                (a, b)
+            } else if &root_a == root2 && &root_b == root1 {
+                (b, a)
+            } else {
+                continue;
+            };
+
+            let mut literals = self.explain(a, term1).expect("a is equal to term1");
+            literals.push(literal.clone());
+            literals.extend(self.explain(term2, b).expect("term2 is equal to b"));
+            literals.push(distinct_literal.clone());
+            return Err(Conflict { literals });
+        }
+
+        Ok(())
+    }
+
+    /// Asserts that two terms are not equal. Terms that weren't added before are added.
+    ///
+    /// If the terms are already equal, nothing is changed and the conflict is returned.
+    pub fn assert_distinct(&mut self, term1: T, term2: T, literal: L) -> Result<(), Conflict<L>> {
+        self.add_term(term1.clone());
+        self.add_term(term2.clone());
+        let root1 = self.find(&term1).expect("term was added");
+        let root2 = self.find(&term2).expect("term was added");
+
+        if root1 == root2 {
+            let mut literals = self.explain(&term1, &term2).expect("terms are equal");
+            literals.push(literal);
+            return Err(Conflict { literals });
+        }
+
+        let idx = self.disequalities.len();
+        self.disequalities.push((term1, term2, literal));
+        self.distinct.entry(root1.clone()).or_default().push(idx);
+        self.distinct.entry(root2.clone()).or_default().push(idx);
+        self.trail.push(Undo::Distinct { root1, root2 });
+
+        Ok(())
+    }
+}
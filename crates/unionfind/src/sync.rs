@@ -0,0 +1,210 @@
+use crate::extra::{ByRank, BySize, GrowableExtra, MergeExtra};
+use crate::generic::{
+    AddErrorSimple, InvalidStructure, PairsUnion, UnionByRankError, UnionError, UnionFind,
+    UnionStatus,
+};
+use crate::union::Union;
+use crate::watch::RepresentativeChange;
+use std::collections::HashMap;
+use std::hash::Hash;
+use std::sync::PoisonError;
+
+// With `--cfg loom`, the synchronization primitives are replaced by those of loom, so the
+// interleavings of concurrent operations can be checked exhaustively.
+#[cfg(loom)]
+use loom::sync::{Arc, RwLock, RwLockReadGuard, RwLockWriteGuard};
+#[cfg(not(loom))]
+use std::sync::{Arc, RwLock, RwLockReadGuard, RwLockWriteGuard};
+
+/// A handle to a [`UnionFind`] that can be shared between threads.
+///
+/// Cloning the handle is cheap and gives another handle to the same union find.
+/// Operations that only read use [`find`](UnionFind::find) under a read lock, so they can
+/// happen in parallel. Operations that modify the union find, including
+/// [`find_shorten`](SyncUnionFind::find_shorten), take a write lock.
+///
+/// For operations that are not passed through, or to perform multiple operations
+/// without other threads interfering, use [`read`](SyncUnionFind::read) or
+/// [`write`](SyncUnionFind::write).
+///
+/// Poisoning of the lock is ignored. When another thread panics while it holds the write lock,
+/// for example in a [`Union`] or in the [`Hash`] implementation of the elements, the union
+/// find is left as it was at that point, which may be halfway through an operation. Use
+/// [`validate`](SyncUnionFind::validate) to check the parent mapping afterwards if that
+/// matters.
+#[derive(Debug)]
+pub struct SyncUnionFind<T: Hash + Eq, V, E = ()> {
+    inner: Arc<RwLock<UnionFind<T, V, E>>>,
+}
+
+impl<T: Hash + Eq, V, E> Clone for SyncUnionFind<T, V, E> {
+    fn clone(&self) -> Self {
+        Self {
+            inner: Arc::clone(&self.inner),
+        }
+    }
+}
+
+impl<T: Hash + Eq, V, E> From<UnionFind<T, V, E>> for SyncUnionFind<T, V, E> {
+    fn from(uf: UnionFind<T, V, E>) -> Self {
+        Self::new(uf)
+    }
+}
+
+impl<T: Hash + Eq, V, E> SyncUnionFind<T, V, E> {
+    pub fn new(uf: UnionFind<T, V, E>) -> Self {
+        Self {
+            inner: Arc::new(RwLock::new(uf)),
+        }
+    }
+
+    /// Locks the union find for reading, ignoring poisoning.
+    pub fn read(&self) -> RwLockReadGuard<'_, UnionFind<T, V, E>> {
+        self.inner.read().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    /// Locks the union find for writing, ignoring poisoning.
+    pub fn write(&self) -> RwLockWriteGuard<'_, UnionFind<T, V, E>> {
+        self.inner.write().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    /// Returns the union find if this is the only handle to it.
+    pub fn into_inner(self) -> Result<UnionFind<T, V, E>, Self> {
+        match Arc::try_unwrap(self.inner) {
+            Ok(lock) => Ok(lock.into_inner().unwrap_or_else(PoisonError::into_inner)),
+            Err(inner) => Err(Self { inner }),
+        }
+    }
+
+    /// See [`UnionFind::len`].
+    pub fn len(&self) -> usize {
+        self.read().len()
+    }
+
+    /// See [`UnionFind::is_empty`].
+    pub fn is_empty(&self) -> bool {
+        self.read().is_empty()
+    }
+
+    /// Returns a copy of the parent mapping, see [`UnionFind::raw`].
+    pub fn raw(&self) -> HashMap<T, T>
+    where
+        T: Clone,
+    {
+        self.read().raw().clone()
+    }
+
+    /// See [`UnionFind::validate`].
+    pub fn validate(&self) -> Result<(), InvalidStructure<T>>
+    where
+        T: Clone,
+    {
+        self.read().validate()
+    }
+}
+
+impl<T: Hash + Eq + Clone, V, E> SyncUnionFind<T, V, E> {
+    /// See [`UnionFind::find`]. Takes a read lock.
+    pub fn find(&self, elem: &T) -> Option<T> {
+        self.read().find(elem)
+    }
+
+    /// See [`UnionFind::find_shorten`]. Takes a write lock.
+    pub fn find_shorten(&self, elem: &T) -> Option<T> {
+        self.write().find_shorten(elem)
+    }
+
+    /// See [`UnionFind::union_by`].
+    pub fn union_by<U: Union<T>>(
+        &self,
+        elem1: &T,
+        elem2: &T,
+        union: U,
+    ) -> Result<UnionStatus, UnionError<U::Err, E::MergeError>>
+    where
+        E: MergeExtra<T>,
+    {
+        self.write().union_by(elem1, elem2, union)
+    }
+
+    /// See [`UnionFind::watch`].
+    pub fn watch(&self, elem: &T) -> bool {
+        self.write().watch(elem)
+    }
+
+    /// See [`UnionFind::unwatch`].
+    pub fn unwatch(&self, elem: &T) -> bool {
+        self.write().unwatch(elem)
+    }
+
+    /// See [`UnionFind::drain_representative_changes`].
+    pub fn drain_representative_changes(&self) -> Vec<RepresentativeChange<T>> {
+        self.write().drain_representative_changes().collect()
+    }
+}
+
+impl<T: Hash + Eq + Clone, V, E> SyncUnionFind<T, V, E>
+where
+    E: GrowableExtra<T, V>,
+{
+    /// See [`UnionFind::add_with_extra`].
+    pub fn add_with_extra(
+        &self,
+        elem: T,
+        extra: V,
+    ) -> Result<(), AddErrorSimple<T, V, HashMap<T, T>, E>> {
+        self.write().add_with_extra(elem, extra)
+    }
+
+    /// See [`UnionFind::get_or_insert_with`].
+    pub fn get_or_insert_with(
+        &self,
+        elem: T,
+        make_extra: impl FnOnce() -> V,
+    ) -> Result<T, AddErrorSimple<T, V, HashMap<T, T>, E>> {
+        self.write().get_or_insert_with(elem, make_extra)
+    }
+}
+
+impl<T: Hash + Eq + Clone, V: Default, E> SyncUnionFind<T, V, E>
+where
+    E: GrowableExtra<T, V>,
+{
+    /// See [`UnionFind::add`].
+    pub fn add(&self, elem: T) -> Result<(), AddErrorSimple<T, V, HashMap<T, T>, E>> {
+        self.write().add(elem)
+    }
+
+    /// See [`UnionFind::find_or_add`].
+    pub fn find_or_add(&self, elem: &T) -> Result<T, AddErrorSimple<T, V, HashMap<T, T>, E>> {
+        // only take a write lock when the element is not present yet
+        if let Some(root) = self.find(elem) {
+            return Ok(root);
+        }
+        self.write().find_or_add(elem)
+    }
+}
+
+impl<T: Hash + Eq + Clone, V> SyncUnionFind<T, V, ByRank<T>> {
+    /// See [`UnionFind::union_by_rank`].
+    pub fn union_by_rank(&self, elem1: &T, elem2: &T) -> Result<UnionStatus, UnionByRankError> {
+        self.write().union_by_rank(elem1, elem2)
+    }
+}
+
+impl<T: Hash + Eq + Clone, V> SyncUnionFind<T, V, BySize<T>> {
+    /// See [`UnionFind::connected_pairs`].
+    pub fn connected_pairs(&self) -> u64 {
+        self.read().connected_pairs()
+    }
+
+    /// See [`UnionFind::union_by_counting_pairs`].
+    pub fn union_by_counting_pairs<U: Union<T>>(
+        &self,
+        elem1: &T,
+        elem2: &T,
+        union: U,
+    ) -> Result<PairsUnion, UnionError<U::Err>> {
+        self.write().union_by_counting_pairs(elem1, elem2, union)
+    }
+}
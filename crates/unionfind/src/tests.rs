@@ -1,4 +1,50 @@
-use crate::HashUnionFindByRank;
+use crate::algorithms::{boruvka, kruskal, kruskal_by, offline_lca, single_linkage};
+use crate::bipartite::{BipartitenessChecker, Verdict};
+use crate::bridges::OnlineBridges;
+use crate::cell::CellUnionFind;
+use crate::concurrent::ConcurrentUnionFind;
+use crate::congruence::CongruenceClosure;
+use crate::dense::VecUnionFind;
+use crate::difference::{Constraint, DifferenceConstraints};
+use crate::distributed::{partition_of, Coordinator, Worker};
+use crate::encoding::{
+    decode_labels, encode_labels, encode_partition, normalize_labels, DecodeError,
+};
+use crate::entry::Entry;
+use crate::external::{
+    components_of_edge_list, components_of_edge_list_with_progress, EdgeListError, EdgeListStats,
+    Edges,
+};
+use crate::extra::{ByRank, BySize, Extra, GrowableExtra, Members, MergeExtra, Values};
+use crate::generic::{
+    AddError, InvalidStructure, NewUnionFindError, RootedUnion, SetRepresentativeError,
+    UnionByRankError, UnionError, UnionFind, UnionOrAddError, UnionStatus,
+};
+use crate::mapping::{
+    AlreadyIn, DenseMapping, GrowableMapping, Mapping, NotInOrder, PagedMapping, ParentMapping,
+};
+use crate::offline::{answer_connectivity_queries, Event};
+use crate::persistent::PersistentUnionFind;
+use crate::progress::{BulkError, CancellationToken, Progress};
+use crate::ranges::{merge_ip_nets, merge_ranges, IpNet, IpNetError, MergedRange};
+use crate::rollback::RollbackUnionFind;
+use crate::sketch::{ConnectivitySketch, SketchMismatch};
+use crate::slice::SliceUnionFind;
+use crate::small::SmallUnionFind;
+use crate::smt::{Conflict, Merge, TheoryUnionFind};
+use crate::sync::SyncUnionFind;
+use crate::timed::TimedUnionFind;
+use crate::transaction::Transaction;
+use crate::union::{infallible, ByMax, ByMin};
+use crate::watch::RepresentativeChange;
+use crate::weighted::{Group, Parity, UnionWithDiffError, WeightedUnionFind};
+use crate::{
+    BTreeUnionFindByRank, HashUnionFindByRank, HashUnionFindByRankWithHasher, HashUnionFindBySize,
+};
+use std::collections::{BTreeMap, HashMap};
+use std::convert::Infallible;
+use std::hash::{BuildHasherDefault, DefaultHasher};
+use std::ops::RangeInclusive;
 
 #[test]
 pub fn grow() {
@@ -88,3 +134,2709 @@ pub fn union_by_rank() {
 
     by_rank_test!(HashUnionFindByRank::<usize>);
 }
+
+#[test]
+pub fn get_or_insert_with() {
+    let mut uf = HashUnionFindByRank::<usize>::new([0, 1]).unwrap();
+    uf.union_by_rank(&0, &1).unwrap();
+
+    let mut calls = 0;
+    let root = uf
+        .get_or_insert_with(0, || {
+            calls += 1;
+            0
+        })
+        .unwrap();
+    assert_eq!(Some(root), uf.find(&1));
+    assert_eq!(calls, 0);
+
+    let root = uf
+        .get_or_insert_with(2, || {
+            calls += 1;
+            0
+        })
+        .unwrap();
+    assert_eq!(root, 2);
+    assert_eq!(uf.find(&2), Some(2));
+    assert_eq!(calls, 1);
+}
+
+#[test]
+pub fn difference_constraints() {
+    let mut solver = DifferenceConstraints::new();
+    solver.add_constraint("a", "b", 3).unwrap();
+    solver.add_constraint("b", "c", 4).unwrap();
+    solver.add_constraint("d", "e", -2).unwrap();
+    // implied by the first two constraints
+    solver.add_constraint("a", "c", 7).unwrap();
+
+    assert_eq!(solver.diff(&"c", &"a"), Some(-7));
+    assert_eq!(solver.diff(&"a", &"d"), None);
+
+    let solution = solver.solution();
+    assert_eq!(solution["a"] - solution["b"], 3);
+    assert_eq!(solution["b"] - solution["c"], 4);
+    assert_eq!(solution["d"] - solution["e"], -2);
+
+    let err = solver.add_constraint("c", "a", 1).unwrap_err();
+    assert_eq!(
+        err.witness,
+        vec![
+            Constraint { x: "b", y: "c", c: 4 },
+            Constraint { x: "a", y: "b", c: 3 },
+            Constraint { x: "c", y: "a", c: 1 },
+        ]
+    );
+
+    // the rejected constraint was not added
+    assert_eq!(solver.diff(&"c", &"a"), Some(-7));
+}
+
+#[test]
+pub fn bipartiteness() {
+    let mut checker = BipartitenessChecker::with_explanations();
+    assert_eq!(checker.add_edge(1, 2), Verdict::Bipartite);
+    assert_eq!(checker.add_edge(2, 3), Verdict::Bipartite);
+    assert_eq!(checker.add_edge(3, 4), Verdict::Bipartite);
+    assert_eq!(checker.add_edge(4, 1), Verdict::Bipartite);
+    assert_eq!(checker.same_side(&1, &3), Some(true));
+    assert_eq!(checker.same_side(&1, &4), Some(false));
+
+    assert_eq!(
+        checker.add_edge(1, 3),
+        Verdict::BecameNonBipartite {
+            odd_cycle: Some(vec![1, 2, 3])
+        }
+    );
+    assert!(!checker.is_bipartite());
+    assert_eq!(checker.first_odd_edge(), Some(&(1, 3)));
+    assert_eq!(checker.add_edge(5, 6), Verdict::NonBipartite);
+
+    let mut checker = BipartitenessChecker::new();
+    assert_eq!(
+        checker.add_edge(7, 7),
+        Verdict::BecameNonBipartite { odd_cycle: None }
+    );
+}
+
+#[test]
+pub fn theory_union_find() {
+    let mut uf = TheoryUnionFind::new();
+    uf.mark_interesting("f(a)");
+    uf.assert_distinct("a", "d", 1).unwrap();
+    uf.assert_equal("f(a)", "b", 2).unwrap();
+    assert_eq!(
+        uf.drain_merges().collect::<Vec<_>>(),
+        vec![Merge {
+            from: "f(a)",
+            into: "b"
+        }]
+    );
+
+    uf.push_level();
+    uf.assert_equal("a", "b", 3).unwrap();
+    uf.assert_equal("c", "d", 4).unwrap();
+    assert_eq!(uf.explain(&"a", &"f(a)"), Some(vec![3, 2]));
+    assert_eq!(
+        uf.assert_equal("b", "c", 5),
+        Err(Conflict {
+            literals: vec![3, 5, 4, 1]
+        })
+    );
+    assert!(!uf.are_equal(&"a", &"d"));
+    assert_eq!(
+        uf.assert_distinct("f(a)", "a", 6),
+        Err(Conflict {
+            literals: vec![2, 3, 6]
+        })
+    );
+
+    uf.backtrack_to(0);
+    assert_eq!(uf.level(), 0);
+    assert!(!uf.are_equal(&"a", &"b"));
+    assert!(uf.are_equal(&"b", &"f(a)"));
+    uf.assert_equal("b", "c", 5).unwrap();
+    uf.assert_equal("a", "c", 7).unwrap();
+    assert_eq!(
+        uf.assert_equal("a", "d", 8),
+        Err(Conflict {
+            literals: vec![8, 1]
+        })
+    );
+}
+
+#[test]
+pub fn watch() {
+    let mut uf = HashUnionFindByRank::<usize>::new(0..6).unwrap();
+    assert!(uf.watch(&0));
+    assert!(uf.watch(&4));
+    assert!(!uf.watch(&10));
+
+    uf.union_by_rank(&0, &1).unwrap();
+    uf.union_by_rank(&2, &3).unwrap();
+    uf.union_by_rank(&1, &3).unwrap();
+    assert_eq!(
+        uf.drain_representative_changes().collect::<Vec<_>>(),
+        vec![
+            RepresentativeChange {
+                elem: 0,
+                old: 0,
+                new: 1
+            },
+            RepresentativeChange {
+                elem: 0,
+                old: 1,
+                new: 3
+            },
+        ]
+    );
+
+    assert!(uf.unwatch(&0));
+    uf.union_by_rank(&0, &4).unwrap();
+    assert_eq!(
+        uf.drain_representative_changes().collect::<Vec<_>>(),
+        vec![RepresentativeChange {
+            elem: 4,
+            old: 4,
+            new: 3
+        }]
+    );
+}
+
+/// Extra information used in tests: an optional kind for every class,
+/// where classes of different kinds can't be unioned.
+#[derive(Debug)]
+struct Kinds(HashMap<usize, Option<&'static str>>);
+
+#[derive(Debug, PartialEq)]
+struct KindMismatch(&'static str, &'static str);
+
+impl Extra<usize, Option<&'static str>> for Kinds {
+    type DefaultMappingErr = Infallible;
+
+    fn default_mapping(
+        elems: impl IntoIterator<Item = usize>,
+    ) -> Result<Self, Self::DefaultMappingErr> {
+        Ok(Kinds(elems.into_iter().map(|i| (i, None)).collect()))
+    }
+}
+
+impl GrowableExtra<usize, Option<&'static str>> for Kinds {
+    type AddError = Infallible;
+
+    fn add(&mut self, k: usize, v: Option<&'static str>) -> Result<(), Self::AddError> {
+        self.0.insert(k, v);
+        Ok(())
+    }
+}
+
+impl MergeExtra<usize> for Kinds {
+    type MergeError = KindMismatch;
+
+    fn merge(&mut self, a: &usize, b: &usize, into: &usize) -> Result<(), Self::MergeError> {
+        let kind = match (self.0[a], self.0[b]) {
+            (Some(a), Some(b)) if a != b => return Err(KindMismatch(a, b)),
+            (a, b) => a.or(b),
+        };
+        self.0.insert(*into, kind);
+        Ok(())
+    }
+}
+
+#[test]
+pub fn merge_extra_veto() {
+    let mut uf = UnionFind::<usize, Option<&str>, Kinds>::new([]).unwrap();
+    uf.add_with_extra(0, Some("int")).unwrap();
+    uf.add_with_extra(1, None).unwrap();
+    uf.add_with_extra(2, Some("bool")).unwrap();
+
+    let first = infallible(|a, _| a);
+    uf.union_by(&1, &0, first).unwrap();
+    assert_eq!(uf.find(&0), Some(1));
+
+    assert!(matches!(
+        uf.union_by(&2, &0, first),
+        Err(UnionError::ExtraNotMergeable(KindMismatch("bool", "int")))
+    ));
+    assert_eq!(uf.find(&2), Some(2));
+    assert_eq!(uf.find(&0), Some(1));
+}
+
+#[test]
+pub fn union_by_closure() {
+    let mut uf = UnionFind::<usize, (), ()>::new(0..4).unwrap();
+    let mut unions = 0;
+    let mut even_only = |a: usize, b: usize| {
+        unions += 1;
+        if a % 2 == b % 2 {
+            Ok(a.min(b))
+        } else {
+            Err("different parity")
+        }
+    };
+
+    assert!(matches!(
+        uf.union_by(&3, &1, &mut even_only),
+        Ok(UnionStatus::PerformedUnion)
+    ));
+    assert!(matches!(
+        uf.union_by(&0, &1, &mut even_only),
+        Err(UnionError::NotUnionable("different parity"))
+    ));
+    assert_eq!(unions, 2);
+    assert_eq!(uf.find(&3), Some(1));
+    assert_eq!(uf.find(&0), Some(0));
+}
+
+#[test]
+pub fn connected_pairs() {
+    let mut uf = HashUnionFindBySize::<usize>::new(0..6).unwrap();
+    let first = infallible(|a, _| a);
+    assert_eq!(uf.connected_pairs(), 0);
+
+    uf.union_by(&0, &1, first).unwrap();
+    uf.union_by(&2, &3, first).unwrap();
+    uf.union_by(&4, &2, first).unwrap();
+    assert_eq!(uf.connected_pairs(), 4);
+
+    let res = uf.union_by_counting_pairs(&1, &3, first).unwrap();
+    assert_eq!(res.status, UnionStatus::PerformedUnion);
+    assert_eq!(res.newly_connected_pairs, 6);
+    assert_eq!(uf.connected_pairs(), 10);
+
+    let res = uf.union_by_counting_pairs(&0, &4, first).unwrap();
+    assert_eq!(res.status, UnionStatus::AlreadyEquivalent);
+    assert_eq!(res.newly_connected_pairs, 0);
+}
+
+#[test]
+pub fn raw_access() {
+    let mut uf = HashUnionFindByRank::<usize>::new(0..4).unwrap();
+    uf.union_by_rank(&0, &1).unwrap();
+    assert_eq!(uf.raw().len(), 4);
+    assert_eq!(uf.validate(), Ok(()));
+
+    uf.raw_mut().insert(2, 3);
+    assert_eq!(uf.num_sets(), 2);
+    uf.raw_mut().insert(3, 2);
+    assert!(matches!(uf.validate(), Err(InvalidStructure::Cycle { .. })));
+
+    uf.raw_mut().insert(3, 7);
+    assert_eq!(
+        uf.validate(),
+        Err(InvalidStructure::DanglingParent { elem: 3, parent: 7 })
+    );
+
+    let (mut parent, extra) = uf.into_raw_parts();
+    parent.insert(3, 3);
+    let uf = HashUnionFindByRank::from_raw_parts(parent, extra).unwrap();
+    assert_eq!(uf.find(&2), Some(3));
+}
+
+#[test]
+pub fn iteration() {
+    let mut uf = HashUnionFindByRank::<usize>::new(0..4).unwrap();
+    uf.union_by_rank(&0, &1).unwrap();
+    uf.union_by_rank(&2, &1).unwrap();
+
+    let mut keys: Vec<_> = uf.keys().collect();
+    keys.sort();
+    assert_eq!(keys, vec![0, 1, 2, 3]);
+    assert_eq!(uf.len(), 4);
+
+    for (elem, parent) in &uf {
+        assert_eq!(uf.find(&elem), uf.find(parent));
+    }
+
+    let root = uf.find(&0).unwrap();
+    let mut pairs: Vec<_> = uf.into_iter().collect();
+    pairs.sort();
+    assert_eq!(pairs, vec![(0, root), (1, root), (2, root), (3, 3)]);
+}
+
+#[test]
+pub fn index_representative() {
+    let mut uf = HashUnionFindByRank::new(0..4).unwrap();
+    uf.union_by_rank(&0, &1).unwrap();
+    uf.union_by_rank(&1, &2).unwrap();
+
+    assert_eq!(uf[&2], uf.find(&0).unwrap());
+    assert_eq!(uf[&3], 3);
+}
+
+#[test]
+#[should_panic(expected = "element is not in the union find")]
+pub fn index_unknown_element() {
+    let uf = HashUnionFindByRank::new(0..4).unwrap();
+    let _ = uf[&4];
+}
+
+#[cfg(not(loom))]
+#[test]
+pub fn sync_union_find() {
+    let uf = SyncUnionFind::new(HashUnionFindByRank::<usize>::new(0..100).unwrap());
+
+    let handles: Vec<_> = (0..4)
+        .map(|t| {
+            let uf = uf.clone();
+            std::thread::spawn(move || {
+                for i in (t..99).step_by(4) {
+                    uf.union_by_rank(&i, &(i + 1)).unwrap();
+                }
+            })
+        })
+        .collect();
+    for handle in handles {
+        handle.join().unwrap();
+    }
+
+    assert_eq!(uf.find(&0), uf.find(&99));
+    uf.add(100).unwrap();
+    assert_eq!(uf.find_or_add(&100), Ok(100));
+    assert_ne!(uf.find(&0), uf.find(&100));
+
+    let uf = uf.into_inner().unwrap();
+    assert_eq!(uf.len(), 101);
+}
+
+#[test]
+pub fn compression_budget() {
+    let mut uf = UnionFind::<usize, ()>::new(0..6).unwrap();
+    // build the chain 0 -> 1 -> 2 -> 3 -> 4 -> 5
+    for i in 0..5 {
+        uf.union_by(&i, &(i + 1), infallible(|_, b| b)).unwrap();
+    }
+    let chain = uf.raw().clone();
+
+    uf.set_compression_budget(Some(2));
+    assert_eq!(uf.find_shorten(&0), Some(5));
+    let changed = uf.raw().iter().filter(|(k, v)| chain[k] != **v).count();
+    assert_eq!(changed, 2);
+    assert_eq!(uf.raw()[&0], 5);
+    assert_eq!(uf.raw()[&1], 5);
+    assert_eq!(uf.raw()[&2], 3);
+
+    uf.set_compression_budget(None);
+    assert_eq!(uf.find_shorten(&2), Some(5));
+    assert!(uf.raw().values().all(|&parent| parent == 5));
+}
+
+#[test]
+pub fn progress_and_cancellation() {
+    let mut uf = HashUnionFindByRank::<usize>::new(0..100).unwrap();
+    let mut reported = Vec::new();
+    let merges = uf
+        .union_all_by_rank_with_progress(
+            (0..20).map(|i| (i, i + 1)),
+            &mut Progress::none().report_every(8, |n| reported.push(n)),
+        )
+        .unwrap();
+    assert_eq!(merges, 20);
+    assert_eq!(reported, vec![8, 16]);
+
+    let token = CancellationToken::new();
+    let cancel = token.clone();
+    let mut progress = Progress::none()
+        .report_every(10, move |_| cancel.cancel())
+        .cancel_with(token);
+    assert_eq!(
+        uf.union_all_by_rank_with_progress((50..99).map(|i| (i, i + 1)), &mut progress),
+        Err(BulkError::Cancelled { processed: 10 })
+    );
+    assert_eq!(uf.find(&50), uf.find(&60));
+    assert_ne!(uf.find(&50), uf.find(&61));
+
+    assert_eq!(
+        uf.union_all_by_rank_with_progress([(1, 2), (3, 200)], &mut Progress::none()),
+        Err(BulkError::Item {
+            index: 1,
+            error: UnionByRankError::Elem2NotFound
+        })
+    );
+}
+
+#[test]
+pub fn progress_of_other_bulk_operations() {
+    let token = CancellationToken::new();
+    let cancel = token.clone();
+    let mut progress = Progress::none()
+        .report_every(2, move |_| cancel.cancel())
+        .cancel_with(token);
+    let mut uf = HashUnionFindBySize::<u32>::new(0..10).unwrap();
+    assert!(matches!(
+        uf.union_all_with_progress((0..9).map(|i| (i + 1, i)), ByMin, &mut progress),
+        Err(BulkError::Cancelled { processed: 2 })
+    ));
+    assert_eq!(uf.find(&2), Some(0));
+    assert_eq!(uf.find(&3), Some(3));
+
+    let mut reported = Vec::new();
+    let uf = HashUnionFindByRank::from_pairs_with_progress(
+        [(1, 2), (3, 4), (2, 3)],
+        &mut Progress::none().report_every(1, |n| reported.push(n)),
+    )
+    .unwrap();
+    assert_eq!(uf.num_sets(), 1);
+    assert_eq!(reported, vec![1, 2, 3]);
+
+    let token = CancellationToken::new();
+    token.cancel();
+    assert!(matches!(
+        HashUnionFindByRank::from_pairs_with_progress(
+            [(1, 2)],
+            &mut Progress::none().cancel_with(token.clone())
+        ),
+        Err(BulkError::Cancelled { processed: 0 })
+    ));
+
+    let mut output = Vec::new();
+    let cancelled = components_of_edge_list_with_progress::<u32>(
+        "1 2\n2 3\n".as_bytes(),
+        &mut output,
+        &mut Progress::none().cancel_with(token),
+    );
+    assert!(matches!(
+        cancelled,
+        Err(EdgeListError::Cancelled { edges: 0 })
+    ));
+    assert!(output.is_empty());
+}
+
+#[test]
+pub fn serde_roundtrip() {
+    let mut uf = HashUnionFindByRank::<usize>::new(0..10).unwrap();
+    for i in (0..8).step_by(2) {
+        uf.union_by_rank(&i, &(i + 2)).unwrap();
+    }
+
+    let serialized = serde_json::to_vec(&uf).unwrap();
+    let deserialized: HashUnionFindByRank<usize> =
+        serde_json::from_reader(serialized.as_slice()).unwrap();
+
+    assert_eq!(deserialized.validate(), Ok(()));
+    for i in 0..10 {
+        assert_eq!(deserialized.find(&i), uf.find(&i));
+    }
+}
+
+#[test]
+pub fn edge_list_components() {
+    let edges = "# a comment\n1 2\n2,3\n\n4\t5 0.5\n3 1\n";
+    let mut output = Vec::new();
+    let stats = components_of_edge_list::<u32>(edges.as_bytes(), &mut output).unwrap();
+    assert_eq!(
+        stats,
+        EdgeListStats {
+            vertices: 5,
+            edges: 4,
+            components: 2,
+        }
+    );
+
+    let labels: HashMap<u32, u32> = String::from_utf8(output)
+        .unwrap()
+        .lines()
+        .map(|line| {
+            let (vertex, component) = line.split_once(',').unwrap();
+            (vertex.parse().unwrap(), component.parse().unwrap())
+        })
+        .collect();
+    assert_eq!(labels.len(), 5);
+    assert_eq!(labels[&1], labels[&2]);
+    assert_eq!(labels[&1], labels[&3]);
+    assert_eq!(labels[&4], labels[&5]);
+    assert_ne!(labels[&1], labels[&4]);
+
+    let malformed = components_of_edge_list::<u32>("1 2\n3\n".as_bytes(), std::io::sink());
+    assert!(matches!(
+        malformed,
+        Err(EdgeListError::Malformed { line: 2 })
+    ));
+    let invalid = components_of_edge_list::<u32>("1 x\n".as_bytes(), std::io::sink());
+    assert!(matches!(
+        invalid,
+        Err(EdgeListError::InvalidVertex { line: 1, .. })
+    ));
+
+    // the third column is only a weight when asked for
+    let labeled = "1 2 friends\n2 3 0.5\n";
+    let edges: Vec<_> = Edges::<_, u32>::new(labeled.as_bytes()).collect();
+    assert!(edges.iter().all(|edge| edge.as_ref().unwrap().weight.is_none()));
+    let mut weighted = Edges::<_, u32>::new(labeled.as_bytes()).with_weights();
+    assert!(matches!(
+        weighted.next(),
+        Some(Err(EdgeListError::InvalidWeight { line: 1, .. }))
+    ));
+    assert_eq!(weighted.next().unwrap().unwrap().weight, Some(0.5));
+}
+
+#[test]
+pub fn distributed_components() {
+    let edges = [(0, 1), (1, 2), (3, 4), (5, 6), (6, 7), (7, 5), (2, 8), (9, 9)];
+    let partitions = 3;
+    let mut workers: Vec<Worker<u32>> = (0..partitions)
+        .map(|partition| Worker::new(partition, partitions))
+        .collect();
+    for (u, v) in edges {
+        for owner in [partition_of(&u, partitions), partition_of(&v, partitions)] {
+            assert!(workers[owner].add_edge(u, v));
+        }
+    }
+
+    let mut coordinator = Coordinator::new(partitions);
+    for worker in &mut workers {
+        coordinator.receive(worker.boundary_report());
+    }
+    for worker in &mut workers {
+        worker.apply(coordinator.relabel(worker.partition()));
+    }
+
+    let mut expected = HashUnionFindByRank::new(0..10).unwrap();
+    for (u, v) in edges {
+        expected.union_by_rank(&u, &v).unwrap();
+    }
+
+    let labels: HashMap<u32, u32> = workers
+        .iter_mut()
+        .flat_map(|worker| worker.components())
+        .collect();
+    assert_eq!(labels.len(), 10);
+    for u in 0..10 {
+        for v in 0..10 {
+            assert_eq!(
+                labels[&u] == labels[&v],
+                expected.find(&u) == expected.find(&v)
+            );
+        }
+    }
+}
+
+#[test]
+pub fn serialize_without_extra() {
+    let mut uf = HashUnionFindBySize::<usize>::new(0..10).unwrap();
+    for i in 0..4 {
+        uf.union_by(&i, &(i + 1), infallible(|a, _| a)).unwrap();
+    }
+    uf.union_by(&7, &8, infallible(|a, _| a)).unwrap();
+
+    let mut serialized = Vec::new();
+    uf.serialize_without_extra(&mut serde_json::Serializer::new(&mut serialized))
+        .unwrap();
+    let deserialized = HashUnionFindBySize::<usize>::deserialize_without_extra(
+        &mut serde_json::Deserializer::from_slice(&serialized),
+    )
+    .unwrap();
+    assert_eq!(deserialized.connected_pairs(), uf.connected_pairs());
+    for i in 0..10 {
+        assert_eq!(deserialized.find(&i), uf.find(&i));
+    }
+
+    let mut ranked = HashUnionFindByRank::<usize>::new(0..4).unwrap();
+    ranked.union_by_rank(&0, &1).unwrap();
+    ranked.union_by_rank(&2, &3).unwrap();
+    ranked.union_by_rank(&0, &2).unwrap();
+    let serialized = serde_json::to_vec(&ranked.raw().iter().collect::<Vec<_>>()).unwrap();
+    let mut deserialized = HashUnionFindByRank::<usize>::deserialize_without_extra(
+        &mut serde_json::Deserializer::from_slice(&serialized),
+    )
+    .unwrap();
+    let root = deserialized.find(&0).unwrap();
+    assert_eq!(deserialized.clone().into_raw_parts().1.rank(&root), Some(2));
+    deserialized.union_by_rank(&3, &1).unwrap();
+
+    let cyclic = serde_json::to_vec(&[(0, 1), (1, 0)]).unwrap();
+    assert!(HashUnionFindByRank::<usize>::deserialize_without_extra(
+        &mut serde_json::Deserializer::from_slice(&cyclic),
+    )
+    .is_err());
+}
+
+#[test]
+pub fn deserialize_rejects_invalid_parents() {
+    let uf = HashUnionFindByRank::<usize>::new(0..2).unwrap();
+    let mut json = serde_json::to_value(&uf).unwrap();
+
+    json["parent"] = serde_json::json!([[0, 1], [1, 0]]);
+    let cyclic = serde_json::from_value::<HashUnionFindByRank<usize>>(json.clone());
+    assert!(cyclic.unwrap_err().to_string().contains("cycle"));
+
+    json["parent"] = serde_json::json!([[0, 0], [1, 2]]);
+    let dangling = serde_json::from_value::<HashUnionFindByRank<usize>>(json);
+    assert!(dangling.unwrap_err().to_string().contains("not in the union find"));
+}
+
+#[cfg(feature = "rayon")]
+#[test]
+pub fn rayon_collect() {
+    use rayon::prelude::*;
+
+    let mut uf: HashUnionFindByRank<usize> = (0..100).into_par_iter().collect();
+    assert_eq!(uf.len(), 100);
+    uf.par_extend((50..150).into_par_iter());
+    assert_eq!(uf.len(), 150);
+    assert_eq!(uf.validate(), Ok(()));
+
+    let pairs: Vec<(usize, usize)> = (0..1000).map(|i| (i, (i + 10) % 1000)).collect();
+    let uf = HashUnionFindByRank::par_from_pairs(pairs.into_par_iter());
+    assert_eq!(uf.len(), 1000);
+    for i in 0..1000 {
+        assert_eq!(uf.find(&i), uf.find(&(i % 10)));
+    }
+    assert_ne!(uf.find(&0), uf.find(&1));
+}
+
+#[cfg(feature = "rayon")]
+#[test]
+pub fn rayon_union_all() {
+    use rayon::prelude::*;
+
+    let mut uf = HashUnionFindByRank::new(0..10).unwrap();
+    uf.union_by_rank(&0, &1).unwrap();
+    let pairs: Vec<(usize, usize)> = (1..1000).map(|i| (i, (i + 10) % 1000)).collect();
+    assert_eq!(uf.union_all_par(pairs.into_par_iter()), Ok(990));
+    assert_eq!(uf.len(), 1000);
+    assert_eq!(uf.num_sets(), 9);
+    assert_eq!(uf.find(&990), uf.find(&1));
+    assert_eq!(uf.find(&10), uf.find(&1));
+    assert_ne!(uf.find(&2), uf.find(&3));
+}
+
+#[cfg(feature = "rayon")]
+#[test]
+pub fn rayon_find_many() {
+    let mut uf = HashUnionFindByRank::from_pairs((0..1000).map(|i| (i, (i + 10) % 1000)));
+    let queries: Vec<usize> = (0..2000).rev().collect();
+    let expected: Vec<Option<usize>> = queries.iter().map(|elem| uf.find(elem)).collect();
+    assert_eq!(uf.par_find_many(&queries), expected);
+
+    uf.compress_all();
+    assert!(uf.iter().all(|(elem, root)| uf[&elem] == *root));
+    assert_eq!(uf.par_find_many(&queries), expected);
+    assert_eq!(uf.validate(), Ok(()));
+}
+
+#[cfg(feature = "rayon")]
+#[test]
+pub fn rayon_deterministic() {
+    use rayon::prelude::*;
+
+    let pairs: Vec<(usize, usize)> = (0..1000)
+        .map(|i| ((i * 7) % 1000, (i * 7 + 10) % 1000))
+        .collect();
+    let build = |threads| {
+        let pool = rayon::ThreadPoolBuilder::new()
+            .num_threads(threads)
+            .build()
+            .unwrap();
+        pool.install(|| {
+            HashUnionFindByRank::par_from_pairs_deterministic(pairs.par_iter().copied())
+        })
+    };
+
+    let single = build(1);
+    let multi = build(4);
+    assert_eq!(single.raw(), multi.raw());
+    assert_eq!(single.validate(), Ok(()));
+    for i in 0..1000 {
+        assert_eq!(single.find(&i), Some(i % 10));
+    }
+}
+
+#[test]
+pub fn fallible_constructors() {
+    let uf = HashUnionFindByRank::try_new("1 2 3".split(' ').map(str::parse::<u32>)).unwrap();
+    assert_eq!(uf.len(), 3);
+    assert!(HashUnionFindByRank::try_new("1 x 3".split(' ').map(str::parse::<u32>)).is_err());
+
+    let pairs: [Result<_, Infallible>; 3] = [Ok((1, 2)), Ok((3, 4)), Ok((2, 3))];
+    let uf = HashUnionFindByRank::try_from_pairs(pairs).unwrap();
+    assert_eq!(uf.find(&1), uf.find(&4));
+
+    let pairs = [Ok((1, 2)), Err("broken"), Ok((2, 3))];
+    assert_eq!(
+        HashUnionFindByRank::try_from_pairs(pairs).unwrap_err(),
+        "broken"
+    );
+}
+
+#[test]
+pub fn dense_mapping() {
+    let mut mapping = DenseMapping::<usize>::identity_map([3, 1000, 5]).unwrap();
+    assert_eq!(mapping.len(), 3);
+    assert!(mapping.contains_key(&1000));
+    assert!(!mapping.contains_key(&4));
+    assert!(!mapping.contains_key(&2000));
+    assert_eq!(mapping.add(5, 5), Err(AlreadyIn));
+
+    mapping.add(0, 3).unwrap();
+    mapping.set(5, 3);
+    assert_eq!(mapping.get(&5), Some(&3));
+    assert_eq!(mapping.keys().collect::<Vec<_>>(), vec![0, 3, 5, 1000]);
+    assert_eq!(
+        mapping.iter().collect::<Vec<_>>(),
+        vec![(0, &3), (3, &3), (5, &3), (1000, &1000)]
+    );
+}
+
+#[test]
+pub fn small_union_find() {
+    // implements neither Hash nor Ord
+    #[derive(Debug, PartialEq, Eq)]
+    struct Point(u8, u8);
+
+    let mut uf = SmallUnionFind::new();
+    for i in 0..4 {
+        assert!(uf.add(Point(i, i)));
+    }
+    assert!(!uf.add(Point(0, 0)));
+    assert_eq!(uf.len(), 4);
+
+    assert_eq!(
+        uf.union(&Point(0, 0), &Point(1, 1)).unwrap(),
+        UnionStatus::PerformedUnion
+    );
+    uf.union(&Point(2, 2), &Point(1, 1)).unwrap();
+    assert_eq!(
+        uf.union(&Point(0, 0), &Point(2, 2)).unwrap(),
+        UnionStatus::AlreadyEquivalent
+    );
+    assert!(matches!(
+        uf.union(&Point(5, 5), &Point(0, 0)),
+        Err(UnionError::Elem1NotFound)
+    ));
+
+    assert_eq!(uf.find(&Point(1, 1)), Some(&Point(2, 2)));
+    assert_eq!(uf.find_shorten(&Point(0, 0)), Some(&Point(2, 2)));
+    assert_eq!(uf.find(&Point(3, 3)), Some(&Point(3, 3)));
+    assert_eq!(uf.iter().filter(|(elem, root)| elem == root).count(), 2);
+}
+
+#[test]
+pub fn paged_mapping() {
+    let keys = [u64::MAX, 5, 6, 1 << 40, (1 << 40) + 3];
+    let mut mapping = PagedMapping::<u64, 64>::identity_map(keys).unwrap();
+    assert_eq!(mapping.len(), 5);
+    assert_eq!(mapping.pages(), 3);
+    assert!(mapping.contains_key(&(1 << 40)));
+    assert!(!mapping.contains_key(&7));
+    assert_eq!(mapping.add(6, 0), Err(AlreadyIn));
+
+    mapping.set(6, 5);
+    assert_eq!(mapping.get(&6), Some(&5));
+    assert_eq!(
+        mapping.keys().collect::<Vec<_>>(),
+        vec![5, 6, 1 << 40, (1 << 40) + 3, u64::MAX]
+    );
+}
+
+#[test]
+pub fn pinned_representatives() {
+    let mut uf = HashUnionFindByRank::new(0..6).unwrap();
+    uf.union_by_rank(&0, &1).unwrap();
+    uf.union_by_rank(&2, &3).unwrap();
+    let pinned = uf.pin(&4).unwrap();
+    assert_eq!(pinned, 4);
+
+    // 4 has a lower rank, but is pinned
+    uf.union_by_rank(&0, &4).unwrap();
+    uf.union_by_rank(&2, &4).unwrap();
+    for i in 0..5 {
+        assert_eq!(uf.find(&i), Some(4));
+    }
+
+    uf.pin(&5);
+    assert_eq!(uf.union_by_rank(&5, &0), Err(UnionByRankError::BothPinned));
+    assert!(uf.unpin(&5));
+    uf.union_by_rank(&5, &0).unwrap();
+    assert_eq!(uf.find(&5), Some(4));
+    assert_eq!(uf.validate(), Ok(()));
+
+    let mut uf = UnionFind::<usize, ()>::new(0..3).unwrap();
+    uf.pin(&1);
+    uf.union_by(&1, &2, infallible(|_, b| b)).unwrap();
+    assert_eq!(uf.find(&2), Some(1));
+}
+
+#[test]
+pub fn online_bridges() {
+    let mut bridges = OnlineBridges::new();
+    bridges.add_edge(0, 1);
+    bridges.add_edge(1, 2);
+    bridges.add_edge(2, 3);
+    assert_eq!(bridges.bridge_count(), 3);
+    bridges.add_edge(3, 1);
+    assert_eq!(bridges.bridge_count(), 1);
+    assert!(bridges.two_edge_connected(&1, &3));
+    assert!(!bridges.two_edge_connected(&0, &1));
+
+    bridges.add_edge(4, 5);
+    bridges.add_edge(5, 0);
+    assert_eq!(bridges.bridge_count(), 3);
+    assert!(bridges.connected(&4, &2));
+    bridges.add_edge(4, 2);
+    assert_eq!(bridges.bridge_count(), 0);
+    assert!(bridges.two_edge_connected(&0, &4));
+
+    // compare with removing every edge in turn on a pseudo random graph
+    let mut bridges = OnlineBridges::new();
+    let mut edges = Vec::new();
+    let mut seed = 7u64;
+    for _ in 0..40 {
+        seed = seed.wrapping_mul(6364136223846793005).wrapping_add(1442695040888963407);
+        let u = (seed >> 33) % 20;
+        let v = (seed >> 45) % 20;
+        edges.push((u, v));
+        bridges.add_edge(u, v);
+
+        let expected = (0..edges.len())
+            .filter(|&removed| {
+                let (u, v) = edges[removed];
+                let mut uf = HashUnionFindByRank::new(0..20).unwrap();
+                for (i, (a, b)) in edges.iter().enumerate() {
+                    if i != removed {
+                        uf.union_by_rank(a, b).unwrap();
+                    }
+                }
+                uf.find(&u) != uf.find(&v)
+            })
+            .count();
+        assert_eq!(bridges.bridge_count(), expected);
+    }
+}
+
+#[test]
+pub fn offline_connectivity_queries() {
+    let answers = answer_connectivity_queries([
+        Event::Connected("a", "b"),
+        Event::Edge("a", "b"),
+        Event::Connected("a", "b"),
+        Event::Edge("c", "d"),
+        Event::Connected("a", "d"),
+        Event::Edge("b", "c"),
+        Event::Connected("a", "d"),
+        Event::Connected("e", "e"),
+    ]);
+    assert_eq!(answers, vec![false, true, false, true, true]);
+}
+
+#[test]
+pub fn quotient_edges() {
+    let mut uf = HashUnionFindByRank::new(0..6).unwrap();
+    uf.union_by_rank(&0, &1).unwrap();
+    uf.union_by_rank(&2, &3).unwrap();
+    let edges = [(0, 1), (1, 2), (0, 3), (3, 4), (4, 9)];
+    let root01 = uf.find(&0).unwrap();
+    let root23 = uf.find(&2).unwrap();
+
+    assert_eq!(
+        uf.quotient_edges(edges, false),
+        vec![(root01, root23), (root01, root23), (root23, 4), (4, 9)]
+    );
+    assert_eq!(
+        uf.quotient_edges(edges, true),
+        vec![(root01, root23), (root23, 4), (4, 9)]
+    );
+
+    #[cfg(feature = "petgraph")]
+    {
+        let graph = uf.quotient_graph(edges);
+        // four classes, and 9 which is not in the union find
+        assert_eq!(graph.node_count(), 5);
+        assert_eq!(graph.edge_count(), 3);
+        assert!(graph.edge_weights().any(|&weight| weight == 2));
+    }
+}
+
+/// Run with `RUSTFLAGS="--cfg loom" cargo test --release loom_`.
+#[cfg(loom)]
+#[test]
+pub fn loom_sync_union_find() {
+    loom::model(|| {
+        let uf = SyncUnionFind::new(HashUnionFindByRank::<usize>::new(0..4).unwrap());
+
+        let handles: Vec<_> = [(0, 1), (2, 3)]
+            .into_iter()
+            .map(|(a, b)| {
+                let uf = uf.clone();
+                loom::thread::spawn(move || {
+                    uf.union_by_rank(&a, &b).unwrap();
+                    uf.find_or_add(&4).unwrap()
+                })
+            })
+            .collect();
+        let roots: Vec<_> = handles.into_iter().map(|h| h.join().unwrap()).collect();
+
+        assert_eq!(roots, vec![4, 4]);
+        assert_eq!(uf.len(), 5);
+        assert_eq!(uf.find(&0), uf.find(&1));
+        assert_eq!(uf.find(&2), uf.find(&3));
+        assert_ne!(uf.find(&0), uf.find(&2));
+        assert_eq!(uf.validate(), Ok(()));
+    });
+}
+
+/// Run with `RUSTFLAGS="--cfg loom" cargo test --release loom_`.
+#[cfg(loom)]
+#[test]
+pub fn loom_concurrent_union_find() {
+    loom::model(|| {
+        let uf = loom::sync::Arc::new(ConcurrentUnionFind::new(3));
+
+        let handles: Vec<_> = [(0, 1), (2, 1)]
+            .into_iter()
+            .map(|(a, b)| {
+                let uf = uf.clone();
+                loom::thread::spawn(move || {
+                    uf.union(a, b).unwrap();
+                    assert_eq!(uf.connected(a, b), Some(true));
+                })
+            })
+            .collect();
+        for handle in handles {
+            handle.join().unwrap();
+        }
+
+        let mut uf = loom::sync::Arc::try_unwrap(uf).unwrap();
+        assert_eq!(uf.num_sets(), 1);
+        assert_eq!(uf.connected(0, 2), Some(true));
+        let roots = uf.roots();
+        assert!(roots.iter().all(|root| *root == roots[0]));
+    });
+}
+
+#[test]
+pub fn merge_overlapping_ranges() {
+    let empty = RangeInclusive::new(14, 13);
+    let ranges = [10..=12, 1..=3, 20..=25, 4..=5, 2..=2, empty.clone(), 13..=19];
+    assert_eq!(
+        merge_ranges(&ranges),
+        vec![
+            MergedRange {
+                range: 1..=5,
+                members: vec![1, 3, 4],
+            },
+            MergedRange {
+                range: 10..=25,
+                members: vec![0, 2, 6],
+            },
+            MergedRange {
+                range: empty,
+                members: vec![5],
+            },
+        ]
+    );
+
+    let ranges = [250u8..=255, 0..=3, 255..=255];
+    assert_eq!(merge_ranges(&ranges).len(), 2);
+}
+
+#[test]
+pub fn merge_cidr_blocks() {
+    let nets: Vec<IpNet> = ["10.0.0.0/25", "10.0.1.0/24", "10.0.0.128/25", "192.168.0.1", "::/0"]
+        .iter()
+        .map(|net| net.parse().unwrap())
+        .collect();
+    let merged = merge_ip_nets(&nets);
+
+    let display = |nets: &[IpNet]| nets.iter().map(IpNet::to_string).collect::<Vec<_>>();
+    assert_eq!(merged.len(), 3);
+    assert_eq!(merged[0].0, vec![0, 1, 2]);
+    assert_eq!(display(&merged[0].1), vec!["10.0.0.0/23"]);
+    assert_eq!(display(&merged[1].1), vec!["192.168.0.1/32"]);
+    assert_eq!(display(&merged[2].1), vec!["::/0"]);
+
+    // the last IPv4 address has no successor, but isn't adjacent to the first IPv6 address
+    let nets: Vec<IpNet> = ["255.255.255.0/24", "::/128"]
+        .iter()
+        .map(|net| net.parse().unwrap())
+        .collect();
+    let merged = merge_ip_nets(&nets);
+    assert_eq!(merged.len(), 2);
+    assert_eq!(display(&merged[0].1), vec!["255.255.255.0/24"]);
+    assert_eq!(display(&merged[1].1), vec!["::/128"]);
+
+    let range = "10.0.0.1".parse().unwrap()..="10.0.0.6".parse().unwrap();
+    assert_eq!(
+        display(&IpNet::covering(&range)),
+        vec!["10.0.0.1/32", "10.0.0.2/31", "10.0.0.4/31", "10.0.0.6/32"]
+    );
+
+    assert_eq!("10.0.0.1/24".parse::<IpNet>(), Err(IpNetError::HostBitsSet));
+    assert_eq!(
+        "10.0.0.0/33".parse::<IpNet>(),
+        Err(IpNetError::InvalidPrefix("33".to_string()))
+    );
+}
+
+#[test]
+pub fn path_between() {
+    let mut uf = HashUnionFindByRank::new(0..6).unwrap();
+    uf.union_by_rank(&0, &1).unwrap();
+    assert_eq!(uf.path_between(&0, &1), None);
+
+    uf.track_paths();
+    uf.union_by_rank(&2, &3).unwrap();
+    uf.union_by_rank(&3, &4).unwrap();
+    uf.union_by_rank(&4, &2).unwrap();
+    uf.union_by_rank(&5, &4).unwrap();
+    uf.union_by_rank(&1, &5).unwrap();
+
+    assert_eq!(uf.path_between(&2, &5), Some(vec![2, 3, 4, 5]));
+    assert_eq!(uf.path_between(&1, &2), Some(vec![1, 5, 4, 3, 2]));
+    assert_eq!(uf.path_between(&3, &3), Some(vec![3]));
+    // 0 and 1 were unioned before paths were tracked
+    assert_eq!(uf.path_between(&0, &2), None);
+}
+
+#[test]
+pub fn explain() {
+    let mut uf = HashUnionFindByRank::new(0..6).unwrap();
+    uf.track_paths();
+    uf.union_by_rank(&2, &3).unwrap();
+    uf.union_by_rank(&4, &3).unwrap();
+    uf.union_by_rank(&0, &1).unwrap();
+    uf.union_by_rank(&4, &2).unwrap();
+    uf.union_by_rank(&1, &4).unwrap();
+    for elem in 0..6 {
+        uf.find_shorten(&elem);
+    }
+
+    assert_eq!(uf.explain(&2, &4), Some(vec![(2, 3), (4, 3)]));
+    assert_eq!(uf.explain(&0, &2), Some(vec![(0, 1), (1, 4), (4, 3), (2, 3)]));
+    assert_eq!(uf.explain(&1, &1), Some(vec![]));
+    assert_eq!(uf.explain(&0, &5), None);
+}
+
+#[cfg(feature = "schemars")]
+#[test]
+pub fn json_schema() {
+    use crate::distributed::BoundaryReport;
+
+    let schema = serde_json::to_value(schemars::schema_for!(HashUnionFindByRank<u32>)).unwrap();
+    let properties = schema["properties"].as_object().unwrap();
+    // the schema describes exactly the fields that are serialized
+    let serialized = serde_json::to_value(HashUnionFindByRank::new(0..4u32).unwrap()).unwrap();
+    let mut expected: Vec<_> = serialized.as_object().unwrap().keys().collect();
+    let mut described: Vec<_> = properties.keys().collect();
+    expected.sort();
+    described.sort();
+    assert_eq!(described, expected);
+    assert_eq!(properties["parent"]["type"], "array");
+
+    let schema = serde_json::to_value(schemars::schema_for!(BoundaryReport<String>)).unwrap();
+    assert!(schema["properties"]["boundary"].is_object());
+}
+
+#[test]
+pub fn reset_to_singletons() {
+    let mut uf = HashUnionFindBySize::new(0..4).unwrap();
+    uf.union_by(&0, &1, infallible(|a, _| a)).unwrap();
+    uf.union_by(&1, &2, infallible(|a, _| a)).unwrap();
+    uf.watch(&2);
+    assert_eq!(uf.connected_pairs(), 3);
+
+    uf.reset_to_singletons();
+    assert_eq!(uf.len(), 4);
+    assert_eq!(uf.connected_pairs(), 0);
+    for i in 0..4 {
+        assert_eq!(uf.find(&i), Some(i));
+    }
+    assert_eq!(
+        uf.drain_representative_changes().collect::<Vec<_>>(),
+        vec![RepresentativeChange {
+            elem: 2,
+            old: 0,
+            new: 2
+        }]
+    );
+
+    uf.union_by(&3, &2, infallible(|a, _| a)).unwrap();
+    assert_eq!(uf.connected_pairs(), 1);
+    assert_eq!(uf.find(&2), Some(3));
+}
+
+#[cfg(feature = "async")]
+#[test]
+pub fn cooperative_bulk_operations() {
+    use std::future::Future;
+    use std::sync::Arc;
+    use std::task::{Context, Poll, Wake, Waker};
+
+    struct NoopWaker;
+    impl Wake for NoopWaker {
+        fn wake(self: Arc<Self>) {}
+    }
+
+    /// Polls a future to completion, returning its output and how often it yielded.
+    fn run<F: Future>(future: F) -> (F::Output, usize) {
+        let waker = Waker::from(Arc::new(NoopWaker));
+        let mut cx = Context::from_waker(&waker);
+        let mut future = std::pin::pin!(future);
+        let mut yields = 0;
+        loop {
+            match future.as_mut().poll(&mut cx) {
+                Poll::Ready(output) => return (output, yields),
+                Poll::Pending => yields += 1,
+            }
+        }
+    }
+
+    let mut uf = HashUnionFindByRank::empty();
+    assert_eq!(run(uf.extend_async(0..100, 10)), (Ok(100), 10));
+    assert_eq!(run(uf.extend_async(50..150, 1000)), (Ok(50), 0));
+
+    let pairs = (0..149).map(|i| (i, i + 1));
+    assert_eq!(run(uf.union_all_by_rank_async(pairs, 50)), (Ok(149), 2));
+
+    let (roots, yields) = run(uf.find_all_async([0, 149, 150], 1));
+    assert_eq!(yields, 3);
+    assert_eq!(roots[0], roots[1]);
+    assert_eq!(roots[2], None);
+
+    let (result, _) = run(uf.union_all_by_rank_async([(0, 1), (0, 150)], 1));
+    assert_eq!(
+        result,
+        Err(BulkError::Item {
+            index: 1,
+            error: UnionByRankError::Elem2NotFound
+        })
+    );
+}
+
+#[test]
+pub fn slice_union_find() {
+    let words = ["apple", "bear", "avocado", "cat", "banana", "cherry", "dog"];
+    let mut uf = SliceUnionFind::new(&words[..]);
+    for i in 0..words.len() {
+        for j in i + 1..words.len() {
+            if words[i].as_bytes()[0] == words[j].as_bytes()[0] {
+                uf.union(i, j).unwrap();
+            }
+        }
+    }
+    assert!(matches!(uf.union(0, 7), Err(UnionError::Elem2NotFound)));
+
+    let groups: Vec<Vec<&str>> = uf.groups().map(|group| group.copied().collect()).collect();
+    assert_eq!(
+        groups,
+        vec![
+            vec!["apple", "avocado"],
+            vec!["bear", "banana"],
+            vec!["cat", "cherry"],
+            vec!["dog"]
+        ]
+    );
+
+    let mut uf = SliceUnionFind::new(words.to_vec());
+    uf.union(6, 0).unwrap();
+    uf.union(3, 2).unwrap();
+    uf.union(5, 2).unwrap();
+    assert_eq!(uf.reorder(), vec![0..2, 2..3, 3..6, 6..7]);
+    assert_eq!(
+        uf.items(),
+        ["apple", "dog", "bear", "avocado", "cat", "cherry", "banana"]
+    );
+    assert_eq!(uf.find(1), Some(0));
+    assert_eq!(uf.find(5), Some(3));
+}
+
+#[test]
+pub fn partition_encoding() {
+    let mut uf = HashUnionFindByRank::new(0..10_000u32).unwrap();
+    for i in 0..10_000 {
+        uf.union_by_rank(&i, &((i * 7919) % 10_000 / 50)).unwrap();
+    }
+    let elems: Vec<u32> = (0..10_000).collect();
+    let encoded = encode_partition(&uf, &elems);
+    let labels = decode_labels(&encoded).unwrap();
+    assert_eq!(labels, normalize_labels(elems.iter().map(|i| uf[i])));
+    assert!(encoded.len() * 5 < serde_json::to_vec(&uf).unwrap().len());
+
+    for labels in [vec![], vec![0], vec![0, 1, 2, 3], vec![0, 0, 1, 0, 2, 1, 1, 3]] {
+        assert_eq!(decode_labels(&encode_labels(&labels)), Ok(labels));
+    }
+
+    let encoded = encode_labels(&[0, 1, 0, 1]);
+    assert_eq!(
+        decode_labels(&encoded[..encoded.len() - 1]),
+        Err(DecodeError::Truncated)
+    );
+    let mut invalid = encoded.clone();
+    *invalid.last_mut().unwrap() = 2;
+    assert_eq!(decode_labels(&invalid), Err(DecodeError::InvalidLabel(3)));
+    let mut trailing = encoded;
+    trailing.push(0);
+    assert_eq!(decode_labels(&trailing), Err(DecodeError::TrailingBytes(1)));
+}
+
+#[test]
+pub fn connectivity_sketch() {
+    // three cycles with chords, and an isolated vertex
+    let mut edges = Vec::new();
+    for (start, len) in [(0u64, 40u64), (40, 30), (70, 29)] {
+        for i in 0..len {
+            edges.push((start + i, start + (i + 1) % len));
+            edges.push((start + i, start + (i * 7 + 3) % len));
+        }
+    }
+    edges.retain(|(u, v)| u != v);
+    edges.sort_by_key(|&(u, v)| (u.min(v), u.max(v)));
+    edges.dedup_by_key(|&mut (u, v)| (u.min(v), u.max(v)));
+
+    let mut sketch = ConnectivitySketch::new(12, 42);
+    let mut other = ConnectivitySketch::new(12, 42);
+    for (i, &(u, v)) in edges.iter().enumerate() {
+        if i % 2 == 0 {
+            sketch.add_edge(u, v);
+        } else {
+            other.add_edge(u, v);
+        }
+    }
+    other.add_vertex(99);
+    sketch.merge(&other).unwrap();
+    assert_eq!(sketch.vertex_count(), 100);
+
+    let uf = sketch.components();
+    assert_eq!(sketch.component_count(), 4);
+    assert_eq!(uf.find(&0), uf.find(&39));
+    assert_eq!(uf.find(&40), uf.find(&69));
+    assert_ne!(uf.find(&0), uf.find(&40));
+    assert_eq!(uf.find(&99), Some(99));
+
+    // removing every edge between 0..20 and 20..40 splits the first cycle
+    for &(u, v) in &edges {
+        if u < 40 && v < 40 && (u < 20) != (v < 20) {
+            sketch.remove_edge(u, v);
+        }
+    }
+    assert_eq!(sketch.component_count(), 5);
+
+    assert_eq!(
+        sketch.merge(&ConnectivitySketch::new(12, 7)),
+        Err(SketchMismatch)
+    );
+}
+
+#[test]
+pub fn union_by_size() {
+    let mut uf = HashUnionFindBySize::new(0..6).unwrap();
+    uf.union_by_size(&0, &1).unwrap();
+    uf.union_by_size(&2, &0).unwrap();
+    assert_eq!(uf.set_size(&2), Some(3));
+    let root = uf.find(&0).unwrap();
+    assert_ne!(root, 2);
+
+    // the larger class keeps its representative, whichever argument it is
+    uf.union_by_size(&3, &1).unwrap();
+    assert_eq!(uf.find(&3), Some(root));
+    assert_eq!(uf.set_size(&3), Some(4));
+    assert_eq!(uf.set_size(&5), Some(1));
+    assert_eq!(uf.set_size(&6), None);
+    assert_eq!(uf.connected_pairs(), 6);
+    assert!(matches!(uf.union_by_size(&6, &0), Err(UnionError::Elem1NotFound)));
+
+    // tracked paths contain the elements that were passed, not their representatives
+    let mut uf = HashUnionFindBySize::new(0..4).unwrap();
+    uf.track_paths();
+    uf.union_by_size(&0, &1).unwrap();
+    uf.union_by_size(&2, &3).unwrap();
+    uf.union_by_size(&1, &3).unwrap();
+    assert_eq!(uf.explain(&1, &3), Some(vec![(1, 3)]));
+    assert_eq!(uf.path_between(&0, &2), Some(vec![0, 1, 3, 2]));
+}
+
+#[test]
+pub fn num_sets() {
+    let mut uf = HashUnionFindByRank::new(0..5).unwrap();
+    assert_eq!(uf.num_sets(), 5);
+    uf.union_by_rank(&0, &1).unwrap();
+    uf.union_by_rank(&1, &0).unwrap();
+    uf.add(5).unwrap();
+    assert!(uf.add(5).is_err());
+    uf.union_by_rank(&2, &5).unwrap();
+    assert_eq!(uf.num_sets(), 4);
+
+    let json = serde_json::to_string(&uf).unwrap();
+    let uf: HashUnionFindByRank<usize> = serde_json::from_str(&json).unwrap();
+    assert_eq!(uf.num_sets(), 4);
+    let uf = HashUnionFindByRank::from_pairs([(0, 1), (2, 3), (1, 3), (4, 4)]);
+    assert_eq!(uf.num_sets(), 2);
+}
+
+#[test]
+pub fn connected() {
+    let mut uf = HashUnionFindByRank::new(0..4).unwrap();
+    uf.union_by_rank(&0, &1).unwrap();
+    uf.union_by_rank(&1, &2).unwrap();
+
+    assert_eq!(uf.connected(&0, &2), Some(true));
+    assert_eq!(uf.connected(&3, &3), Some(true));
+    assert_eq!(uf.connected(&0, &3), Some(false));
+    assert_eq!(uf.connected(&0, &4), None);
+    assert_eq!(uf.connected(&4, &5), None);
+}
+
+#[test]
+pub fn members_of() {
+    let mut uf: UnionFind<usize, (), Members<usize>> = UnionFind::new(0..6).unwrap();
+    uf.union_by(&0, &1, infallible(|a, _| a)).unwrap();
+    uf.union_by(&2, &3, infallible(|a, _| a)).unwrap();
+    uf.union_by(&1, &3, infallible(|a, _| a)).unwrap();
+    uf.union_by(&4, &4, infallible(|a, _| a)).unwrap();
+
+    let mut scanned: Vec<_> = uf.members_of(&2).unwrap().collect();
+    scanned.sort();
+    assert_eq!(scanned, vec![0, 1, 2, 3]);
+
+    let members: Vec<_> = uf.class_members(&2).unwrap().copied().collect();
+    assert_eq!(members[0], 2);
+    let mut members = members;
+    members.sort();
+    assert_eq!(members, scanned);
+    assert_eq!(uf.class_members(&5).unwrap().collect::<Vec<_>>(), vec![&5]);
+    assert!(uf.members_of(&6).is_none());
+    assert!(uf.class_members(&6).is_none());
+
+    let mut serialized = Vec::new();
+    uf.serialize_without_extra(&mut serde_json::Serializer::new(&mut serialized))
+        .unwrap();
+    let uf = UnionFind::<usize, (), Members<usize>>::deserialize_without_extra(
+        &mut serde_json::Deserializer::from_slice(&serialized),
+    )
+    .unwrap();
+    let mut members: Vec<_> = uf.class_members(&0).unwrap().copied().collect();
+    members.sort();
+    assert_eq!(members, vec![0, 1, 2, 3]);
+}
+
+#[test]
+pub fn roots_and_iter() {
+    let mut uf = HashUnionFindByRank::new(0..5).unwrap();
+    uf.union_by_rank(&0, &1).unwrap();
+    uf.union_by_rank(&2, &1).unwrap();
+    uf.union_by_rank(&3, &4).unwrap();
+
+    let mut roots: Vec<_> = uf.roots().copied().collect();
+    roots.sort();
+    assert_eq!(roots, vec![uf[&0].min(uf[&3]), uf[&0].max(uf[&3])]);
+    assert_eq!(uf.iter().count(), 5);
+    for (elem, root) in &uf {
+        assert_eq!(uf.find(&elem).as_ref(), Some(root));
+    }
+    assert_eq!(uf.parents().count(), 5);
+}
+
+#[test]
+pub fn classes() {
+    let mut uf = HashUnionFindByRank::new(0..6).unwrap();
+    uf.union_by_rank(&0, &1).unwrap();
+    uf.union_by_rank(&1, &2).unwrap();
+    uf.union_by_rank(&3, &4).unwrap();
+
+    let classes = uf.classes();
+    assert_eq!(classes.len(), 3);
+    for (root, members) in &classes {
+        assert!(members.iter().all(|member| &uf[member] == root));
+    }
+
+    let mut classes: Vec<_> = uf
+        .into_classes()
+        .into_iter()
+        .map(|mut class| {
+            class.sort();
+            class
+        })
+        .collect();
+    classes.sort();
+    assert_eq!(classes, vec![vec![0, 1, 2], vec![3, 4], vec![5]]);
+}
+
+#[test]
+pub fn vec_union_find() {
+    let mut uf = VecUnionFind::with_len(4);
+    assert_eq!(uf.push(), 4);
+    assert!(matches!(uf.add(6), Err(AddError::Parent(NotInOrder))));
+    assert_eq!(uf.union_by_rank(&0, &1).unwrap(), UnionStatus::PerformedUnion);
+    assert_eq!(uf.union_by_rank(&2, &1).unwrap(), UnionStatus::PerformedUnion);
+    assert_eq!(uf.union_by_rank(&0, &2).unwrap(), UnionStatus::AlreadyEquivalent);
+    assert!(matches!(uf.union_by_rank(&5, &0), Err(UnionByRankError::Elem1NotFound)));
+
+    assert_eq!(uf.len(), 5);
+    assert_eq!(uf.num_sets(), 3);
+    assert_eq!(uf.connected(&0, &2), Some(true));
+    assert_eq!(uf.connected(&3, &4), Some(false));
+    assert_eq!(uf.find(&2), uf.find(&0));
+    assert_eq!(uf.find(&5), None);
+    assert_eq!(uf.iter().filter(|(key, root)| key == *root).count(), 3);
+}
+
+#[test]
+pub fn custom_parent_mapping() {
+    let mut uf: UnionFind<usize, usize, ByRank<usize>, DenseMapping<usize>> =
+        UnionFind::new([1, 3, 5, 7]).unwrap();
+    uf.union_by_rank(&1, &3).unwrap();
+    uf.union_by(&5, &7, infallible(|a, _| a)).unwrap();
+    uf.add(9).unwrap();
+    assert!(matches!(uf.add(9), Err(AddError::Parent(AlreadyIn))));
+    assert_eq!(uf.connected(&1, &3), Some(true));
+    assert_eq!(uf.connected(&3, &5), Some(false));
+    assert_eq!(uf.find(&2), None);
+    assert_eq!(uf.num_sets(), 3);
+
+    let json = serde_json::to_string(&uf).unwrap();
+    let uf: UnionFind<usize, usize, ByRank<usize>, DenseMapping<usize>> =
+        serde_json::from_str(&json).unwrap();
+    assert_eq!(uf.num_sets(), 3);
+    assert_eq!(uf[&7], 5);
+
+    let mut uf: UnionFind<&str, (), (), BTreeMap<&str, &str>> =
+        UnionFind::new(["a", "b", "c"]).unwrap();
+    uf.union_by(&"a", &"c", infallible(|_, b| b)).unwrap();
+    assert_eq!(uf.find_shorten(&"a"), Some("c"));
+}
+
+#[test]
+pub fn btree_union_find_serializes_in_order() {
+    let build = |elems: Vec<u32>| {
+        let mut uf = BTreeUnionFindByRank::new(elems).unwrap();
+        uf.union_by_rank(&4, &1).unwrap();
+        uf.union_by_rank(&2, &3).unwrap();
+        serde_json::to_string(&uf).unwrap()
+    };
+    let json = build(vec![3, 1, 4, 2, 0]);
+    assert_eq!(json, build(vec![0, 1, 2, 3, 4]));
+    assert!(json.starts_with(r#"{"parent":[[0,0],[1,1],[2,3],[3,3],[4,1]],"extra""#));
+
+    let mut uf: BTreeUnionFindByRank<u32> = serde_json::from_str(&json).unwrap();
+    assert_eq!(uf.num_sets(), 3);
+    assert_eq!(uf.find_shorten(&4), Some(1));
+}
+
+#[test]
+pub fn btree_union_find_iterates_in_order() {
+    let mut uf = BTreeUnionFindByRank::new([3, 1, 4, 2, 0]).unwrap();
+    uf.union_by_rank(&4, &1).unwrap();
+    uf.union_by_rank(&2, &3).unwrap();
+
+    assert_eq!(uf.len(), 5);
+    assert_eq!(uf.keys().collect::<Vec<_>>(), vec![0, 1, 2, 3, 4]);
+    assert_eq!(
+        uf.iter().map(|(elem, root)| (elem, *root)).collect::<Vec<_>>(),
+        vec![(0, 0), (1, 1), (2, 3), (3, 3), (4, 1)]
+    );
+    assert_eq!(uf.roots().collect::<Vec<_>>(), vec![&0, &1, &3]);
+    assert_eq!(uf.raw().keys().copied().collect::<Vec<_>>(), vec![0, 1, 2, 3, 4]);
+    assert_eq!(uf.classes()[&1], vec![1, 4]);
+}
+
+#[test]
+pub fn custom_hasher() {
+    type Hasher = BuildHasherDefault<DefaultHasher>;
+    let mut uf = HashUnionFindByRankWithHasher::<u32, Hasher>::new(0..4).unwrap();
+    uf.union_by_rank(&0, &1).unwrap();
+    uf.add(4).unwrap();
+    uf.union_by_rank(&4, &1).unwrap();
+    assert_eq!(uf.connected(&0, &4), Some(true));
+    assert_eq!(uf.num_sets(), 3);
+
+    let json = serde_json::to_string(&uf).unwrap();
+    let mut uf: HashUnionFindByRankWithHasher<u32, Hasher> = serde_json::from_str(&json).unwrap();
+    assert_eq!(uf.connected(&1, &4), Some(true));
+    assert_eq!(uf.len(), 5);
+    assert_eq!(uf.iter().count(), 5);
+    assert_eq!(uf.classes().len(), 3);
+    assert_eq!(uf.restrict([0, 4]).connected(&0, &4), Some(true));
+
+    let mut transaction = uf.begin();
+    transaction.union_by_rank(&2, &3).unwrap();
+    transaction.abort();
+    assert_eq!(uf.num_sets(), 3);
+    assert!(uf.remove(&1));
+    assert_eq!(uf.connected(&0, &4), Some(true));
+
+    let mut sizes: UnionFind<u32, (), BySize<u32, Hasher>, HashMap<u32, u32, Hasher>> =
+        UnionFind::new(0..4).unwrap();
+    sizes.union_by_size(&0, &1).unwrap();
+    assert_eq!(sizes.set_size(&1), Some(2));
+
+    let mut members: UnionFind<u32, (), Members<u32, Hasher>> = UnionFind::new(0..4).unwrap();
+    members.union_by(&2, &3, infallible(|a, _| a)).unwrap();
+    assert_eq!(members.class_members(&3).unwrap().count(), 2);
+
+    let mut values: UnionFind<u32, u32, Values<u32, u32, Hasher>> =
+        UnionFind::try_new((0..4).map(Ok::<_, Infallible>)).unwrap();
+    *values.value_mut(&0).unwrap() = 5;
+    values.union_values(&0, &1, |a, b| Ok::<_, Infallible>(a + b)).unwrap();
+    assert_eq!(values.value(&1), Some(&5));
+}
+
+#[test]
+pub fn find_on_long_chain() {
+    const LEN: usize = 200_000;
+    let mut uf: UnionFind<usize, ()> = UnionFind::new(0..LEN).unwrap();
+    for i in 0..LEN - 1 {
+        uf.union_by(&i, &(i + 1), infallible(|_, b| b)).unwrap();
+    }
+    assert_eq!(uf.find(&0), Some(LEN - 1));
+
+    uf.set_compression_budget(Some(10));
+    assert_eq!(uf.find_shorten(&0), Some(LEN - 1));
+    assert_eq!(uf.raw()[&9], LEN - 1);
+    assert_eq!(uf.raw()[&10], 11);
+
+    uf.set_compression_budget(None);
+    assert_eq!(uf.find_shorten(&10), Some(LEN - 1));
+    assert!(uf.raw().values().all(|&parent| parent == LEN - 1));
+}
+
+#[test]
+pub fn find_halve_and_split() {
+    let chain = || {
+        let parent: HashMap<usize, usize> = (0..8).map(|i| (i, (i + 1).min(7))).collect();
+        UnionFind::<usize, ()>::from_raw_parts(parent, ()).unwrap()
+    };
+
+    let mut uf = chain();
+    assert_eq!(uf.find_halve(&0), Some(7));
+    let expected: HashMap<_, _> = [(0, 2), (1, 2), (2, 4), (3, 4), (4, 6), (5, 6), (6, 7), (7, 7)]
+        .into_iter()
+        .collect();
+    assert_eq!(uf.raw(), &expected);
+
+    let mut uf = chain();
+    assert_eq!(uf.find_split(&0), Some(7));
+    let expected: HashMap<_, _> = [(0, 2), (1, 3), (2, 4), (3, 5), (4, 6), (5, 7), (6, 7), (7, 7)]
+        .into_iter()
+        .collect();
+    assert_eq!(uf.raw(), &expected);
+    assert_eq!(uf.find_split(&8), None);
+}
+
+#[test]
+pub fn cell_union_find() {
+    let parent: HashMap<usize, usize> = (0..5).map(|i| (i, (i + 1).min(4))).collect();
+    let uf = CellUnionFind::new(UnionFind::<usize, ()>::from_raw_parts(parent, ()).unwrap());
+
+    let shared = &uf;
+    assert_eq!(shared.find(&0), Some(4));
+    assert!(uf.borrow().raw().values().all(|&parent| parent == 4));
+
+    let borrowed = uf.borrow();
+    assert_eq!(uf.find(&2), Some(4));
+    assert_eq!(uf.connected(&0, &5), None);
+    drop(borrowed);
+
+    uf.union_by(&0, &0, infallible(|a, _| a)).unwrap();
+    assert_eq!(uf.into_inner().num_sets(), 1);
+}
+
+#[test]
+pub fn rollback_union_find() {
+    let mut uf = RollbackUnionFind::new(0..4);
+    uf.union(&0, &1).unwrap();
+    let before = uf.snapshot();
+
+    uf.union(&1, &2).unwrap();
+    assert!(uf.add(4));
+    uf.union(&4, &3).unwrap();
+    let middle = uf.snapshot();
+    assert_eq!(uf.union(&2, &0).unwrap(), UnionStatus::AlreadyEquivalent);
+    uf.union(&0, &3).unwrap();
+    assert_eq!(uf.num_sets(), 1);
+
+    uf.rollback(middle);
+    assert_eq!(uf.connected(&0, &3), Some(false));
+    assert_eq!(uf.connected(&3, &4), Some(true));
+
+    uf.rollback(before);
+    assert_eq!(uf.connected(&0, &1), Some(true));
+    assert_eq!(uf.connected(&1, &2), Some(false));
+    assert_eq!(uf.find(&4), None);
+    assert_eq!(uf.num_sets(), 3);
+    assert_eq!(uf.len(), 4);
+}
+
+#[test]
+pub fn persistent_union_find() {
+    let empty = PersistentUnionFind::new(0..100);
+    let (first, status) = empty.union(&0, &1).unwrap();
+    assert_eq!(status, UnionStatus::PerformedUnion);
+    let (second, _) = first.union(&2, &3).unwrap();
+    let (other, _) = first.union(&1, &2).unwrap();
+
+    assert_eq!(empty.connected(&0, &1), Some(false));
+    assert_eq!(first.connected(&0, &1), Some(true));
+    assert_eq!(first.connected(&2, &3), Some(false));
+    assert_eq!(second.connected(&2, &3), Some(true));
+    assert_eq!(second.connected(&1, &2), Some(false));
+    assert_eq!(other.connected(&0, &2), Some(true));
+    assert_eq!(other.connected(&2, &3), Some(false));
+    assert_eq!(
+        (empty.num_sets(), first.num_sets(), second.num_sets()),
+        (100, 99, 98)
+    );
+
+    let mut chain = second.clone();
+    for i in 1..100 {
+        chain = chain.union(&(i - 1), &i).unwrap().0;
+    }
+    assert_eq!(chain.num_sets(), 1);
+    assert_eq!(second.num_sets(), 98);
+    assert_eq!(chain.union(&0, &99).unwrap().1, UnionStatus::AlreadyEquivalent);
+    assert!(matches!(
+        chain.add(100).union(&100, &101),
+        Err(UnionError::Elem2NotFound)
+    ));
+    assert_eq!(chain.len(), 100);
+}
+
+#[test]
+pub fn timed_union_find() {
+    let mut uf = TimedUnionFind::new(0..6);
+    uf.union(&0, &1).unwrap();
+    uf.union(&2, &3).unwrap();
+    assert_eq!(uf.union(&1, &0).unwrap(), UnionStatus::AlreadyEquivalent);
+    uf.union(&3, &0).unwrap();
+    uf.add(6);
+    uf.union(&6, &4).unwrap();
+    assert_eq!(uf.time(), 5);
+
+    assert_eq!(uf.connected_at(&0, &1, 0), Some(false));
+    assert_eq!(uf.connected_at(&0, &1, 1), Some(true));
+    assert_eq!(uf.connected_at(&1, &2, 3), Some(false));
+    assert_eq!(uf.connected_at(&1, &2, 4), Some(true));
+    assert_eq!(uf.connected_at(&4, &6, 5), Some(true));
+    assert_eq!(uf.find_at(&3, 1), Some(3));
+    assert_eq!(uf.find(&3), uf.find(&0));
+
+    assert_eq!(uf.connected_since(&0, &0), Some(0));
+    assert_eq!(uf.connected_since(&1, &0), Some(1));
+    assert_eq!(uf.connected_since(&1, &3), Some(4));
+    assert_eq!(uf.connected_since(&1, &4), None);
+    assert_eq!(uf.connected_since(&1, &7), None);
+    assert_eq!(uf.num_sets(), 3);
+}
+
+#[test]
+pub fn transaction_commit_and_abort() {
+    let mut uf = HashUnionFindByRank::new(0..4).unwrap();
+    uf.union_by_rank(&0, &1).unwrap();
+    assert!(uf.watch(&2));
+
+    let mut transaction: Transaction<_, _, _> = uf.begin();
+    transaction.add(4).unwrap();
+    transaction.union_by_rank(&4, &2).unwrap();
+    transaction.union_by_rank(&2, &0).unwrap();
+    assert_eq!(transaction.connected(&1, &4), Some(true));
+    assert_eq!(transaction.num_sets(), 2);
+    transaction.abort();
+
+    assert_eq!(uf.find(&4), None);
+    assert_eq!(uf.connected(&0, &2), Some(false));
+    assert_eq!(uf.num_sets(), 3);
+    assert!(uf.drain_representative_changes().next().is_none());
+
+    {
+        let mut transaction = uf.begin();
+        transaction.union_by_rank(&2, &3).unwrap();
+    }
+    assert_eq!(uf.connected(&2, &3), Some(false));
+
+    let mut transaction = uf.begin();
+    transaction.union_by_rank(&2, &3).unwrap();
+    transaction.union_by_rank(&3, &0).unwrap();
+    transaction.commit();
+    assert_eq!(uf.connected(&1, &2), Some(true));
+    assert_eq!(uf.num_sets(), 1);
+    assert_eq!(uf.drain_representative_changes().count(), 2);
+}
+
+#[cfg(not(loom))]
+#[test]
+pub fn concurrent_union_find() {
+    let mut uf = ConcurrentUnionFind::new(10_000);
+    std::thread::scope(|scope| {
+        for thread in 0..4 {
+            let uf = &uf;
+            scope.spawn(move || {
+                // every thread links a quarter of the edges of the paths through even and odd keys
+                for key in (thread..9_998).step_by(4) {
+                    uf.union(key, key + 2).unwrap();
+                }
+            });
+        }
+    });
+
+    assert_eq!(uf.num_sets(), 2);
+    assert_eq!(uf.connected(0, 9_998), Some(true));
+    assert_eq!(uf.connected(1, 9_999), Some(true));
+    assert_eq!(uf.connected(0, 1), Some(false));
+    assert_eq!(uf.connected(0, 10_000), None);
+    assert_eq!(uf.union(4, 8).unwrap(), UnionStatus::AlreadyEquivalent);
+    let roots = uf.roots();
+    assert!(roots.iter().step_by(2).all(|root| *root == roots[0]));
+}
+
+#[test]
+pub fn weighted_union_find() {
+    let mut uf = WeightedUnionFind::new();
+    for elem in ["a", "b", "c", "d"] {
+        assert!(uf.add(elem));
+    }
+    assert!(!uf.add("a"));
+
+    uf.union_with_diff(&"a", &"c", 3).unwrap();
+    uf.union_with_diff(&"b", &"c", -2).unwrap();
+    assert_eq!(uf.diff(&"a", &"b"), Some(5));
+    assert_eq!(uf.diff(&"b", &"a"), Some(-5));
+    assert_eq!(uf.diff(&"a", &"d"), None);
+
+    assert_eq!(uf.union_with_diff(&"a", &"b", 5), Ok(UnionStatus::AlreadyEquivalent));
+    assert_eq!(
+        uf.union_with_diff(&"b", &"a", 1),
+        Err(UnionWithDiffError::Contradiction { existing: -5 })
+    );
+    assert_eq!(
+        uf.union_with_diff(&"e", &"a", 1),
+        Err(UnionWithDiffError::Elem1NotFound)
+    );
+    uf.union_with_diff(&"d", &"b", 10).unwrap();
+    assert_eq!(uf.diff(&"d", &"a"), Some(5));
+    assert_eq!(uf.len(), 4);
+}
+
+#[test]
+pub fn parity_union_find() {
+    let mut uf = WeightedUnionFind::new();
+    for elem in 0..4 {
+        uf.add(elem);
+    }
+    uf.union_different(&0, &1).unwrap();
+    uf.union_different(&1, &2).unwrap();
+    uf.union_same(&3, &1).unwrap();
+    assert_eq!(uf.same_side(&0, &2), Some(true));
+    assert_eq!(uf.same_side(&0, &3), Some(false));
+    assert_eq!(uf.union_same(&2, &0), Ok(UnionStatus::AlreadyEquivalent));
+
+    // 0, 1 and 2 would form an odd cycle
+    assert_eq!(
+        uf.union_different(&0, &2),
+        Err(UnionWithDiffError::Contradiction {
+            existing: Parity(false)
+        })
+    );
+}
+
+/// A permutation of three elements, where `op` applies `self` first.
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Permutation([usize; 3]);
+
+impl Group for Permutation {
+    fn identity() -> Self {
+        Permutation([0, 1, 2])
+    }
+
+    fn op(&self, other: &Self) -> Self {
+        Permutation(self.0.map(|i| other.0[i]))
+    }
+
+    fn inverse(&self) -> Self {
+        let mut inverse = [0; 3];
+        for (i, j) in self.0.into_iter().enumerate() {
+            inverse[j] = i;
+        }
+        Permutation(inverse)
+    }
+}
+
+#[test]
+pub fn group_weighted_union_find() {
+    let swap = Permutation([1, 0, 2]);
+    let rotate = Permutation([1, 2, 0]);
+    assert_ne!(swap.op(&rotate), rotate.op(&swap));
+
+    let mut uf = WeightedUnionFind::new();
+    for elem in 0..4 {
+        uf.add(elem);
+    }
+    uf.union_with_diff(&0, &1, swap).unwrap();
+    uf.union_with_diff(&2, &3, rotate).unwrap();
+    uf.union_with_diff(&1, &2, rotate).unwrap();
+
+    assert_eq!(uf.diff(&0, &3), Some(swap.op(&rotate).op(&rotate)));
+    assert_eq!(uf.diff(&3, &0), Some(swap.op(&rotate).op(&rotate).inverse()));
+    assert_eq!(uf.diff(&2, &2), Some(Permutation::identity()));
+    assert_eq!(
+        uf.union_with_diff(&0, &2, rotate.op(&swap)),
+        Err(UnionWithDiffError::Contradiction {
+            existing: swap.op(&rotate)
+        })
+    );
+}
+
+#[test]
+pub fn union_values() {
+    // unification of type variables, where the value is the type a variable is known to be
+    let unify = |a: &Option<&'static str>, b: &Option<&'static str>| match (a, b) {
+        (Some(a), Some(b)) if a != b => Err((*a, *b)),
+        _ => Ok(a.or(*b)),
+    };
+    let mut uf: UnionFind<u32, Option<&str>, Values<u32, Option<&str>>> =
+        UnionFind::new(0..3).unwrap();
+    uf.add_with_extra(3, Some("int")).unwrap();
+    uf.add_with_extra(4, Some("bool")).unwrap();
+
+    assert_eq!(uf.union_values(&0, &1, unify).unwrap(), UnionStatus::PerformedUnion);
+    assert_eq!(uf.value(&1), Some(&None));
+    assert_eq!(uf.union_values(&1, &3, unify).unwrap(), UnionStatus::PerformedUnion);
+    assert_eq!(uf.value(&0), Some(&Some("int")));
+    assert!(matches!(
+        uf.union_values(&4, &0, unify),
+        Err(UnionError::ExtraNotMergeable(("bool", "int")))
+    ));
+    assert_eq!(uf.connected(&0, &4), Some(false));
+    assert_eq!(uf.value(&4), Some(&Some("bool")));
+
+    uf.pin(&2);
+    assert_eq!(uf.union_values(&0, &2, unify).unwrap(), UnionStatus::PerformedUnion);
+    assert_eq!(uf.find(&3), Some(2));
+    assert_eq!(uf.value(&2), Some(&Some("int")));
+    *uf.value_mut(&1).unwrap() = Some("float");
+    assert_eq!(uf.value(&3), Some(&Some("float")));
+    assert_eq!(uf.num_sets(), 2);
+}
+
+#[test]
+pub fn congruence_closure() {
+    let mut cc = CongruenceClosure::new();
+    let a = cc.constant("a");
+    let b = cc.constant("b");
+    let c = cc.constant("c");
+    let fa = cc.term("f", &[a]);
+    let fc = cc.term("f", &[c]);
+    cc.assert_equal(fa, b);
+    assert!(!cc.are_equal(fc, b));
+    cc.assert_equal(a, c);
+    assert!(cc.are_equal(fc, b));
+    assert_eq!(cc.term("f", &[a]), fa);
+    assert_eq!(cc.get(fc), Some((&"f", &[c][..])));
+
+    // from f(f(f(x))) = x and f(f(f(f(f(x))))) = x follows f(x) = x
+    let x = cc.constant("x");
+    let mut powers = vec![x];
+    for i in 0..5 {
+        let next = cc.term("f", &[powers[i]]);
+        powers.push(next);
+    }
+    cc.assert_equal(powers[3], x);
+    assert!(!cc.are_equal(powers[1], x));
+    cc.assert_equal(powers[5], x);
+    assert!(cc.are_equal(powers[1], x));
+
+    // terms created after an equality also take part in it
+    let gb = cc.term("g", &[b, x]);
+    let gfc = cc.term("g", &[fc, powers[2]]);
+    assert!(cc.are_equal(gb, gfc));
+    assert_eq!(cc.num_classes(), 4);
+}
+
+#[test]
+pub fn labeled_unions() {
+    let mut uf =
+        UnionFind::<_, usize, ByRank<_>, HashMap<_, _>, &str>::new(["a", "b", "c", "d", "e", "f"])
+            .unwrap();
+    uf.union_by_rank_labeled(&"a", &"b", "same email").unwrap();
+    uf.union_by_rank_labeled(&"c", &"d", "same phone").unwrap();
+    uf.union_by_rank_labeled(&"b", &"a", "same name").unwrap();
+    uf.union_by_rank_labeled(&"d", &"b", "same address").unwrap();
+    uf.union_by_rank(&"d", &"e").unwrap();
+    uf.union_by_labeled(&"f", &"e", ByMin, "same company").unwrap();
+
+    assert_eq!(
+        uf.labels_between(&"a", &"c"),
+        Some(vec![&"same email", &"same address", &"same phone"])
+    );
+    // the unlabeled union between d and e is left out
+    assert_eq!(
+        uf.labels_between(&"a", &"f"),
+        Some(vec![&"same email", &"same address", &"same company"])
+    );
+    assert_eq!(uf.path_between(&"a", &"f"), Some(vec!["a", "b", "d", "e", "f"]));
+    assert_eq!(uf.labels_between(&"b", &"b"), Some(vec![]));
+    assert_eq!(uf.find(&"a"), uf.find(&"f"));
+    assert_eq!(uf.num_sets(), 1);
+}
+
+#[test]
+pub fn kruskal_minimum_spanning_forest() {
+    let edges = [("a", "b", 4), ("b", "c", 1), ("a", "c", 2), ("c", "d", 7), ("e", "f", 3)];
+    assert_eq!(
+        kruskal(edges),
+        vec![("b", "c", 1), ("a", "c", 2), ("e", "f", 3), ("c", "d", 7)]
+    );
+
+    let edges = [(0, 1, 0.5), (1, 2, -1.0), (0, 2, 0.25)];
+    assert_eq!(
+        kruskal_by(edges, f64::total_cmp),
+        vec![(1, 2, -1.0), (0, 2, 0.25)]
+    );
+}
+
+#[test]
+pub fn boruvka_minimum_spanning_forest() {
+    let edges: Vec<(u32, u32, u32)> = (0..200)
+        .flat_map(|i| [(i, (i * 7 + 3) % 200, (i * 31) % 17), (i, (i + 1) % 200, 20)])
+        .chain([(200, 201, 5), (201, 202, 5), (200, 202, 5)])
+        .collect();
+    let mut expected = kruskal(edges.clone());
+    let mut forest = boruvka(edges);
+    assert_eq!(forest.len(), 201);
+    forest.sort();
+    expected.sort();
+    assert_eq!(forest, expected);
+}
+
+#[cfg(feature = "petgraph")]
+#[test]
+pub fn petgraph_components() {
+    use crate::graph::{components, from_graph};
+    use petgraph::graph::{NodeIndex, UnGraph};
+
+    let graph = UnGraph::<(), ()>::from_edges([(0, 1), (3, 2), (1, 4), (5, 5)]);
+    let uf = from_graph(&graph);
+    assert_eq!(uf.num_sets(), 3);
+    assert_eq!(uf.find(&NodeIndex::new(0)), uf.find(&NodeIndex::new(4)));
+
+    let node = |indices: &[usize]| indices.iter().map(|&i| NodeIndex::new(i)).collect::<Vec<_>>();
+    assert_eq!(
+        components(&graph),
+        vec![node(&[0, 1, 4]), node(&[2, 3]), node(&[5])]
+    );
+}
+
+#[cfg(feature = "petgraph")]
+#[test]
+pub fn petgraph_union_find_conversion() {
+    let mut theirs = petgraph::unionfind::UnionFind::<u32>::new(6);
+    theirs.union(0, 1);
+    theirs.union(2, 1);
+    theirs.union(4, 5);
+
+    let mut ours = VecUnionFind::from(theirs);
+    assert_eq!(ours.num_sets(), 3);
+    assert_eq!(ours.connected(&0, &2), Some(true));
+    assert_eq!(ours.connected(&3, &4), Some(false));
+    ours.union_by_rank(&3, &0).unwrap();
+
+    let theirs = petgraph::unionfind::UnionFind::<u32>::from(ours);
+    assert!(theirs.equiv(3, 2));
+    assert!(theirs.equiv(4, 5));
+    assert!(!theirs.equiv(0, 5));
+}
+
+#[cfg(feature = "unify")]
+#[test]
+pub fn unification_table() {
+    use crate::unify::{UnificationTable, UnifyKey};
+
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, serde::Serialize, serde::Deserialize)]
+    struct TypeVar(u32);
+
+    impl UnifyKey for TypeVar {
+        type Value = Option<String>;
+
+        fn index(&self) -> u32 {
+            self.0
+        }
+
+        fn from_index(index: u32) -> Self {
+            TypeVar(index)
+        }
+
+        fn tag() -> &'static str {
+            "TypeVar"
+        }
+    }
+
+    let mut table = UnificationTable::<TypeVar>::new();
+    let a = table.new_key(None);
+    let b = table.new_key(Some("int".to_string()));
+    let c = table.new_key(None);
+    assert_eq!(a, TypeVar(0));
+    assert_eq!(table.len(), 3);
+
+    table.unify_var_var(a, b).unwrap();
+    assert!(table.unioned(a, b));
+    assert_eq!(table.probe_value(a), Some("int".to_string()));
+
+    table.unify_var_value(c, Some("bool".to_string())).unwrap();
+    assert_eq!(table.unify_var_var(c, a), Err(("bool".to_string(), "int".to_string())));
+    assert!(!table.unioned(a, c));
+    assert_eq!(table.probe_value(c), Some("bool".to_string()));
+
+    let json = serde_json::to_string(&table).unwrap();
+    let mut table: UnificationTable<TypeVar> = serde_json::from_str(&json).unwrap();
+    assert!(table.unioned(b, a));
+    assert_eq!(table.probe_value(b), Some("int".to_string()));
+}
+
+#[test]
+pub fn grid_labeling() {
+    use crate::grid::{label_grid, FourOrEight};
+
+    let image = ["#..#", ".#.#", "...."];
+    let is_foreground = |x: usize, y: usize| image[y].as_bytes()[x] == b'#';
+
+    assert_eq!(
+        label_grid(4, 3, is_foreground, FourOrEight::Four),
+        vec![1, 0, 0, 2, 0, 3, 0, 2, 0, 0, 0, 0]
+    );
+    assert_eq!(
+        label_grid(4, 3, is_foreground, FourOrEight::Eight),
+        vec![1, 0, 0, 2, 0, 1, 0, 2, 0, 0, 0, 0]
+    );
+    assert!(label_grid(0, 5, is_foreground, FourOrEight::Eight).is_empty());
+}
+
+#[test]
+pub fn percolation() {
+    use crate::percolation::{BondPercolation, SitePercolation};
+
+    let mut sites = SitePercolation::new(3, 3);
+    assert!(sites.open(0, 0));
+    assert!(!sites.open(0, 0));
+    sites.open(0, 1);
+    sites.open(2, 2);
+    assert!(sites.is_full(0, 1));
+    assert!(!sites.percolates());
+    sites.open(1, 1);
+    sites.open(1, 2);
+    assert!(sites.percolates());
+    assert_eq!(sites.num_open(), 5);
+
+    // (2, 2) is connected to the bottom row, which is connected to the top row through
+    // the virtual bottom node only
+    let mut isolated = SitePercolation::new(3, 3);
+    for (x, y) in [(0, 0), (0, 1), (0, 2), (2, 2)] {
+        isolated.open(x, y);
+    }
+    assert!(isolated.percolates());
+    assert!(!isolated.is_full(2, 2));
+    assert!(!isolated.is_full(1, 1));
+
+    let mut bonds = BondPercolation::new(2, 3);
+    bonds.open((0, 0), (0, 1));
+    assert!(bonds.is_full(0, 1));
+    assert!(!bonds.percolates());
+    bonds.open((0, 1), (1, 1));
+    bonds.open((1, 2), (1, 1));
+    assert!(bonds.percolates());
+}
+
+#[test]
+pub fn single_linkage_clusters() {
+    let points = [0.0, 10.0, 0.5, 1.2, 10.9, 20.0, 11.8];
+    let dist = |a: &f64, b: &f64| (a - b).abs();
+
+    assert_eq!(
+        single_linkage(&points, dist, 1.0),
+        vec![vec![0, 2, 3], vec![1, 4, 6], vec![5]]
+    );
+    assert_eq!(single_linkage(&points, dist, 0.1).len(), points.len());
+    assert_eq!(single_linkage(&points, dist, 100.0).len(), 1);
+}
+
+#[test]
+pub fn tarjan_offline_lca() {
+    //        0
+    //      /   \
+    //     1     2
+    //    / \     \
+    //   3   4     5
+    //       |
+    //       6
+    let edges = [(0, 1), (0, 2), (1, 3), (1, 4), (2, 5), (4, 6)];
+    let queries = [(3, 6), (6, 5), (4, 4), (1, 6), (3, 0), (5, 7)];
+
+    assert_eq!(
+        offline_lca(0, edges, &queries),
+        vec![Some(1), Some(0), Some(4), Some(1), Some(0), None]
+    );
+}
+
+#[test]
+#[should_panic(expected = "every vertex has at most one parent")]
+pub fn offline_lca_of_a_graph_that_is_not_a_tree() {
+    offline_lca(0, [(0, 1), (0, 2), (1, 3), (2, 3)], &[(1, 2)]);
+}
+
+#[test]
+pub fn interval_union_find() {
+    use crate::interval::IntervalUnionFind;
+
+    let mut slots = IntervalUnionFind::new(6);
+    assert!(slots.occupy(2));
+    assert!(!slots.occupy(2));
+    slots.occupy(3);
+    slots.occupy(5);
+    assert_eq!(slots.next_free_at_or_after(2), Some(4));
+    assert_eq!(slots.next_free_at_or_after(1), Some(1));
+    assert_eq!(slots.occupy_next_free(2), Some(4));
+    assert_eq!(slots.next_free_at_or_after(2), None);
+    assert_eq!(slots.next_free_at_or_after(6), None);
+    assert!(slots.is_free(0));
+    assert!(!slots.is_free(4));
+    assert_eq!(slots.num_free(), 2);
+}
+
+#[test]
+pub fn offline_dynamic_connectivity() {
+    use crate::offline::{answer_dynamic_connectivity_queries, DynamicEvent::*};
+
+    let events = vec![
+        Connected(0, 1),
+        Insert(0, 1),
+        Insert(1, 2),
+        Connected(0, 2),
+        Delete(2, 1),
+        Connected(0, 2),
+        Connected(0, 1),
+        Insert(0, 1),
+        Delete(1, 0),
+        Connected(1, 0),
+        Delete(0, 1),
+        Connected(0, 1),
+        Connected(3, 3),
+        Delete(5, 6),
+    ];
+
+    assert_eq!(
+        answer_dynamic_connectivity_queries(events),
+        vec![false, true, false, true, true, false, true]
+    );
+    assert!(answer_dynamic_connectivity_queries::<u32>([Insert(0, 1)]).is_empty());
+
+    // an emptied list for one orientation doesn't hide an insertion in the other
+    let events = [Insert(0, 1), Delete(0, 1), Insert(1, 0), Delete(0, 1), Connected(0, 1)];
+    assert_eq!(answer_dynamic_connectivity_queries(events), vec![false]);
+}
+
+#[test]
+pub fn remove_elements() {
+    let mut uf = HashUnionFindBySize::<u32>::new(0..6).unwrap();
+    uf.union_by_size(&0, &1).unwrap();
+    uf.union_by_size(&2, &1).unwrap();
+    uf.union_by_size(&3, &4).unwrap();
+    let root = uf.find(&0).unwrap();
+    uf.watch(&2);
+
+    // removing the representative keeps the rest of the class together
+    assert!(uf.remove(&root));
+    assert!(!uf.remove(&root));
+    let remaining: Vec<u32> = [0, 1, 2].into_iter().filter(|&e| e != root).collect();
+    assert!(uf.connected(&remaining[0], &remaining[1]).unwrap());
+    assert_eq!(uf.set_size(&remaining[0]), Some(2));
+    assert_eq!(uf.connected_pairs(), 2);
+    if root != 2 {
+        let new_root = uf.find(&2).unwrap();
+        let changes: Vec<_> = uf.drain_representative_changes().collect();
+        assert_eq!(changes.len(), 1);
+        assert_eq!((changes[0].old, changes[0].new), (root, new_root));
+    }
+
+    assert!(uf.remove(&5));
+    assert!(uf.remove(&4));
+    assert_eq!(uf.set_size(&3), Some(1));
+    assert_eq!(uf.num_sets(), 2);
+    assert_eq!(uf.len(), 3);
+    assert_eq!(uf.validate(), Ok(()));
+
+    let mut members = UnionFind::<u32, (), Members<u32>>::new(0..4).unwrap();
+    members.union_by(&0, &1, infallible(|a, _| a)).unwrap();
+    members.union_by(&1, &2, infallible(|a, _| a)).unwrap();
+    members.remove(&1);
+    let mut class: Vec<u32> = members.class_members(&0).unwrap().copied().collect();
+    class.sort();
+    assert_eq!(class, vec![0, 2]);
+}
+
+#[test]
+pub fn retain_elements() {
+    let mut uf = HashUnionFindByRank::<u32>::new(0..10).unwrap();
+    for (a, b) in [(0, 2), (2, 4), (4, 6), (1, 3), (5, 7)] {
+        uf.union_by_rank(&a, &b).unwrap();
+    }
+    let mut calls = 0;
+    uf.retain(|elem| {
+        calls += 1;
+        elem % 2 == 0 || *elem == 5
+    });
+
+    assert_eq!(calls, 10);
+    assert_eq!(uf.len(), 6);
+    assert_eq!(uf.num_sets(), 3);
+    assert!(uf.connected(&0, &6).unwrap());
+    assert_eq!(uf.find(&5), Some(5));
+    assert_eq!(uf.find(&3), None);
+    assert_eq!(uf.validate(), Ok(()));
+
+    let mut values: UnionFind<u32, u32, Values<u32, u32>> = UnionFind::new(0..4).unwrap();
+    *values.value_mut(&1).unwrap() = 7;
+    values
+        .union_values(&0, &1, |a, b| Ok::<_, Infallible>(a + b))
+        .unwrap();
+    values.retain(|elem| *elem == 0);
+    assert_eq!(values.value(&0), Some(&7));
+}
+
+#[test]
+pub fn absorb_union_finds() {
+    let mut uf = HashUnionFindByRank::<u32>::from_pairs([(0, 1), (2, 3)]);
+    let other = HashUnionFindByRank::<u32>::from_pairs([(1, 2), (4, 5), (6, 6)]);
+    assert_eq!(uf.absorb(other), Ok(2));
+
+    assert_eq!(uf.len(), 7);
+    assert_eq!(uf.num_sets(), 3);
+    assert!(uf.connected(&0, &3).unwrap());
+    assert!(uf.connected(&4, &5).unwrap());
+    assert!(!uf.connected(&3, &6).unwrap());
+    assert_eq!(uf.validate(), Ok(()));
+
+    let mut sizes = HashUnionFindBySize::<u32>::new(0..3).unwrap();
+    sizes.union_by_size(&0, &1).unwrap();
+    let mut other = HashUnionFindBySize::<u32>::new(2..6).unwrap();
+    other.union_by_size(&2, &4).unwrap();
+    other.union_by_size(&3, &5).unwrap();
+    assert_eq!(sizes.absorb_by(other, infallible(|a, _| a)).unwrap(), 2);
+    assert_eq!(sizes.set_size(&4), Some(2));
+    assert_eq!(sizes.set_size(&5), Some(2));
+    assert_eq!(sizes.connected_pairs(), 3);
+}
+
+#[test]
+pub fn restrict_to_keys() {
+    let mut uf = HashUnionFindBySize::<u32>::new(0..8).unwrap();
+    for (a, b) in [(0, 1), (1, 2), (3, 4), (5, 6)] {
+        uf.union_by_size(&a, &b).unwrap();
+    }
+    let root = uf.find(&0).unwrap();
+    let restricted = uf.restrict([0, 2, 4, 5, 7, 42]);
+
+    assert_eq!(restricted.len(), 5);
+    assert_eq!(restricted.num_sets(), 4);
+    assert!(restricted.connected(&0, &2).unwrap());
+    assert!(!restricted.connected(&4, &5).unwrap());
+    assert_eq!(restricted.find(&1), None);
+    assert_eq!(restricted.set_size(&0), Some(2));
+    assert_eq!(restricted.validate(), Ok(()));
+    // a representative among the keys stays the representative
+    if root != 1 {
+        assert_eq!(restricted.find(&2), Some(root));
+    }
+    assert_eq!(uf.len(), 8);
+}
+
+#[test]
+pub fn map_keys_relabels_elements() {
+    let names = ["a", "b", "c", "d"];
+    let mut uf = HashUnionFindByRank::<u32>::new(0..4).unwrap();
+    uf.union_by_rank(&0, &2).unwrap();
+    uf.union_by_rank(&2, &3).unwrap();
+
+    let mut named: HashUnionFindByRank<String> = uf.map_keys(|id| names[id as usize].to_string());
+    assert_eq!(named.len(), 4);
+    assert_eq!(named.num_sets(), 2);
+    assert!(named.connected("a", "d").unwrap());
+    assert!(!named.connected("a", "b").unwrap());
+    assert_eq!(named.validate(), Ok(()));
+    named.union_by_rank("a", "b").unwrap();
+    assert_eq!(named.num_sets(), 1);
+}
+
+#[test]
+pub fn canonicalize_representatives() {
+    let mut uf1 = HashUnionFindByRank::<u32>::new(0..8).unwrap();
+    let mut uf2 = HashUnionFindByRank::<u32>::new(0..8).unwrap();
+    for (a, b) in [(5, 3), (3, 1), (7, 6), (0, 4)] {
+        uf1.union_by_rank(&a, &b).unwrap();
+    }
+    for (a, b) in [(6, 7), (4, 0), (1, 3), (1, 5)] {
+        uf2.union_by_rank(&a, &b).unwrap();
+    }
+    uf1.watch(&5);
+    let before = uf1[&5];
+    uf1.canonicalize();
+    uf2.canonicalize();
+
+    assert_eq!(uf1.raw(), uf2.raw());
+    assert_eq!(uf1[&5], 1);
+    assert_eq!(uf1.raw()[&6], 6);
+    assert_eq!(uf1.num_sets(), 4);
+    assert_eq!(uf1.validate(), Ok(()));
+    let changes: Vec<_> = uf1.drain_representative_changes().collect();
+    assert_eq!(changes.len(), usize::from(before != 1));
+    assert!(changes.iter().all(|change| change.old == before && change.new == 1));
+
+    let mut pinned = HashUnionFindByRank::<u32>::new(0..3).unwrap();
+    pinned.union_by_rank(&0, &2).unwrap();
+    let root = pinned.pin(&0).unwrap();
+    pinned.canonicalize();
+    assert_eq!(pinned.find(&0), Some(root));
+}
+
+#[test]
+pub fn set_representative() {
+    let mut uf = HashUnionFindBySize::<u32>::new(0..6).unwrap();
+    for (a, b) in [(0, 1), (2, 3), (1, 3), (4, 5)] {
+        uf.union_by_size(&a, &b).unwrap();
+    }
+    uf.watch(&2);
+    let old = uf.find(&0).unwrap();
+    assert_eq!(uf.set_representative(&0), Ok(old));
+
+    assert_eq!(uf.find(&3), Some(0));
+    assert_eq!(uf.set_size(&3), Some(4));
+    assert_eq!(uf.num_sets(), 2);
+    assert_eq!(uf.validate(), Ok(()));
+    assert_eq!(uf.drain_representative_changes().count(), usize::from(old != 0));
+    assert_eq!(uf.set_representative(&0), Ok(0));
+    assert_eq!(uf.set_representative(&9), Err(SetRepresentativeError::NotFound));
+
+    let mut ranked = HashUnionFindByRank::<u32>::new(0..4).unwrap();
+    ranked.union_by_rank(&0, &1).unwrap();
+    let root = ranked.pin(&0).unwrap();
+    let other = if root == 0 { 1 } else { 0 };
+    assert_eq!(
+        ranked.set_representative(&other),
+        Err(SetRepresentativeError::Pinned)
+    );
+    ranked.unpin(&root);
+    assert_eq!(ranked.set_representative(&other), Ok(root));
+    assert!(ranked.extra.rank(&other).unwrap() > ranked.extra.rank(&root).unwrap());
+}
+
+#[test]
+pub fn union_by_min_and_max() {
+    let mut uf = HashUnionFindBySize::<u32>::new(0..6).unwrap();
+    for (a, b) in [(4, 2), (5, 3), (2, 5)] {
+        uf.union_by_min(&a, &b).unwrap();
+    }
+    assert_eq!(uf.find(&5), Some(2));
+    assert_eq!(uf.set_size(&4), Some(4));
+
+    let mut uf = UnionFind::<u32, (), ()>::new(0..6).unwrap();
+    uf.union_by_max(&1, &0).unwrap();
+    uf.union_by(&3, &1, ByMax).unwrap();
+    uf.union_by(&4, &5, ByMin).unwrap();
+    assert_eq!(uf.find(&0), Some(3));
+    assert_eq!(uf.find(&5), Some(4));
+}
+
+#[test]
+pub fn union_returns_root() {
+    let mut uf = HashUnionFindByRank::<u32>::new(0..4).unwrap();
+    let union = uf.union_by_rank_get_root(&0, &1).unwrap();
+    assert_eq!(union.status, UnionStatus::PerformedUnion);
+    assert_eq!(uf.find(&0), Some(union.root));
+    let union = uf.union_by_rank_get_root(&2, &1).unwrap();
+    assert_eq!(uf.find(&2), Some(union.root));
+    assert_eq!(
+        uf.union_by_rank_get_root(&0, &2),
+        Ok(RootedUnion {
+            status: UnionStatus::AlreadyEquivalent,
+            root: union.root,
+        })
+    );
+
+    let mut uf = UnionFind::<u32, (), ()>::new(0..4).unwrap();
+    let union = uf.union_by_get_root(&3, &1, ByMin).unwrap();
+    assert_eq!(union.root, 1);
+    uf.pin(&2);
+    assert_eq!(uf.union_by_get_root(&1, &2, ByMin).unwrap().root, 2);
+}
+
+#[test]
+pub fn union_or_add_missing_elements() {
+    let mut uf = HashUnionFindByRank::<u32>::empty();
+    assert_eq!(uf.union_by_rank_or_add(&1, &2).unwrap(), UnionStatus::PerformedUnion);
+    assert_eq!(uf.union_by_rank_or_add(&2, &1).unwrap(), UnionStatus::AlreadyEquivalent);
+    uf.union_by_rank_or_add(&3, &3).unwrap();
+    assert_eq!(uf.len(), 3);
+    assert_eq!(uf.num_sets(), 2);
+
+    let mut uf = HashUnionFindBySize::<u32>::new([0]).unwrap();
+    uf.union_or_add(&0, &5, ByMin).unwrap();
+    uf.union_or_add(&6, &5, ByMin).unwrap();
+    assert_eq!(uf.find(&6), Some(0));
+    assert_eq!(uf.set_size(&6), Some(3));
+    assert_eq!(uf.len(), 3);
+}
+
+#[test]
+pub fn find_shorten_or_add() {
+    let mut uf = HashUnionFindByRank::<u32>::new(0..3).unwrap();
+    uf.union_by_rank(&0, &1).unwrap();
+    uf.union_by_rank(&1, &2).unwrap();
+    let root = uf.find(&2).unwrap();
+    assert_eq!(uf.find_shorten_or_add(&2), Ok(root));
+    assert_eq!(uf.raw()[&2], root);
+    assert_eq!(uf.find_shorten_or_add(&7), Ok(7));
+    assert_eq!(uf.len(), 4);
+    assert_eq!(uf.num_sets(), 2);
+}
+
+#[test]
+pub fn union_all_pairs() {
+    let mut uf = HashUnionFindByRank::<u32>::new(0..6).unwrap();
+    assert_eq!(uf.union_all_by_rank([(0, 1), (1, 2), (2, 0), (3, 4)]), Ok(3));
+    assert_eq!(uf.num_sets(), 3);
+    assert_eq!(
+        uf.union_all_by_rank([(4, 5), (5, 9)]),
+        Err(BulkError::Item {
+            index: 1,
+            error: UnionByRankError::Elem2NotFound,
+        })
+    );
+    assert!(uf.connected(&3, &5).unwrap());
+
+    let mut uf = HashUnionFindBySize::<u32>::new(0..4).unwrap();
+    assert_eq!(uf.union_all([(3, 2), (1, 0), (2, 1)], ByMin).unwrap(), 3);
+    assert_eq!(uf.find(&3), Some(0));
+}
+
+#[test]
+pub fn from_pairs_by_union() {
+    let aliases = [("bob", "robert"), ("rob", "robert"), ("alice", "ali"), ("eve", "eve")];
+    let uf = HashUnionFindBySize::from_pairs_by(aliases, ByMin).unwrap();
+    assert_eq!(uf.len(), 6);
+    assert_eq!(uf.num_sets(), 3);
+    assert_eq!(uf.find(&"robert"), Some("bob"));
+    assert_eq!(uf.set_size(&"rob"), Some(3));
+    assert_eq!(uf.find(&"alice"), Some("ali"));
+}
+
+#[test]
+pub fn collect_and_extend() {
+    let mut uf: HashUnionFindByRank<u32> = (0..4).chain(2..6).collect();
+    assert_eq!(uf.len(), 6);
+    assert_eq!(uf.num_sets(), 6);
+
+    uf.extend([5, 6, 7]);
+    assert_eq!(uf.len(), 8);
+    uf.extend([(0, 1), (1, 7), (8, 9)]);
+    assert_eq!(uf.len(), 10);
+    assert_eq!(uf.num_sets(), 7);
+    assert!(uf.connected(&0, &7).unwrap());
+    assert!(uf.connected(&8, &9).unwrap());
+
+    let members: UnionFind<u32, (), Members<u32>> = (0..3).filter(|elem| elem % 2 == 0).collect();
+    assert_eq!(members.class_members(&2).unwrap().count(), 1);
+}
+
+#[test]
+pub fn empty_union_find() {
+    let mut uf = HashUnionFindByRank::<String>::empty();
+    assert!(uf.is_empty());
+    assert_eq!(uf.num_sets(), 0);
+    assert_eq!(uf.find_or_add(&"a".to_string()), Ok("a".to_string()));
+    uf.union_by_rank_or_add(&"a".into(), &"b".into()).unwrap();
+    assert_eq!(uf.len(), 2);
+    assert_eq!(uf.num_sets(), 1);
+
+    let mut dense = UnionFind::<usize, (), BySize<usize>, DenseMapping<usize>>::default();
+    dense.add(0).unwrap();
+    dense.add(1).unwrap();
+    dense.union_by_size(&0, &1).unwrap();
+    assert_eq!(dense.set_size(&1), Some(2));
+}
+
+#[test]
+pub fn capacity_management() {
+    let mut uf = HashUnionFindByRank::<u32>::with_capacity(1000);
+    assert!(uf.raw().capacity() >= 1000);
+    uf.extend(0..10);
+    uf.union_by_rank(&0, &9).unwrap();
+
+    uf.shrink_to_fit();
+    assert!(uf.raw().capacity() < 1000);
+    assert_eq!(uf.len(), 10);
+    assert!(uf.connected(&0, &9).unwrap());
+    uf.reserve(500);
+    assert!(uf.raw().capacity() >= 510);
+}
+
+#[test]
+pub fn borrowed_lookups() {
+    let mut uf = HashUnionFindByRank::<String>::empty();
+    for name in ["ada", "grace", "alan"] {
+        uf.add(name.to_string()).unwrap();
+    }
+    uf.union_by_rank("ada", "grace").unwrap();
+    uf.union_by_rank("grace", "alan").unwrap();
+
+    let root = uf.find("alan").unwrap();
+    assert_eq!(uf.find_shorten("alan"), Some(root.clone()));
+    assert_eq!(uf.raw()["alan"], root);
+    assert_eq!(uf.connected("ada", "alan"), Some(true));
+    assert_eq!(uf.find("edsger"), None);
+    assert_eq!(uf.find_shorten("edsger"), None);
+    assert_eq!(uf["ada"], root);
+    assert!(uf.contains("grace"));
+    assert!(!uf.contains("edsger"));
+
+    uf.add("edsger".to_string()).unwrap();
+    uf.union_by("edsger", "ada", ByMin).unwrap();
+    assert_eq!(uf.connected("edsger", "alan"), Some(true));
+    assert!(matches!(
+        uf.union_by_rank("barbara", "ada"),
+        Err(UnionByRankError::Elem1NotFound)
+    ));
+}
+
+#[test]
+pub fn entry_api() {
+    let mut uf = HashUnionFindByRank::<u32>::new([0, 1]).unwrap();
+    assert!(matches!(uf.entry(2), Entry::Vacant(_)));
+
+    let mut entry = uf.entry(2).or_add().unwrap();
+    assert_eq!(entry.root(), &2);
+    entry.union_by_rank(&0).unwrap();
+    assert_eq!(entry.union_by_rank(&1).unwrap(), UnionStatus::PerformedUnion);
+    assert_eq!(entry.union_by_rank(&0).unwrap(), UnionStatus::AlreadyEquivalent);
+    assert_eq!(entry.union_by_rank(&5), Err(UnionByRankError::Elem2NotFound));
+    let root = *entry.root();
+    assert_eq!(uf.find(&1), Some(root));
+
+    match uf.entry(0) {
+        Entry::Occupied(entry) => assert_eq!(entry.root(), &root),
+        Entry::Vacant(_) => panic!("0 is in the union find"),
+    }
+
+    let mut values = UnionFind::<&str, u32, Values<&str, u32>>::new(["a"]).unwrap();
+    let mut entry = values.entry("b").or_add_with(|| 2).unwrap();
+    *entry.value_mut().unwrap() += 1;
+    assert_eq!(entry.value(), Some(&3));
+    entry.into_union_find().union_values(&"a", &"b", |a, b| Ok::<_, ()>(a + b)).unwrap();
+    assert_eq!(values.value(&"a"), Some(&3));
+}
+
+#[test]
+pub fn new_reports_mapping_errors() {
+    type VecRanks = UnionFind<usize, usize, ByRank<usize, Vec<usize>>>;
+
+    assert!(VecUnionFind::new(0..3).is_ok());
+    assert_eq!(
+        VecUnionFind::new([0, 2]).unwrap_err(),
+        NewUnionFindError::Parent(NotInOrder)
+    );
+    assert_eq!(
+        VecRanks::new([1, 0]).unwrap_err(),
+        NewUnionFindError::Extra(NotInOrder)
+    );
+    assert_eq!(ByRank::<usize, Vec<usize>>::new([1]).unwrap_err(), NotInOrder);
+}
+
+#[test]
+pub fn failing_mappings_return_errors() {
+    type VecRanks = UnionFind<usize, usize, ByRank<usize, Vec<usize>>>;
+
+    let mut uf = VecUnionFind::new(0..3).unwrap();
+    assert_eq!(uf.add(5), Err(AddError::Parent(NotInOrder)));
+    assert_eq!(uf.find_or_add(&5), Err(AddError::Parent(NotInOrder)));
+    assert_eq!(uf.find_shorten_or_add(&3), Ok(3));
+    assert!(matches!(
+        uf.union_by_rank_or_add(&0, &7),
+        Err(UnionOrAddError::AddError(AddError::Parent(NotInOrder)))
+    ));
+    assert!(matches!(uf.entry(9).or_add(), Err(AddError::Parent(NotInOrder))));
+    assert_eq!(uf.find(&7), None);
+    assert_eq!(uf.num_sets(), 4);
+
+    // adding an element that is present leaves its class as it was
+    let mut present = HashUnionFindByRank::new(0..3).unwrap();
+    present.union_by_rank(&0, &1).unwrap();
+    let root = present.find(&0).unwrap();
+    let child = if root == 0 { 1 } else { 0 };
+    assert!(matches!(present.add(child), Err(AddError::Parent(AlreadyIn))));
+    assert_eq!(present.find(&child), Some(root));
+    assert_eq!(present.connected(&0, &1), Some(true));
+    assert_eq!(present.num_sets(), 2);
+    let mut present: UnionFind<u32, (), (), BTreeMap<u32, u32>> = UnionFind::new(0..2).unwrap();
+    present.union_by(&0, &1, ByMin).unwrap();
+    assert!(matches!(present.add(1), Err(AddError::Parent(AlreadyIn))));
+    assert_eq!(present.find(&1), Some(0));
+    assert_eq!(present.num_sets(), 1);
+
+    // a failing extra leaves the union find as it was
+    let mut ranks = VecRanks::empty();
+    ranks.add(0).unwrap();
+    assert_eq!(ranks.add(5), Err(AddError::Extra(NotInOrder)));
+    assert_eq!(ranks.find(&5), None);
+    assert_eq!((ranks.len(), ranks.num_sets()), (1, 1));
+    ranks.add(1).unwrap();
+    assert_eq!(ranks.add(5), Err(AddError::Extra(NotInOrder)));
+    assert_eq!(ranks.union_by_rank(&0, &1), Ok(UnionStatus::PerformedUnion));
+
+    type DenseRanks = UnionFind<usize, usize, ByRank<usize, Vec<usize>>, DenseMapping<usize>>;
+    let mut dense = DenseRanks::empty();
+    assert_eq!(dense.add(3), Err(AddError::Extra(NotInOrder)));
+    assert_eq!(dense.find(&3), None);
+    assert_eq!(dense.add(0), Ok(()));
+    assert_eq!(dense.num_sets(), 1);
+
+    assert_eq!(
+        VecRanks::try_new([Ok::<_, Infallible>(1)]).unwrap_err(),
+        NewUnionFindError::Extra(NotInOrder)
+    );
+    assert_eq!(
+        VecRanks::try_new([Ok(0), Err("x")]).unwrap_err(),
+        NewUnionFindError::Elems("x")
+    );
+    assert!(matches!(
+        VecRanks::from_pairs_by([(0, 1), (3, 4)], ByMin),
+        Err(UnionOrAddError::AddError(AddError::Extra(NotInOrder)))
+    ));
+}
+
+#[test]
+pub fn read_api_with_custom_parent_mapping() {
+    type DenseUnionFind = UnionFind<usize, usize, ByRank<usize>, DenseMapping<usize>>;
+    let mut uf = DenseUnionFind::new([1, 3, 5, 7, 9]).unwrap();
+    uf.union_by_rank(&1, &3).unwrap();
+    uf.union_by_rank(&5, &7).unwrap();
+    uf.union_by_rank(&3, &7).unwrap();
+
+    assert_eq!(uf.len(), 5);
+    assert!(!uf.is_empty());
+    assert_eq!(uf.keys().collect::<Vec<_>>(), vec![1, 3, 5, 7, 9]);
+    assert_eq!(uf.raw().len(), 5);
+    assert_eq!(uf.roots().count(), 2);
+    assert!(uf.iter().all(|(elem, root)| uf.find(&elem).as_ref() == Some(root)));
+    assert_eq!(uf.classes()[&uf[&1]].len(), 4);
+    assert_eq!(uf.validate(), Ok(()));
+
+    let restricted = uf.restrict([3, 5, 9, 11]);
+    assert_eq!(restricted.len(), 3);
+    assert_eq!(restricted.connected(&3, &5), Some(true));
+
+    uf.canonicalize();
+    assert!(uf.parents().all(|(elem, parent)| *parent == if elem == 9 { 9 } else { 1 }));
+
+    let mut transaction = uf.begin();
+    transaction.add(11).unwrap();
+    transaction.union_by_rank(&11, &9).unwrap();
+    transaction.abort();
+    assert_eq!(uf.find(&11), None);
+    assert_eq!(uf.num_sets(), 2);
+
+    assert!(uf.remove(&1));
+    assert_eq!(uf.connected(&3, &7), Some(true));
+    uf.retain(|elem| *elem != 9);
+    assert_eq!(uf.len(), 3);
+    assert_eq!(uf.num_sets(), 1);
+
+    uf.reset_to_singletons();
+    let mut classes = uf.into_classes();
+    classes.sort();
+    assert_eq!(classes, vec![vec![3], vec![5], vec![7]]);
+}
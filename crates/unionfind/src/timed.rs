@@ -0,0 +1,169 @@
+//! A union find that remembers when classes were unioned, so connectivity can be queried
+//! at any earlier point in time.
+
+use crate::generic::{UnionError, UnionStatus};
+use std::cmp::Ordering;
+use std::collections::HashMap;
+use std::convert::Infallible;
+use std::hash::Hash;
+
+#[derive(Debug, Clone)]
+struct Node<T> {
+    parent: T,
+    /// The time at which this element was linked below its parent, or `usize::MAX` for
+    /// representatives.
+    time: usize,
+    rank: usize,
+}
+
+/// A semi-persistent union find: only the latest version can be modified, but finds can be
+/// performed in every earlier version.
+///
+/// The time starts at 0, and every call to [`union`](TimedUnionFind::union) advances it by
+/// one, also when the elements were already in the same class. So after `k` unions,
+/// [`find_at(elem, k)`](TimedUnionFind::find_at) finds `elem` as if only the first `k` unions
+/// had been made. Elements added later are present at all times, in a class on their own.
+///
+/// Every link stores the time it was made. Unions are made by rank and no path shortening is
+/// performed, so times increase along every path, and finds take logarithmic time.
+#[derive(Debug, Clone)]
+pub struct TimedUnionFind<T: Hash + Eq> {
+    nodes: HashMap<T, Node<T>>,
+    time: usize,
+    sets: usize,
+}
+
+impl<T: Hash + Eq + Clone> Default for TimedUnionFind<T> {
+    fn default() -> Self {
+        Self::new([])
+    }
+}
+
+impl<T: Hash + Eq + Clone> TimedUnionFind<T> {
+    /// Creates a union find in which every element is in a class on its own.
+    pub fn new(elems: impl IntoIterator<Item = T>) -> Self {
+        let mut uf = Self {
+            nodes: HashMap::new(),
+            time: 0,
+            sets: 0,
+        };
+        for elem in elems {
+            uf.add(elem);
+        }
+        uf
+    }
+
+    pub fn len(&self) -> usize {
+        self.nodes.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.nodes.is_empty()
+    }
+
+    /// The number of classes at the current time.
+    pub fn num_sets(&self) -> usize {
+        self.sets
+    }
+
+    /// The current time, which is the number of unions made so far.
+    pub fn time(&self) -> usize {
+        self.time
+    }
+
+    /// Adds an element in a class on its own. Returns false if it was already present.
+    pub fn add(&mut self, elem: T) -> bool {
+        if self.nodes.contains_key(&elem) {
+            return false;
+        }
+        let node = Node {
+            parent: elem.clone(),
+            time: usize::MAX,
+            rank: 0,
+        };
+        self.nodes.insert(elem, node);
+        self.sets += 1;
+        true
+    }
+
+    /// Find the representative of an element at the current time.
+    pub fn find(&self, elem: &T) -> Option<T> {
+        self.find_at(elem, self.time)
+    }
+
+    /// Find the representative of an element after the first `time` unions.
+    pub fn find_at(&self, elem: &T, time: usize) -> Option<T> {
+        let mut current = self.nodes.get_key_value(elem)?.0;
+        loop {
+            let node = &self.nodes[current];
+            if node.time > time || node.parent == *current {
+                return Some(current.clone());
+            }
+            current = &node.parent;
+        }
+    }
+
+    /// Returns whether two elements were in the same class after the first `time` unions,
+    /// or `None` if either of them is not in the union find.
+    pub fn connected_at(&self, elem1: &T, elem2: &T, time: usize) -> Option<bool> {
+        Some(self.find_at(elem1, time)? == self.find_at(elem2, time)?)
+    }
+
+    /// Returns the earliest time at which two elements were in the same class. Returns
+    /// `None` if they are not in the same class now, or either of them is not in the union
+    /// find.
+    pub fn connected_since(&self, elem1: &T, elem2: &T) -> Option<usize> {
+        let mut current1 = self.nodes.get_key_value(elem1)?.0;
+        let mut current2 = self.nodes.get_key_value(elem2)?.0;
+        let mut since = 0;
+        // Times increase towards the representative, so the path with the earliest link
+        // can always be followed first, until both paths meet.
+        while current1 != current2 {
+            let node1 = &self.nodes[current1];
+            let node2 = &self.nodes[current2];
+            if node1.time <= node2.time {
+                if node1.time == usize::MAX {
+                    return None;
+                }
+                since = node1.time;
+                current1 = &node1.parent;
+            } else {
+                since = node2.time;
+                current2 = &node2.parent;
+            }
+        }
+        Some(since)
+    }
+
+    /// Union the classes of two elements by rank, and advance the time by one.
+    /// If either element is not in the union find, the time is not advanced.
+    pub fn union(&mut self, elem1: &T, elem2: &T) -> Result<UnionStatus, UnionError<Infallible>> {
+        let root1 = self.find(elem1).ok_or(UnionError::Elem1NotFound)?;
+        let root2 = self.find(elem2).ok_or(UnionError::Elem2NotFound)?;
+        self.time += 1;
+        if root1 == root2 {
+            return Ok(UnionStatus::AlreadyEquivalent);
+        }
+
+        let rank1 = self.nodes[&root1].rank;
+        let rank2 = self.nodes[&root2].rank;
+        let (child, root) = match rank1.cmp(&rank2) {
+            Ordering::Less => (root1, root2),
+            Ordering::Equal | Ordering::Greater => (root2, root1),
+        };
+        if rank1 == rank2 {
+            self.nodes
+                .get_mut(&root)
+                .expect("root is in the union find")
+                .rank += 1;
+        }
+        let child = self
+            .nodes
+            .get_mut(&child)
+            .expect("root is in the union find");
+        child.parent = root;
+        child.time = self.time;
+        self.sets -= 1;
+        Ok(UnionStatus::PerformedUnion)
+    }
+}
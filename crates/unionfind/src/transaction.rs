@@ -0,0 +1,189 @@
+use crate::extra::{ByRank, GrowableExtra, MergeExtra, UndoExtra};
+use crate::forest::SpanningForest;
+use crate::generic::{AddErrorSimple, UnionByRankError, UnionError, UnionFind, UnionStatus};
+use crate::mapping::GrowableMapping;
+use crate::union::Union;
+use crate::watch::Watchers;
+use std::collections::HashMap;
+use std::hash::{BuildHasher, Hash};
+use std::mem;
+use std::ops::Deref;
+
+/// A change made during a transaction, with what is needed to undo it.
+enum Undo<T, S> {
+    Add(T),
+    Union {
+        /// The representative that was linked below the other one.
+        child: T,
+        saved: [(T, S); 2],
+    },
+}
+
+/// A batch of additions and unions on a [`UnionFind`], which is either kept with
+/// [`commit`](Transaction::commit), or undone with [`abort`](Transaction::abort). Dropping a
+/// transaction without committing it aborts it. Started with
+/// [`begin`](UnionFind::begin).
+///
+/// Aborting takes time proportional to the number of changes made during the transaction,
+/// instead of the size of the union find. To make this possible, no paths are shortened
+/// during the transaction. The union find can be read through the transaction.
+///
+/// Changes of representatives of [watched](UnionFind::watch) elements, and unions for
+/// [`path_between`](UnionFind::path_between), are only reported when the transaction is
+/// committed.
+pub struct Transaction<'a, T, V, E, M = HashMap<T, T>>
+where
+    T: Hash + Eq + Clone,
+    E: UndoExtra<T>,
+    M: GrowableMapping<T, T>,
+{
+    uf: &'a mut UnionFind<T, V, E, M>,
+    undo: Vec<Undo<T, E::Saved>>,
+    /// The watchers and unions of the union find, set aside until the transaction ends.
+    watchers: Watchers<T>,
+    union_tree: Option<SpanningForest<T, Option<()>>>,
+    /// The elements passed to every union that merged two classes.
+    unions: Vec<(T, T)>,
+}
+
+impl<'a, T: Hash + Eq + Clone, V, E: UndoExtra<T>, M: GrowableMapping<T, T>>
+    Transaction<'a, T, V, E, M>
+{
+    pub(crate) fn new(uf: &'a mut UnionFind<T, V, E, M>) -> Self {
+        let watchers = mem::take(&mut uf.watchers);
+        let union_tree = uf.union_tree.take();
+        Self {
+            uf,
+            undo: Vec::new(),
+            watchers,
+            union_tree,
+            unions: Vec::new(),
+        }
+    }
+
+    /// Keeps all changes made during the transaction.
+    pub fn commit(mut self) {
+        // No paths were shortened, so the parent of every child is the representative it
+        // was linked below.
+        for undo in mem::take(&mut self.undo) {
+            if let Undo::Union { child, .. } = undo {
+                let root = self.uf.parent.get(&child).expect("child is a member");
+                self.watchers.root_changed(&child, root);
+            }
+        }
+        if let Some(tree) = &mut self.union_tree {
+            for (elem1, elem2) in self.unions.drain(..) {
+                tree.add_edge(elem1, elem2, None);
+            }
+        }
+    }
+
+    /// Undoes all changes made during the transaction.
+    pub fn abort(self) {}
+
+    /// Adds an element in a class on its own, see [`UnionFind::add`].
+    pub fn add(&mut self, elem: T) -> Result<(), AddErrorSimple<T, V, M, E>>
+    where
+        E: GrowableExtra<T, V>,
+        V: Default,
+    {
+        self.uf.add(elem.clone())?;
+        self.undo.push(Undo::Add(elem));
+        Ok(())
+    }
+
+    /// See [`UnionFind::union_by`].
+    pub fn union_by<U: Union<T>>(
+        &mut self,
+        elem1: &T,
+        elem2: &T,
+        union: U,
+    ) -> Result<UnionStatus, UnionError<U::Err, E::MergeError>>
+    where
+        E: MergeExtra<T>,
+    {
+        let root1 = self.uf.find(elem1).ok_or(UnionError::Elem1NotFound)?;
+        let root2 = self.uf.find(elem2).ok_or(UnionError::Elem2NotFound)?;
+        let saved = self.save(&root1, &root2);
+        let status = self.uf.union_helper(root1, root2, union)?;
+        self.record(elem1, elem2, status, saved);
+        Ok(status)
+    }
+
+    fn save(&self, root1: &T, root2: &T) -> [(T, E::Saved); 2] {
+        [
+            (root1.clone(), self.uf.extra.save(root1)),
+            (root2.clone(), self.uf.extra.save(root2)),
+        ]
+    }
+
+    fn record(&mut self, elem1: &T, elem2: &T, status: UnionStatus, saved: [(T, E::Saved); 2]) {
+        if status == UnionStatus::AlreadyEquivalent {
+            return;
+        }
+        let [(root1, _), (root2, _)] = &saved;
+        let child = if self.uf.parent.get(root1) == Some(root1) {
+            root2.clone()
+        } else {
+            root1.clone()
+        };
+        self.undo.push(Undo::Union { child, saved });
+        if self.union_tree.is_some() {
+            self.unions.push((elem1.clone(), elem2.clone()));
+        }
+    }
+}
+
+impl<T: Hash + Eq + Clone, V, S: BuildHasher, M: GrowableMapping<T, T>>
+    Transaction<'_, T, V, ByRank<T, HashMap<T, usize, S>>, M>
+{
+    /// See [`UnionFind::union_by_rank`].
+    pub fn union_by_rank(&mut self, elem1: &T, elem2: &T) -> Result<UnionStatus, UnionByRankError> {
+        let root1 = self.uf.find(elem1).ok_or(UnionByRankError::Elem1NotFound)?;
+        let root2 = self.uf.find(elem2).ok_or(UnionByRankError::Elem2NotFound)?;
+        let saved = self.save(&root1, &root2);
+        let status = self.uf.union_by_rank_helper(root1, root2)?;
+        self.record(elem1, elem2, status, saved);
+        Ok(status)
+    }
+}
+
+impl<T: Hash + Eq + Clone, V, E: UndoExtra<T>, M: GrowableMapping<T, T>> Deref
+    for Transaction<'_, T, V, E, M>
+{
+    type Target = UnionFind<T, V, E, M>;
+
+    fn deref(&self) -> &Self::Target {
+        self.uf
+    }
+}
+
+impl<T: Hash + Eq + Clone, V, E: UndoExtra<T>, M: GrowableMapping<T, T>> Drop
+    for Transaction<'_, T, V, E, M>
+{
+    /// Undoes all changes that were not committed, most recent first, and gives the
+    /// watchers and unions back to the union find.
+    fn drop(&mut self) {
+        for undo in self.undo.drain(..).rev() {
+            match undo {
+                Undo::Add(elem) => {
+                    // additions are undone in reverse order, so `elem` was added last
+                    self.uf.parent.undo_add(&elem);
+                    self.uf.extra.remove(&elem);
+                    self.uf.sets -= 1;
+                }
+                Undo::Union { child, saved } => {
+                    for (root, saved) in saved.into_iter().rev() {
+                        self.uf.extra.restore(&root, saved);
+                        if root == child {
+                            self.uf.parent.set(child.clone(), root);
+                        }
+                    }
+                    self.uf.sets += 1;
+                }
+            }
+        }
+        self.uf.watchers = mem::take(&mut self.watchers);
+        self.uf.union_tree = self.union_tree.take();
+    }
+}
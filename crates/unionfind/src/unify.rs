@@ -0,0 +1,185 @@
+//! A unification table with an interface like the one of the `ena` crate, used by rustc, on
+//! top of a [`UnionFind`]. Code written against `ena` can switch to this table by implementing
+//! [`UnifyKey`] and [`UnifyValue`] from this module instead, and additionally gets
+//! serialization of the table.
+//!
+//! Snapshots are not supported, and the order of roots can't be chosen by the key type.
+
+use crate::extra::Values;
+use crate::generic::{UnionError, UnionFind};
+use serde::{Deserialize, Serialize};
+use std::fmt::Debug;
+use std::hash::Hash;
+
+/// A key in a [`UnificationTable`]. Keys are created by the table, and are the integers
+/// `0..len` converted with [`from_index`](UnifyKey::from_index).
+pub trait UnifyKey: Copy + Debug + Hash + Eq {
+    /// The value of every class of keys.
+    type Value: UnifyValue;
+
+    fn index(&self) -> u32;
+
+    fn from_index(index: u32) -> Self;
+
+    /// A name for the kind of key, for debugging.
+    fn tag() -> &'static str;
+}
+
+/// The value of a class in a [`UnificationTable`], which is combined when classes are unified.
+pub trait UnifyValue: Clone + Debug {
+    /// Returned when two values can't be combined. Use [`NoError`] if values always combine.
+    type Error;
+
+    /// Combines the values of two classes that are unified.
+    fn unify_values(value1: &Self, value2: &Self) -> Result<Self, Self::Error>;
+}
+
+/// The error of values that can always be unified.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NoError {}
+
+impl UnifyValue for () {
+    type Error = NoError;
+
+    fn unify_values(_: &(), _: &()) -> Result<(), NoError> {
+        Ok(())
+    }
+}
+
+/// Values that are either unknown, or known and then must be equal to unify.
+impl<V: Clone + Debug + PartialEq> UnifyValue for Option<V> {
+    type Error = (V, V);
+
+    fn unify_values(value1: &Self, value2: &Self) -> Result<Self, (V, V)> {
+        match (value1, value2) {
+            (Some(v1), Some(v2)) if v1 != v2 => Err((v1.clone(), v2.clone())),
+            (Some(v), _) | (_, Some(v)) => Ok(Some(v.clone())),
+            (None, None) => Ok(None),
+        }
+    }
+}
+
+/// Keys with a value per class, which are unified while combining their values.
+///
+/// Unlike the table of `ena`, every method panics on keys that were not created by the table,
+/// instead of only some of them.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(bound(
+    serialize = "K: Serialize, K::Value: Serialize",
+    deserialize = "K: Deserialize<'de>, K::Value: Deserialize<'de>"
+))]
+pub struct UnificationTable<K: UnifyKey> {
+    uf: UnionFind<K, K::Value, Values<K, K::Value>>,
+}
+
+impl<K: UnifyKey> Default for UnificationTable<K> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<K: UnifyKey> UnificationTable<K> {
+    pub fn new() -> Self {
+        Self {
+            uf: UnionFind::empty(),
+        }
+    }
+
+    /// The number of keys created.
+    pub fn len(&self) -> usize {
+        self.uf.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.uf.is_empty()
+    }
+
+    /// Creates a key in a class on its own, with the given value.
+    pub fn new_key(&mut self, value: K::Value) -> K {
+        let index = u32::try_from(self.len()).expect("too many keys for the key type");
+        let key = K::from_index(index);
+        self.uf
+            .add_with_extra(key, value)
+            .expect("keys are created in order");
+        key
+    }
+
+    /// The representative of the class of a key.
+    pub fn find(&mut self, key: K) -> K {
+        self.uf.find_shorten(&key).unwrap_or_else(|| missing(key))
+    }
+
+    /// Returns whether two keys are in the same class.
+    pub fn unioned(&mut self, key1: K, key2: K) -> bool {
+        self.find(key1) == self.find(key2)
+    }
+
+    /// The value of the class of a key.
+    pub fn probe_value(&mut self, key: K) -> K::Value {
+        self.uf
+            .value_mut(&key)
+            .unwrap_or_else(|| missing(key))
+            .clone()
+    }
+
+    /// Unifies the classes of two keys, combining their values with
+    /// [`unify_values`](UnifyValue::unify_values). When the values can't be combined, nothing
+    /// is changed.
+    pub fn unify_var_var(
+        &mut self,
+        key1: K,
+        key2: K,
+    ) -> Result<(), <K::Value as UnifyValue>::Error> {
+        match self.uf.union_values(&key1, &key2, K::Value::unify_values) {
+            Ok(_) => Ok(()),
+            Err(UnionError::ExtraNotMergeable(err)) => Err(err),
+            Err(UnionError::Elem1NotFound) => missing(key1),
+            Err(UnionError::Elem2NotFound) => missing(key2),
+            Err(UnionError::NotUnionable(never)) => match never {},
+            Err(UnionError::BothPinned) => unreachable!("the table pins no keys"),
+        }
+    }
+
+    /// Combines the value of the class of a key with `value`. When the values can't be
+    /// combined, nothing is changed.
+    pub fn unify_var_value(
+        &mut self,
+        key: K,
+        value: K::Value,
+    ) -> Result<(), <K::Value as UnifyValue>::Error> {
+        let current = self.uf.value_mut(&key).unwrap_or_else(|| missing(key));
+        *current = K::Value::unify_values(current, &value)?;
+        Ok(())
+    }
+
+    /// The union find backing the table.
+    pub fn into_inner(self) -> UnionFind<K, K::Value, Values<K, K::Value>> {
+        self.uf
+    }
+}
+
+impl<K: UnifyKey> UnificationTable<K>
+where
+    K::Value: UnifyValue<Error = NoError>,
+{
+    /// Unifies the classes of two keys, for values that can always be combined.
+    pub fn union(&mut self, key1: K, key2: K) {
+        match self.unify_var_var(key1, key2) {
+            Ok(()) => {}
+            Err(never) => match never {},
+        }
+    }
+
+    /// Combines the value of the class of a key with `value`, for values that can always be
+    /// combined.
+    pub fn union_value(&mut self, key: K, value: K::Value) {
+        match self.unify_var_value(key, value) {
+            Ok(()) => {}
+            Err(never) => match never {},
+        }
+    }
+}
+
+fn missing<K: UnifyKey>(key: K) -> ! {
+    panic!("{} key {:?} was not created by this table", K::tag(), key)
+}
@@ -1,18 +1,72 @@
 use std::convert::Infallible;
 
+/// Decides which of two representatives becomes the representative of the unioned class,
+/// see [`union_by`](crate::generic::UnionFind::union_by).
+///
+/// Closures implement this trait, so ad-hoc strategies don't need a type of their own. Pass a
+/// closure by mutable reference to use the same `FnMut` for several unions.
 pub trait Union<T> {
     type Err;
 
     fn union(self, a: T, b: T) -> Result<T, Self::Err>;
 }
 
-impl<F, T> Union<T> for F
+/// Any closure returning the new representative, or an error when the classes can't be
+/// unioned, can be used as a union. Closures that can't fail can be wrapped with [`infallible`].
+impl<F, T, E> Union<T> for F
+where
+    F: FnOnce(T, T) -> Result<T, E>,
+{
+    type Err = E;
+
+    fn union(self, a: T, b: T) -> Result<T, Self::Err> {
+        (self)(a, b)
+    }
+}
+
+/// A union that always succeeds, created with [`infallible`].
+#[derive(Debug, Clone, Copy)]
+pub struct InfallibleUnion<F>(pub F);
+
+impl<F, T> Union<T> for InfallibleUnion<F>
 where
     F: FnOnce(T, T) -> T,
 {
     type Err = Infallible;
 
     fn union(self, a: T, b: T) -> Result<T, Self::Err> {
-        Ok((self)(a, b))
+        Ok((self.0)(a, b))
+    }
+}
+
+/// Turns a closure that returns the new representative into a [`Union`] that never fails,
+/// for example `infallible(|a, _| a)` to always keep the representative of the first class.
+pub fn infallible<F>(f: F) -> InfallibleUnion<F> {
+    InfallibleUnion(f)
+}
+
+/// A union keeping the smallest of the two representatives, so that the representative of
+/// every class is its smallest element when all unions use it.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ByMin;
+
+impl<T: Ord> Union<T> for ByMin {
+    type Err = Infallible;
+
+    fn union(self, a: T, b: T) -> Result<T, Self::Err> {
+        Ok(a.min(b))
+    }
+}
+
+/// A union keeping the largest of the two representatives, so that the representative of
+/// every class is its largest element when all unions use it.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ByMax;
+
+impl<T: Ord> Union<T> for ByMax {
+    type Err = Infallible;
+
+    fn union(self, a: T, b: T) -> Result<T, Self::Err> {
+        Ok(a.max(b))
     }
 }
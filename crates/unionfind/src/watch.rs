@@ -0,0 +1,101 @@
+use std::collections::HashMap;
+use std::hash::Hash;
+use std::vec::Drain;
+
+/// Reported when the representative of a watched element changes, because its
+/// class was merged into another class.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RepresentativeChange<T> {
+    /// The watched element.
+    pub elem: T,
+    /// The representative of `elem` before the union.
+    pub old: T,
+    /// The representative of `elem` after the union.
+    pub new: T,
+}
+
+/// Keeps track of watched elements in a [`UnionFind`](crate::generic::UnionFind),
+/// grouped by their representative.
+#[derive(Debug, Clone)]
+pub(crate) struct Watchers<T> {
+    /// For every representative, the watched elements in its class.
+    by_root: HashMap<T, Vec<T>>,
+    changes: Vec<RepresentativeChange<T>>,
+}
+
+impl<T> Default for Watchers<T> {
+    fn default() -> Self {
+        Self {
+            by_root: HashMap::new(),
+            changes: Vec::new(),
+        }
+    }
+}
+
+impl<T: Hash + Eq + Clone> Watchers<T> {
+    pub(crate) fn watch(&mut self, elem: T, root: T) {
+        let watched = self.by_root.entry(root).or_default();
+        if !watched.contains(&elem) {
+            watched.push(elem);
+        }
+    }
+
+    /// Returns whether `elem` was watched.
+    pub(crate) fn unwatch(&mut self, elem: &T, root: &T) -> bool {
+        let Some(watched) = self.by_root.get_mut(root) else {
+            return false;
+        };
+        let len = watched.len();
+        watched.retain(|i| i != elem);
+        let removed = watched.len() != len;
+
+        if watched.is_empty() {
+            self.by_root.remove(root);
+        }
+        removed
+    }
+
+    /// Must be called whenever `old_root` stops being a representative because
+    /// its class was merged into the class of `new_root`.
+    pub(crate) fn root_changed(&mut self, old_root: &T, new_root: &T) {
+        // fast path: usually nothing is watched
+        if self.by_root.is_empty() {
+            return;
+        }
+        let Some(watched) = self.by_root.remove(old_root) else {
+            return;
+        };
+
+        self.changes
+            .extend(watched.iter().map(|elem| RepresentativeChange {
+                elem: elem.clone(),
+                old: old_root.clone(),
+                new: new_root.clone(),
+            }));
+        self.by_root
+            .entry(new_root.clone())
+            .or_default()
+            .extend(watched);
+    }
+
+    /// Must be called when every element becomes its own representative again.
+    pub(crate) fn reset(&mut self) {
+        let by_root = std::mem::take(&mut self.by_root);
+        for (root, watched) in by_root {
+            for elem in watched {
+                if elem != root {
+                    self.changes.push(RepresentativeChange {
+                        elem: elem.clone(),
+                        old: root.clone(),
+                        new: elem.clone(),
+                    });
+                }
+                self.by_root.entry(elem.clone()).or_default().push(elem);
+            }
+        }
+    }
+
+    pub(crate) fn drain_changes(&mut self) -> Drain<'_, RepresentativeChange<T>> {
+        self.changes.drain(..)
+    }
+}
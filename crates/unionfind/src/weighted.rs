@@ -0,0 +1,274 @@
+use crate::generic::UnionStatus;
+use std::cmp::Ordering;
+use std::collections::HashMap;
+use std::hash::Hash;
+use std::ops::{Add, Sub};
+use thiserror::Error;
+
+/// The weights of a [`WeightedUnionFind`]. Weights don't have to commute, so besides numbers
+/// under addition, they can for example be permutations or affine maps under composition.
+///
+/// Implementations must satisfy the group laws: `op` is associative, `identity` is neutral
+/// for `op`, and `x.op(&x.inverse())` is the identity. Floating point numbers only satisfy
+/// these approximately, so contradictions may be reported due to rounding errors.
+pub trait Group: Clone + PartialEq {
+    fn identity() -> Self;
+
+    fn op(&self, other: &Self) -> Self;
+
+    fn inverse(&self) -> Self;
+}
+
+/// Numbers form a group under addition.
+macro_rules! impl_additive_group {
+    ($($ty:ty),*) => {
+        $(
+            impl Group for $ty {
+                fn identity() -> Self {
+                    0 as $ty
+                }
+
+                fn op(&self, other: &Self) -> Self {
+                    self + other
+                }
+
+                fn inverse(&self) -> Self {
+                    -self
+                }
+            }
+        )*
+    };
+}
+
+impl_additive_group!(i8, i16, i32, i64, i128, isize, f32, f64);
+
+/// A union find where every element additionally stores a weight relative to the
+/// representative of its class. Unions assert a difference between the weights of two
+/// elements, and contradicting unions are rejected.
+///
+/// This answers questions like "how much heavier is `a` than `b`", given facts like
+/// "`a` is 3 heavier than `c`" and "`b` is 2 lighter than `c`".
+///
+/// Weights come from any [`Group`]. The difference between `a` and `b` is
+/// `weight(a).op(weight(b).inverse())`, which for numbers is `weight(a) - weight(b)`.
+#[derive(Debug, Clone)]
+pub struct WeightedUnionFind<T: Hash + Eq, W> {
+    /// A mapping from every key to its parent key, like in [`UnionFind`](crate::generic::UnionFind).
+    parent: HashMap<T, T>,
+    /// For every key, the difference between the key and its parent.
+    offset: HashMap<T, W>,
+    rank: HashMap<T, usize>,
+}
+
+#[derive(Debug, Error, PartialEq)]
+pub enum UnionWithDiffError<W> {
+    #[error("the first element given as an argument to union was not found in the union find")]
+    Elem1NotFound,
+
+    #[error("the second element given as an argument to union was not found in the union find")]
+    Elem2NotFound,
+
+    /// The two elements were already related, by a different difference.
+    #[error("the elements are already related by a different difference")]
+    Contradiction { existing: W },
+}
+
+impl<T: Hash + Eq, W> Default for WeightedUnionFind<T, W> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T: Hash + Eq, W> WeightedUnionFind<T, W> {
+    /// Creates an empty union find.
+    pub fn new() -> Self {
+        Self {
+            parent: HashMap::new(),
+            offset: HashMap::new(),
+            rank: HashMap::new(),
+        }
+    }
+
+    pub fn contains(&self, elem: &T) -> bool {
+        self.parent.contains_key(elem)
+    }
+
+    /// Iterates over all elements in the union find, in arbitrary order.
+    pub fn keys(&self) -> impl Iterator<Item = &T> {
+        self.parent.keys()
+    }
+
+    pub fn len(&self) -> usize {
+        self.parent.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.parent.is_empty()
+    }
+}
+
+impl<T, W> WeightedUnionFind<T, W>
+where
+    T: Hash + Eq + Clone,
+    W: Group,
+{
+    /// Adds an element as a class on its own with weight zero. Returns false
+    /// if the element was already present.
+    pub fn add(&mut self, elem: T) -> bool {
+        if self.contains(&elem) {
+            return false;
+        }
+
+        self.parent.insert(elem.clone(), elem.clone());
+        self.offset.insert(elem.clone(), W::identity());
+        self.rank.insert(elem, 0);
+        true
+    }
+
+    /// Finds the representative of an element together with `weight(elem) - weight(root)`.
+    /// Performs no path shortening.
+    pub fn find(&self, elem: &T) -> Option<(T, W)> {
+        let parent = self.parent.get(elem)?;
+        let offset = self.offset.get(elem)?;
+        if parent == elem {
+            Some((parent.clone(), offset.clone()))
+        } else {
+            let (root, parent_offset) = self.find(parent)?;
+            Some((root, offset.op(&parent_offset)))
+        }
+    }
+
+    /// Like [`find`](WeightedUnionFind::find), but performs path shortening.
+    pub fn find_shorten(&mut self, elem: &T) -> Option<(T, W)> {
+        let parent = self.parent.get(elem)?.clone();
+        let offset = self.offset.get(elem)?.clone();
+        if &parent == elem {
+            Some((parent, offset))
+        } else {
+            let (root, parent_offset) = self.find_shorten(&parent)?;
+            let new_offset = offset.op(&parent_offset);
+            // path shortening
+            self.parent.insert(elem.clone(), root.clone());
+            self.offset.insert(elem.clone(), new_offset.clone());
+            Some((root, new_offset))
+        }
+    }
+
+    /// Returns `weight(elem1) - weight(elem2)` if both elements are in the same class.
+    pub fn diff(&mut self, elem1: &T, elem2: &T) -> Option<W> {
+        let (root1, weight1) = self.find_shorten(elem1)?;
+        let (root2, weight2) = self.find_shorten(elem2)?;
+        (root1 == root2).then(|| weight1.op(&weight2.inverse()))
+    }
+
+    /// Unions the classes of two elements, asserting that `weight(elem1) - weight(elem2) == diff`.
+    pub fn union_with_diff(
+        &mut self,
+        elem1: &T,
+        elem2: &T,
+        diff: W,
+    ) -> Result<UnionStatus, UnionWithDiffError<W>> {
+        let (root1, weight1) = self
+            .find_shorten(elem1)
+            .ok_or(UnionWithDiffError::Elem1NotFound)?;
+        let (root2, weight2) = self
+            .find_shorten(elem2)
+            .ok_or(UnionWithDiffError::Elem2NotFound)?;
+
+        if root1 == root2 {
+            let existing = weight1.op(&weight2.inverse());
+            return if existing == diff {
+                Ok(UnionStatus::AlreadyEquivalent)
+            } else {
+                Err(UnionWithDiffError::Contradiction { existing })
+            };
+        }
+
+        // weight(root1) - weight(root2), where weight(elem1) = weight1 + weight(root1)
+        let root_diff = weight1.inverse().op(&diff).op(&weight2);
+        let rank1 = self.rank[&root1];
+        let rank2 = self.rank[&root2];
+
+        match rank1.cmp(&rank2) {
+            Ordering::Less => self.link(root1, root2, root_diff),
+            Ordering::Equal => {
+                self.rank.insert(root2.clone(), rank2 + 1);
+                self.link(root1, root2, root_diff);
+            }
+            Ordering::Greater => self.link(root2, root1, root_diff.inverse()),
+        }
+
+        Ok(UnionStatus::PerformedUnion)
+    }
+
+    /// Makes `root` point to `new_root`, where `offset == weight(root) - weight(new_root)`.
+    fn link(&mut self, root: T, new_root: T, offset: W) {
+        self.parent.insert(root.clone(), new_root);
+        self.offset.insert(root, offset);
+    }
+}
+
+/// Weights modulo two. Used to track whether two elements are on the same side of a bipartition.
+/// `Parity(true)` means the elements are on different sides.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Parity(pub bool);
+
+impl Add for Parity {
+    type Output = Parity;
+
+    fn add(self, rhs: Self) -> Self::Output {
+        Parity(self.0 != rhs.0)
+    }
+}
+
+impl Sub for Parity {
+    type Output = Parity;
+
+    fn sub(self, rhs: Self) -> Self::Output {
+        Parity(self.0 != rhs.0)
+    }
+}
+
+/// A [`WeightedUnionFind`] over [`Parity`] splits every class into two sides, for example
+/// for 2-coloring a graph, or for "friends and enemies" constraints. A union that puts two
+/// elements on a side that contradicts earlier unions means that the constraints contain
+/// an odd cycle.
+impl<T: Hash + Eq + Clone> WeightedUnionFind<T, Parity> {
+    /// Unions the classes of two elements, asserting that they are on the same side.
+    pub fn union_same(
+        &mut self,
+        elem1: &T,
+        elem2: &T,
+    ) -> Result<UnionStatus, UnionWithDiffError<Parity>> {
+        self.union_with_diff(elem1, elem2, Parity(false))
+    }
+
+    /// Unions the classes of two elements, asserting that they are on different sides.
+    pub fn union_different(
+        &mut self,
+        elem1: &T,
+        elem2: &T,
+    ) -> Result<UnionStatus, UnionWithDiffError<Parity>> {
+        self.union_with_diff(elem1, elem2, Parity(true))
+    }
+
+    /// Returns whether two elements are on the same side, or `None` when they are not in
+    /// the same class.
+    pub fn same_side(&mut self, elem1: &T, elem2: &T) -> Option<bool> {
+        self.diff(elem1, elem2).map(|Parity(different)| !different)
+    }
+}
+
+impl Group for Parity {
+    fn identity() -> Self {
+        Parity(false)
+    }
+
+    fn op(&self, other: &Self) -> Self {
+        *self + *other
+    }
+
+    fn inverse(&self) -> Self {
+        *self
+    }
+}
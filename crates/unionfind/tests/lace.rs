@@ -0,0 +1,74 @@
+use std::io::Write;
+use std::process::{Command, Output, Stdio};
+
+fn lace(args: &[&str], input: &str) -> Output {
+    let mut child = Command::new(env!("CARGO_BIN_EXE_lace"))
+        .args(args)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .unwrap();
+    child
+        .stdin
+        .take()
+        .unwrap()
+        .write_all(input.as_bytes())
+        .unwrap();
+    child.wait_with_output().unwrap()
+}
+
+fn lines(output: &Output) -> Vec<String> {
+    let mut lines: Vec<String> = String::from_utf8(output.stdout.clone())
+        .unwrap()
+        .lines()
+        .map(str::to_string)
+        .collect();
+    lines.sort();
+    lines
+}
+
+#[test]
+pub fn components() {
+    let output = lace(&[], "a b friends\nb,c\n# a comment\nd e colleagues\n");
+    assert!(output.status.success());
+
+    let labels: Vec<(String, String)> = lines(&output)
+        .into_iter()
+        .map(|line| {
+            let (vertex, component) = line.split_once(',').unwrap();
+            (vertex.to_string(), component.to_string())
+        })
+        .collect();
+    let vertices: Vec<&str> = labels.iter().map(|(vertex, _)| vertex.as_str()).collect();
+    assert_eq!(vertices, ["a", "b", "c", "d", "e"]);
+    assert_eq!(labels[0].1, labels[1].1);
+    assert_eq!(labels[0].1, labels[2].1);
+    assert_eq!(labels[3].1, labels[4].1);
+    assert_ne!(labels[0].1, labels[3].1);
+}
+
+#[test]
+pub fn components_as_json() {
+    let output = lace(&["--format", "json", "--delimiter", ";"], "a;b\nc;c\n");
+    assert!(output.status.success());
+
+    let labels: serde_json::Map<String, serde_json::Value> =
+        serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(labels.len(), 3);
+    assert_eq!(labels["a"], labels["b"]);
+    assert_eq!(labels["c"], "c");
+}
+
+#[test]
+pub fn kruskal() {
+    let output = lace(&["--kruskal"], "a b 1\nb c 2.5\na c 3\nd e 0\n");
+    assert!(output.status.success());
+    assert_eq!(lines(&output), ["a,b,1", "b,c,2.5", "d,e,0"]);
+
+    let output = lace(&["--kruskal"], "a b 1\nb c\n");
+    assert!(!output.status.success());
+    let output = lace(&["--kruskal"], "a b friends\n");
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("friends"));
+}